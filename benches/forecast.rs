@@ -0,0 +1,34 @@
+use chrono::Duration;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use weather_util_rust::weather_forecast::WeatherForecast;
+
+fn get_forecast(repeat: i64) -> WeatherForecast {
+    let mut forecast: WeatherForecast =
+        serde_json::from_str(include_str!("../tests/forecast.json")).unwrap();
+    let entries = forecast.list.clone();
+    for days in 1..repeat {
+        forecast
+            .list
+            .extend(entries.iter().cloned().map(|mut entry| {
+                entry.dt += Duration::days(5 * days);
+                entry
+            }));
+    }
+    forecast
+}
+
+fn bench_get_high_low(c: &mut Criterion) {
+    let forecast = get_forecast(1);
+    c.bench_function("get_high_low", |b| {
+        b.iter(|| black_box(&forecast).get_high_low())
+    });
+
+    let forecast = get_forecast(100);
+    c.bench_function("get_high_low_large", |b| {
+        b.iter(|| black_box(&forecast).get_high_low())
+    });
+}
+
+criterion_group!(benches, bench_get_high_low);
+criterion_main!(benches);
@@ -0,0 +1,142 @@
+use chrono::Duration;
+
+use crate::{weather_data::WeatherData, weather_forecast::WeatherForecast};
+
+/// Clothing suggestion for each band of "feels like" temperature in Celcius,
+/// the first band whose upper bound is above the temperature applies.
+///
+/// | Feels like    | Suggestion                      |
+/// |---------------|---------------------------------|
+/// | below -10 C   | heavy coat, hat and gloves      |
+/// | -10 C to 0 C  | winter coat and gloves          |
+/// | 0 C to 10 C   | warm jacket                     |
+/// | 10 C to 18 C  | light jacket                    |
+/// | 18 C to 25 C  | long sleeves                    |
+/// | above 25 C    | t-shirt                         |
+const TEMPERATURE_BANDS: [(f64, &str); 6] = [
+    (-10.0, "Heavy coat, hat and gloves recommended"),
+    (0.0, "Winter coat and gloves recommended"),
+    (10.0, "Warm jacket recommended"),
+    (18.0, "Light jacket recommended"),
+    (25.0, "Long sleeves should be enough"),
+    (f64::INFINITY, "T-shirt weather"),
+];
+
+/// Wind speed in meters per second at and above which a windproof layer is
+/// suggested (a "strong" wind)
+const WINDY_MPS: f64 = 8.0;
+
+/// Chance of precipitation at and above which an umbrella is suggested
+const UMBRELLA_POP: f64 = 0.3;
+
+/// How far ahead of the current observation the forecast is searched for
+/// precipitation
+const ADVICE_HOURS: i64 = 12;
+
+/// Whether rain or snow is falling now or likely within the next few hours
+fn precipitation_expected(data: &WeatherData, forecast: Option<&WeatherForecast>) -> bool {
+    let raining_now = data.rain.is_some()
+        || data.snow.is_some()
+        || data
+            .weather
+            .iter()
+            .any(|cond| (200..700).contains(&cond.id));
+    if raining_now {
+        return true;
+    }
+    let end = data.dt + Duration::hours(ADVICE_HOURS);
+    forecast.is_some_and(|forecast| {
+        forecast
+            .list
+            .iter()
+            .filter(|entry| entry.dt >= data.dt && entry.dt <= end)
+            .any(|entry| match entry.pop {
+                Some(pop) => pop >= UMBRELLA_POP,
+                None => {
+                    let rain = entry.rain.as_ref().and_then(|r| r.three_hour);
+                    let snow = entry.snow.as_ref().and_then(|s| s.three_hour);
+                    rain.or(snow).is_some_and(|p| p.millimeters() > 0.0)
+                }
+            })
+    })
+}
+
+/// Suggest what to wear based on the "feels like" temperature (which
+/// accounts for wind chill), the wind and any precipitation expected in the
+/// next few hours of `forecast`
+/// ```
+/// # use anyhow::Error;
+/// # use std::io::Read;
+/// # use std::fs::File;
+/// use weather_util_rust::weather_data::WeatherData;
+/// use weather_util_rust::advice::get_advice;
+/// # fn main() -> Result<(), Error> {
+/// # let mut buf = String::new();
+/// # let mut f = File::open("tests/weather.json")?;
+/// # f.read_to_string(&mut buf)?;
+/// let data: WeatherData = serde_json::from_str(&buf)?;
+/// assert_eq!(get_advice(&data, None), "Winter coat and gloves recommended.");
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_advice(data: &WeatherData, forecast: Option<&WeatherForecast>) -> String {
+    let feels_like = data.main.feels_like.celcius();
+    let mut advice: Vec<&str> = TEMPERATURE_BANDS
+        .iter()
+        .find(|(threshold, _)| feels_like < *threshold)
+        .map(|(_, suggestion)| *suggestion)
+        .into_iter()
+        .collect();
+    if data.wind.speed.mps() >= WINDY_MPS {
+        advice.push("wear a windproof layer");
+    }
+    if precipitation_expected(data, forecast) {
+        advice.push("bring an umbrella");
+    }
+    format!("{}.", advice.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::convert::TryFrom;
+
+    use crate::{
+        advice::get_advice, speed::Speed, temperature::Temperature, weather_data::WeatherData,
+        weather_forecast::WeatherForecast,
+    };
+
+    #[test]
+    fn test_cold_rainy_advice() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        data.main.feels_like = Temperature::from_celcius(-4.0)?;
+        data.wind.speed = Speed::try_from(9.0)?;
+
+        assert_eq!(
+            get_advice(&data, Some(&forecast)),
+            "Winter coat and gloves recommended; wear a windproof layer."
+        );
+
+        forecast.list[1].pop = Some(0.7);
+        assert_eq!(
+            get_advice(&data, Some(&forecast)),
+            "Winter coat and gloves recommended; wear a windproof layer; bring an umbrella."
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_warm_dry_advice() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        data.main.feels_like = Temperature::from_celcius(28.0)?;
+        data.wind.speed = Speed::try_from(2.0)?;
+        forecast.list[0].pop = Some(0.1);
+
+        assert_eq!(get_advice(&data, Some(&forecast)), "T-shirt weather.");
+        Ok(())
+    }
+}
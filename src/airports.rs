@@ -0,0 +1,160 @@
+use anyhow::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, str::FromStr};
+
+use crate::{
+    key_values::{parse_key_values, parse_lat_lon},
+    latitude::Latitude,
+    longitude::Longitude,
+    weather_data::Coord,
+};
+
+/// ICAO code, IATA code, latitude and longitude of some major airports
+pub const BUILTIN_AIRPORTS: &[(&str, &str, f64, f64)] = &[
+    ("KATL", "ATL", 33.6407, -84.4277),
+    ("KDEN", "DEN", 39.8561, -104.6737),
+    ("KDFW", "DFW", 32.8998, -97.0403),
+    ("KEWR", "EWR", 40.6895, -74.1745),
+    ("KJFK", "JFK", 40.6413, -73.7781),
+    ("KLAX", "LAX", 33.9416, -118.4085),
+    ("KLGA", "LGA", 40.7769, -73.8740),
+    ("KORD", "ORD", 41.9742, -87.9073),
+    ("KSEA", "SEA", 47.4502, -122.3088),
+    ("KSFO", "SFO", 37.6213, -122.3790),
+    ("CYYZ", "YYZ", 43.6777, -79.6248),
+    ("EDDF", "FRA", 50.0379, 8.5622),
+    ("EGLL", "LHR", 51.4700, -0.4543),
+    ("EHAM", "AMS", 52.3105, 4.7683),
+    ("LFPG", "CDG", 49.0097, 2.5479),
+    ("OMDB", "DXB", 25.2532, 55.3657),
+    ("RJTT", "HND", 35.5494, 139.7798),
+    ("YSSY", "SYD", -33.9399, 151.1753),
+];
+
+/// Coordinates of airports by ICAO or IATA code: the `BUILTIN_AIRPORTS`
+/// plus any configured ones, which take precedence.  Configured airports
+/// are written as `code=lat,lon` entries separated by semicolons, e.g.
+/// `KISP=40.7952,-73.1002;ISP=40.7952,-73.1002`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(into = "String", try_from = "String")]
+pub struct Airports {
+    codes: Vec<(String, Latitude, Longitude)>,
+}
+
+impl Airports {
+    /// Coordinates of the airport with the ICAO or IATA `code` (ignoring
+    /// case), `None` if it isn't known
+    /// ```
+    /// use weather_util_rust::airports::Airports;
+    /// let coord = Airports::default().get_coord("egll").unwrap();
+    /// assert_eq!(f64::from(coord.lat), 51.47);
+    /// ```
+    pub fn get_coord(&self, code: &str) -> Option<Coord> {
+        let code = code.trim();
+        if let Some((_, lat, lon)) = self
+            .codes
+            .iter()
+            .find(|(entry, _, _)| entry.eq_ignore_ascii_case(code))
+        {
+            return Some(Coord {
+                lon: *lon,
+                lat: *lat,
+            });
+        }
+        BUILTIN_AIRPORTS
+            .iter()
+            .find(|(icao, iata, _, _)| {
+                icao.eq_ignore_ascii_case(code) || iata.eq_ignore_ascii_case(code)
+            })
+            .and_then(|(_, _, lat, lon)| {
+                Some(Coord {
+                    lon: Longitude::try_from(*lon).ok()?,
+                    lat: Latitude::try_from(*lat).ok()?,
+                })
+            })
+    }
+
+    /// Same as `get_coord`, but an unknown `code` is an error
+    pub fn resolve(&self, code: &str) -> Result<Coord, Error> {
+        self.get_coord(code).ok_or_else(|| {
+            format_err!(
+                "Unknown airport code '{}'; use --lat and --lon with its coordinates instead, or \
+                 add it to AIRPORTS in the config",
+                code.trim()
+            )
+        })
+    }
+}
+
+impl FromStr for Airports {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let codes = parse_key_values(s, "airport", "code=lat,lon", |code, coord| {
+            let (lat, lon) = parse_lat_lon(coord)?;
+            Ok((code.to_uppercase(), lat, lon))
+        })?;
+        Ok(Self { codes })
+    }
+}
+
+impl TryFrom<String> for Airports {
+    type Error = Error;
+    fn try_from(item: String) -> Result<Self, Self::Error> {
+        item.parse()
+    }
+}
+
+impl From<Airports> for String {
+    fn from(item: Airports) -> Self {
+        item.codes
+            .iter()
+            .map(|(code, lat, lon)| format!("{}={},{}", code, lat, lon))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::airports::{Airports, BUILTIN_AIRPORTS};
+
+    #[test]
+    fn test_airports() -> Result<(), Error> {
+        let airports = Airports::default();
+        let coord = airports.resolve("KJFK")?;
+        assert_eq!(f64::from(coord.lat), 40.6413);
+        assert_eq!(f64::from(coord.lon), -73.7781);
+        for code in &["JFK", "jfk", " kjfk "] {
+            let other = airports.resolve(code)?;
+            assert_eq!(f64::from(other.lat), 40.6413, "{}", code);
+        }
+        for (icao, iata, _, _) in BUILTIN_AIRPORTS {
+            assert!(airports.get_coord(icao).is_some(), "{}", icao);
+            assert!(airports.get_coord(iata).is_some(), "{}", iata);
+        }
+
+        let err = airports.resolve("XXXX").unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Unknown airport code 'XXXX'; use --lat and --lon"),
+            "{}",
+            err
+        );
+
+        // Configured airports are added and take precedence
+        let airports: Airports = "kisp=40.7952,-73.1002; JFK = 40.64,-73.78".parse()?;
+        let coord = airports.resolve("KISP")?;
+        assert_eq!(f64::from(coord.lon), -73.1002);
+        assert_eq!(f64::from(airports.resolve("jfk")?.lat), 40.64);
+        assert_eq!(f64::from(airports.resolve("KJFK")?.lat), 40.6413);
+        let s: String = airports.into();
+        assert_eq!(s, "KISP=40.7952,-73.1002;JFK=40.64,-73.78");
+
+        for invalid in &["KISP", "KISP=40.79", "=40.79,-73.1", "KISP=north,-73.1"] {
+            assert!(invalid.parse::<Airports>().is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+}
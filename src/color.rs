@@ -0,0 +1,270 @@
+use anyhow::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use crate::temperature::Temperature;
+
+/// Terminal foreground color
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    pub fn all() -> [Self; 9] {
+        [
+            Self::Default,
+            Self::Black,
+            Self::Red,
+            Self::Green,
+            Self::Yellow,
+            Self::Blue,
+            Self::Magenta,
+            Self::Cyan,
+            Self::White,
+        ]
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Black => "black",
+            Self::Red => "red",
+            Self::Green => "green",
+            Self::Yellow => "yellow",
+            Self::Blue => "blue",
+            Self::Magenta => "magenta",
+            Self::Cyan => "cyan",
+            Self::White => "white",
+        }
+    }
+
+    fn ansi_code(self) -> Option<u8> {
+        match self {
+            Self::Default => None,
+            Self::Black => Some(30),
+            Self::Red => Some(31),
+            Self::Green => Some(32),
+            Self::Yellow => Some(33),
+            Self::Blue => Some(34),
+            Self::Magenta => Some(35),
+            Self::Cyan => Some(36),
+            Self::White => Some(37),
+        }
+    }
+
+    /// Wrap `text` in ANSI escape codes, `Color::Default` leaves it unchanged
+    /// ```
+    /// use weather_util_rust::color::Color;
+    /// assert_eq!(Color::Red.paint("hot"), "\x1b[31mhot\x1b[0m");
+    /// assert_eq!(Color::Default.paint("mild"), "mild");
+    /// ```
+    pub fn paint(self, text: &str) -> String {
+        match self.ansi_code() {
+            Some(code) => format!("\x1b[{}m{}\x1b[0m", code, text),
+            None => text.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for Color {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|color| color.to_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| format_err!("{} is not a valid color", s))
+    }
+}
+
+/// Temperature color bands, written as a comma separated list of
+/// `color:threshold` pairs with thresholds in Celcius, e.g.
+/// `blue:0,default:30,red`.
+///
+/// Each band applies to temperatures below its threshold, checked in
+/// ascending order.  A trailing color without a threshold applies to
+/// everything above the last threshold, without one those temperatures are
+/// left uncolored.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(into = "String", try_from = "String")]
+pub struct TemperatureColors {
+    bands: Vec<(f64, Color)>,
+    above: Color,
+}
+
+impl Default for TemperatureColors {
+    fn default() -> Self {
+        Self {
+            bands: vec![
+                (0.0, Color::Blue),
+                (10.0, Color::Cyan),
+                (20.0, Color::Green),
+                (30.0, Color::Yellow),
+            ],
+            above: Color::Red,
+        }
+    }
+}
+
+impl TemperatureColors {
+    /// Color used to display `temp`
+    /// ```
+    /// use weather_util_rust::color::{Color, TemperatureColors};
+    /// use weather_util_rust::temperature::Temperature;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let colors: TemperatureColors = "blue:0,default:30,red".parse()?;
+    /// assert_eq!(colors.get_color(Temperature::from_celcius(-5.0)?), Color::Blue);
+    /// assert_eq!(colors.get_color(Temperature::from_celcius(35.0)?), Color::Red);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_color(&self, temp: Temperature) -> Color {
+        let celcius = temp.celcius();
+        self.bands
+            .iter()
+            .find(|(threshold, _)| celcius < *threshold)
+            .map_or(self.above, |(_, color)| *color)
+    }
+
+    /// Paint `text` with the color for `temp`
+    pub fn paint(&self, temp: Temperature, text: &str) -> String {
+        self.get_color(temp).paint(text)
+    }
+}
+
+impl FromStr for TemperatureColors {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bands = Vec::new();
+        let mut above = Color::Default;
+        let mut entries = s.split(',').map(str::trim).peekable();
+        while let Some(entry) = entries.next() {
+            let mut iter = entry.splitn(2, ':');
+            let color: Color = iter.next().unwrap_or("").parse()?;
+            match iter.next() {
+                Some(threshold) => {
+                    let threshold: f64 = threshold.trim().parse().map_err(|_| {
+                        format_err!("{} is not a valid temperature threshold", threshold)
+                    })?;
+                    if let Some((previous, _)) = bands.last() {
+                        if threshold <= *previous {
+                            return Err(format_err!(
+                                "temperature thresholds must be in ascending order"
+                            ));
+                        }
+                    }
+                    bands.push((threshold, color));
+                }
+                None if entries.peek().is_none() => above = color,
+                None => {
+                    return Err(format_err!(
+                        "{} is missing a threshold, only the last color may omit one",
+                        entry
+                    ))
+                }
+            }
+        }
+        Ok(Self { bands, above })
+    }
+}
+
+impl TryFrom<String> for TemperatureColors {
+    type Error = Error;
+    fn try_from(item: String) -> Result<Self, Self::Error> {
+        item.parse()
+    }
+}
+
+impl From<TemperatureColors> for String {
+    fn from(item: TemperatureColors) -> Self {
+        let mut output: Vec<_> = item
+            .bands
+            .iter()
+            .map(|(threshold, color)| format!("{}:{}", color, threshold))
+            .collect();
+        if item.above != Color::Default {
+            output.push(item.above.to_string());
+        }
+        output.join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{
+        color::{Color, TemperatureColors},
+        temperature::Temperature,
+    };
+
+    #[test]
+    fn test_default_colors() -> Result<(), Error> {
+        let colors = TemperatureColors::default();
+        assert_eq!(
+            colors.get_color(Temperature::from_celcius(-10.0)?),
+            Color::Blue
+        );
+        assert_eq!(
+            colors.get_color(Temperature::from_celcius(0.0)?),
+            Color::Cyan
+        );
+        assert_eq!(
+            colors.get_color(Temperature::from_celcius(25.0)?),
+            Color::Yellow
+        );
+        assert_eq!(
+            colors.get_color(Temperature::from_celcius(30.0)?),
+            Color::Red
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_colors() -> Result<(), Error> {
+        let temp = Temperature::from_celcius(25.0)?;
+        let colors: TemperatureColors = "blue:0,default:30,red".parse()?;
+        assert_ne!(
+            colors.get_color(temp),
+            TemperatureColors::default().get_color(temp)
+        );
+        assert_eq!(colors.get_color(temp), Color::Default);
+        assert_eq!(colors.paint(temp, "25 C"), "25 C");
+
+        let colors: TemperatureColors = "magenta:24".parse()?;
+        assert_eq!(colors.get_color(temp), Color::Default);
+        assert_eq!(
+            colors.get_color(Temperature::from_celcius(20.0)?),
+            Color::Magenta
+        );
+
+        let s: String = colors.into();
+        assert_eq!(s, "magenta:24");
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_colors() {
+        assert!("purple:0".parse::<TemperatureColors>().is_err());
+        assert!("blue,red".parse::<TemperatureColors>().is_err());
+        assert!("blue:10,red:0".parse::<TemperatureColors>().is_err());
+        assert!("blue:cold".parse::<TemperatureColors>().is_err());
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// A range of OpenWeatherMap condition codes, see
+/// https://openweathermap.org/weather-conditions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    pub first: u32,
+    pub last: u32,
+    pub name: &'static str,
+    /// Short adjective used in summaries, e.g. "partly cloudy"
+    pub phrase: &'static str,
+    pub icon: &'static str,
+    /// Nerd Font weather glyph, for terminals using a patched font
+    pub glyph: &'static str,
+}
+
+const fn condition(
+    first: u32,
+    last: u32,
+    name: &'static str,
+    phrase: &'static str,
+    icon: &'static str,
+    glyph: &'static str,
+) -> Condition {
+    Condition {
+        first,
+        last,
+        name,
+        phrase,
+        icon,
+        glyph,
+    }
+}
+
+/// Every condition known to the crate, the first range containing a code
+/// applies so specific codes come before the group they belong to.
+pub const CONDITIONS: [Condition; 19] = [
+    condition(200, 299, "Thunderstorm", "stormy", "⛈️", "\u{e31d}"),
+    condition(300, 399, "Drizzle", "drizzly", "🌦️", "\u{e31b}"),
+    condition(500, 599, "Rain", "rainy", "🌧️", "\u{e318}"),
+    condition(600, 699, "Snow", "snowy", "🌨️", "\u{e31a}"),
+    condition(701, 701, "Mist", "misty", "🌫️", "\u{e313}"),
+    condition(711, 711, "Smoke", "smoky", "🌫️", "\u{e35c}"),
+    condition(731, 731, "Sand/dust whirls", "dusty", "💨", "\u{e35d}"),
+    condition(741, 741, "Fog", "foggy", "🌫️", "\u{e313}"),
+    condition(751, 751, "Sand", "dusty", "💨", "\u{e37a}"),
+    condition(761, 761, "Dust", "dusty", "💨", "\u{e35d}"),
+    condition(762, 762, "Volcanic ash", "dusty", "🌋", "\u{e3c0}"),
+    condition(771, 771, "Squalls", "squally", "💨", "\u{e34b}"),
+    condition(
+        781,
+        781,
+        "Tornado",
+        "under a tornado warning",
+        "🌪️",
+        "\u{e351}",
+    ),
+    condition(700, 799, "Haze", "hazy", "🌫️", "\u{e3ae}"),
+    condition(800, 800, "Clear", "clear", "☀️", "\u{e30d}"),
+    condition(801, 801, "Few clouds", "mostly clear", "🌤️", "\u{e302}"),
+    condition(
+        802,
+        802,
+        "Scattered clouds",
+        "partly cloudy",
+        "⛅",
+        "\u{e302}",
+    ),
+    condition(803, 803, "Broken clouds", "mostly cloudy", "🌥️", "\u{e312}"),
+    condition(804, 804, "Overcast clouds", "overcast", "☁️", "\u{e312}"),
+];
+
+impl Condition {
+    /// Look up the condition for a code
+    /// ```
+    /// use weather_util_rust::conditions::Condition;
+    /// assert_eq!(Condition::find(741).map(|c| c.name), Some("Fog"));
+    /// assert_eq!(Condition::find(721).map(|c| c.name), Some("Haze"));
+    /// assert!(Condition::find(100).is_none());
+    /// ```
+    pub fn find(id: u32) -> Option<&'static Self> {
+        CONDITIONS
+            .iter()
+            .find(|cond| cond.first <= id && id <= cond.last)
+    }
+
+    /// Rank of a condition code, higher is more severe: by `Severity`, then
+    /// by group (tornadoes, squalls and volcanic ash, thunderstorms, snow,
+    /// rain, drizzle, other atmospheric conditions, clouds) and then by
+    /// code, higher codes being heavier within a group
+    /// ```
+    /// use weather_util_rust::conditions::Condition;
+    /// assert!(Condition::severity(211) > Condition::severity(500));
+    /// assert!(Condition::severity(701) > Condition::severity(804));
+    /// assert!(Condition::severity(804) > Condition::severity(800));
+    /// ```
+    pub fn severity(id: u32) -> u32 {
+        let group = match id {
+            781 => 8,
+            762 | 771 => 7,
+            200..=299 => 6,
+            600..=699 => 5,
+            500..=599 => 4,
+            300..=399 => 3,
+            700..=799 => 2,
+            801..=899 => 1,
+            _ => 0,
+        };
+        Severity::from_code(id) as u32 * 10_000 + group * 1_000 + id % 100
+    }
+
+    /// Code range, e.g. "200-299" or "800"
+    pub fn codes(&self) -> String {
+        if self.first == self.last {
+            self.first.to_string()
+        } else {
+            format!("{}-{}", self.first, self.last)
+        }
+    }
+}
+
+/// Coarse grouping of condition codes for deciding whether to act on them,
+/// e.g. send a notification, in increasing order of severity
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Clear,
+    /// Clouds, drizzle, light rain or snow, mist and haze
+    Mild,
+    /// Rain, snow and reduced visibility
+    Caution,
+    /// Thunderstorms, heavy or freezing precipitation and extreme
+    /// atmospheric conditions (volcanic ash, squalls and tornadoes)
+    Severe,
+}
+
+impl Severity {
+    /// Severity of a condition code, unknown codes are `Clear`
+    /// ```
+    /// use weather_util_rust::conditions::Severity;
+    /// assert_eq!(Severity::from_code(211), Severity::Severe);
+    /// assert_eq!(Severity::from_code(501), Severity::Caution);
+    /// assert_eq!(Severity::from_code(803), Severity::Mild);
+    /// ```
+    pub fn from_code(id: u32) -> Self {
+        match id {
+            200..=299 | 502..=504 | 511 | 522 | 531 | 602 | 622 | 762 | 771 | 781 => Self::Severe,
+            300..=399 | 500 | 520 | 600 | 620 | 701 | 721 | 801..=804 => Self::Mild,
+            500..=799 => Self::Caution,
+            _ => Self::Clear,
+        }
+    }
+}
+
+/// Write a table of the supported condition codes with their names and
+/// icons
+pub fn write_conditions<T: Write>(buf: &mut T) -> Result<(), Error> {
+    writeln!(buf, "{:8} {:18} {:24} Icon Glyph", "Code", "Name", "Phrase")?;
+    CONDITIONS.iter().try_for_each(|cond| {
+        writeln!(
+            buf,
+            "{:8} {:18} {:24} {:4} {}",
+            cond.codes(),
+            cond.name,
+            cond.phrase,
+            cond.icon,
+            cond.glyph
+        )
+        .map_err(Error::from)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::conditions::{write_conditions, Condition, Severity, CONDITIONS};
+
+    #[test]
+    fn test_find() {
+        assert_eq!(Condition::find(200).unwrap().phrase, "stormy");
+        assert_eq!(Condition::find(299).unwrap().phrase, "stormy");
+        assert_eq!(Condition::find(762).unwrap().phrase, "dusty");
+        assert_eq!(Condition::find(800).unwrap().phrase, "clear");
+        assert!(Condition::find(400).is_none());
+        assert!(Condition::find(805).is_none());
+
+        // Every entry must be reachable
+        for cond in CONDITIONS.iter() {
+            assert_eq!(Condition::find(cond.first), Some(cond));
+        }
+    }
+
+    #[test]
+    fn test_write_conditions() -> Result<(), Error> {
+        let mut buf = Vec::new();
+        write_conditions(&mut buf)?;
+        let buf = String::from_utf8(buf)?;
+        assert_eq!(buf.lines().count(), CONDITIONS.len() + 1);
+        assert!(buf.contains("\n200-299  Thunderstorm"), "{}", buf);
+        assert!(buf.contains("\n802      Scattered clouds"), "{}", buf);
+        assert!(buf.contains("⛅"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_severity() {
+        for (id, severity) in &[
+            (200, Severity::Severe),
+            (232, Severity::Severe),
+            (301, Severity::Mild),
+            (500, Severity::Mild),
+            (501, Severity::Caution),
+            (503, Severity::Severe),
+            (511, Severity::Severe),
+            (601, Severity::Caution),
+            (602, Severity::Severe),
+            (701, Severity::Mild),
+            (741, Severity::Caution),
+            (781, Severity::Severe),
+            (800, Severity::Clear),
+            (804, Severity::Mild),
+            (0, Severity::Clear),
+        ] {
+            assert_eq!(Severity::from_code(*id), *severity, "{}", id);
+        }
+        assert!(Severity::Severe > Severity::Caution);
+        assert!(Severity::Mild > Severity::Clear);
+    }
+
+    #[test]
+    fn test_condition_severity() {
+        // tornado, heavy rain, light rain, drizzle, mist and a clear sky
+        let ids = [781, 502, 500, 300, 701, 800];
+        for pair in ids.windows(2) {
+            assert!(
+                Condition::severity(pair[0]) > Condition::severity(pair[1]),
+                "{} > {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+}
@@ -1,14 +1,35 @@
 use anyhow::{format_err, Error};
 use serde::Deserialize;
-use std::{ops::Deref, path::Path, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use crate::{latitude::Latitude, longitude::Longitude};
+use crate::{
+    airports::Airports, color::TemperatureColors, latitude::Latitude,
+    location_names::LocationNames, location_presets::LocationPresets, longitude::Longitude,
+    units::Units,
+};
+
+/// System-wide config file, read before (and overridden by) the per-user
+/// one
+pub const SYSTEM_CONFIG_FILE: &str = "/etc/weather_util/config.env";
 
 /// Configuration data
 #[derive(Default, Debug, Deserialize)]
 pub struct ConfigInner {
     /// openweathermap.org api key
     pub api_key: Option<String>,
+    /// additional comma separated api keys, tried in order whenever a
+    /// request is rate limited
+    pub api_keys: Option<Vec<String>>,
+    /// file containing the openweathermap.org api key, only used if
+    /// `api_key` isn't set
+    #[serde(rename = "openweather_api_key_file", alias = "api_key_file")]
+    pub api_key_file: Option<PathBuf>,
     /// openweathermap.org api endpoint
     pub api_endpoint: Option<String>,
     /// Api path (default is `data/2.5/`)
@@ -23,6 +44,89 @@ pub struct ConfigInner {
     pub lat: Option<Latitude>,
     /// optional default longitude
     pub lon: Option<Longitude>,
+    /// geolocation service used by `--here` (default is
+    /// `http://ip-api.com/json`)
+    pub ip_location_url: Option<String>,
+    /// directory api responses are cached in, setting it turns on the
+    /// cache (otherwise `--cache` or `--offline` use
+    /// `${HOME}/.cache/weather_util`)
+    pub cache_dir: Option<PathBuf>,
+    /// optional friendly names replacing the api's name for nearby
+    /// coordinates, e.g. `40.76,-73.93=Home;51.51,-0.13=Office` (see
+    /// `LocationNames`)
+    pub location_names: Option<LocationNames>,
+    /// optional airports added to (or replacing) the bundled ones for
+    /// `--icao` and `--iata`, e.g. `KISP=40.7952,-73.1002` (see `Airports`)
+    pub airports: Option<Airports>,
+    /// optional named locations for `--preset`, e.g.
+    /// `home=40.71,-74.01;work=Boston,MA,US` (see `LocationPresets`)
+    pub presets: Option<LocationPresets>,
+    /// optional temperature color bands used with `--color`, e.g.
+    /// `blue:0,default:30,red` (see `TemperatureColors`)
+    pub temperature_colors: Option<TemperatureColors>,
+    /// optional default units when `--units` isn't given (imperial or
+    /// metric)
+    pub weather_units: Option<String>,
+}
+
+impl ConfigInner {
+    /// Default units from `WEATHER_UNITS`, an invalid value is reported and
+    /// the built-in default used instead
+    pub fn get_units(&self) -> Units {
+        match self.weather_units.as_deref().map(str::parse) {
+            Some(Ok(units)) => units,
+            Some(Err(e)) => {
+                eprintln!("Ignoring WEATHER_UNITS: {}", e);
+                Units::default()
+            }
+            None => Units::default(),
+        }
+    }
+
+    /// Read `api_key` from `api_key_file` if it isn't set directly
+    fn resolve_api_key(&mut self) -> Result<(), Error> {
+        if self.api_key.is_none() {
+            if let Some(path) = &self.api_key_file {
+                self.api_key = Some(read_api_key_file(path)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read an api key from `path`, ignoring surrounding whitespace
+fn read_api_key_file(path: &Path) -> Result<String, Error> {
+    let api_key = fs::read_to_string(path).map_err(|e| {
+        format_err!(
+            "Failed to read api key from {}: {}",
+            path.to_string_lossy(),
+            e
+        )
+    })?;
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(format_err!(
+            "Api key file {} is empty",
+            path.to_string_lossy()
+        ));
+    }
+    Ok(api_key.to_string())
+}
+
+/// Read the variables of each of the config files in `paths` that exists,
+/// a variable set in several files takes its value from the last one.
+/// Lines that can't be parsed are skipped.
+// `from_path_iter` is deprecated, but it's the only way to read a file
+// without setting its variables
+#[allow(deprecated)]
+pub fn merge_config_files(paths: &[&Path]) -> Result<BTreeMap<String, String>, Error> {
+    let mut vars = BTreeMap::new();
+    for path in paths.iter().filter(|path| path.exists()) {
+        let iter = dotenv::from_path_iter(path)
+            .map_err(|e| format_err!("Failed to read config {}: {}", path.to_string_lossy(), e))?;
+        vars.extend(iter.filter_map(Result::ok));
+    }
+    Ok(vars)
 }
 
 /// Configuration struct
@@ -43,6 +147,12 @@ impl Config {
     /// or if a config file exists at `${HOME}/.config/weather_util/config.env`,
     /// set ENV variables using it.
     ///
+    /// That per-user config is merged over the system-wide
+    /// `SYSTEM_CONFIG_FILE`: each variable is taken from the environment
+    /// (including `.env`) if set there, else from the per-user config, else
+    /// from the system-wide one.  So e.g. `API_ENDPOINT` can be set for
+    /// everyone on a machine while each user sets their own `API_KEY`.
+    ///
     /// Config files should have lines of the following form:
     /// `API_KEY=api_key_value`
     ///
@@ -75,11 +185,15 @@ impl Config {
 
         dotenv::dotenv().ok();
 
-        if env_file.exists() {
-            dotenv::from_path(env_file).ok();
+        let config_files = [Path::new(SYSTEM_CONFIG_FILE), env_file];
+        for (key, value) in merge_config_files(&config_files)? {
+            if env::var_os(&key).is_none() {
+                env::set_var(key, value);
+            }
         }
 
-        let conf: ConfigInner = envy::from_env()?;
+        let mut conf: ConfigInner = envy::from_env()?;
+        conf.resolve_api_key()?;
 
         Ok(Self(Arc::new(conf)))
     }
@@ -92,3 +206,98 @@ impl Deref for Config {
         &self.0
     }
 }
+
+impl From<ConfigInner> for Config {
+    fn from(item: ConfigInner) -> Self {
+        Self(Arc::new(item))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::{env::temp_dir, fs};
+
+    use crate::{
+        config::{merge_config_files, ConfigInner},
+        units::Units,
+    };
+
+    #[test]
+    fn test_weather_units() -> Result<(), Error> {
+        let env = |value: &str| vec![("WEATHER_UNITS".to_string(), value.to_string())];
+        let config: ConfigInner = envy::from_iter(env("metric"))?;
+        assert_eq!(config.get_units(), Units::Metric);
+
+        let config: ConfigInner = envy::from_iter(env("Imperial"))?;
+        assert_eq!(config.get_units(), Units::Imperial);
+
+        let config: ConfigInner = envy::from_iter(env("kelvin"))?;
+        assert_eq!(config.get_units(), Units::Imperial);
+
+        assert_eq!(ConfigInner::default().get_units(), Units::Imperial);
+        Ok(())
+    }
+
+    #[test]
+    fn test_api_key_file() -> Result<(), Error> {
+        let path = temp_dir().join(format!("weather_util_api_key_{}", std::process::id()));
+        fs::write(&path, "  file_api_key\n")?;
+
+        let mut config = ConfigInner {
+            api_key_file: Some(path.clone()),
+            ..ConfigInner::default()
+        };
+        config.resolve_api_key()?;
+        assert_eq!(config.api_key.as_deref(), Some("file_api_key"));
+
+        let mut config = ConfigInner {
+            api_key: Some("env_api_key".into()),
+            api_key_file: Some(path.clone()),
+            ..ConfigInner::default()
+        };
+        config.resolve_api_key()?;
+        assert_eq!(config.api_key.as_deref(), Some("env_api_key"));
+
+        fs::write(&path, "\n")?;
+        let mut config = ConfigInner {
+            api_key_file: Some(path.clone()),
+            ..ConfigInner::default()
+        };
+        assert!(config.resolve_api_key().is_err());
+
+        fs::remove_file(&path)?;
+        let err = config.resolve_api_key().unwrap_err();
+        assert!(
+            err.to_string().starts_with("Failed to read api key from"),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_config_files() -> Result<(), Error> {
+        let dir = temp_dir();
+        let system = dir.join(format!("weather_util_system_{}.env", std::process::id()));
+        let user = dir.join(format!("weather_util_user_{}.env", std::process::id()));
+        fs::write(
+            &system,
+            "API_ENDPOINT=weather.example.com\nAPI_KEY=shared_key\nWEATHER_UNITS=metric\n",
+        )?;
+        fs::write(&user, "# my key\nAPI_KEY=user_key\nZIPCODE=11106\n")?;
+
+        let missing = dir.join("weather_util_missing.env");
+        let vars = merge_config_files(&[&system, &user, &missing])?;
+        fs::remove_file(&system)?;
+        fs::remove_file(&user)?;
+        let config: ConfigInner = envy::from_iter(vars)?;
+        assert_eq!(config.api_endpoint.as_deref(), Some("weather.example.com"));
+        assert_eq!(config.api_key.as_deref(), Some("user_key"));
+        assert_eq!(config.zipcode, Some(11106));
+        assert_eq!(config.get_units(), Units::Metric);
+
+        assert!(merge_config_files(&[&missing])?.is_empty());
+        Ok(())
+    }
+}
@@ -1,7 +1,75 @@
 use anyhow::{format_err, Error};
 use derive_more::{Display, Into};
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, f64::consts::PI};
+use std::{convert::TryFrom, f64::consts::PI, fmt, str::FromStr};
+
+/// Abbreviations of the 32 compass points, clockwise from north
+const COMPASS_POINTS: [&str; 32] = [
+    "N", "NbE", "NNE", "NEbN", "NE", "NEbE", "ENE", "EbN", "E", "EbS", "ESE", "SEbE", "SE", "SEbS",
+    "SSE", "SbE", "S", "SbW", "SSW", "SWbS", "SW", "SWbW", "WSW", "WbS", "W", "WbN", "WNW", "NWbW",
+    "NW", "NWbN", "NNW", "NbW",
+];
+
+/// Granularity of the compass used to label wind directions
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(into = "usize", try_from = "usize")]
+pub enum CompassPoints {
+    Eight,
+    Sixteen,
+    ThirtyTwo,
+}
+
+impl CompassPoints {
+    pub fn all() -> [Self; 3] {
+        [Self::Eight, Self::Sixteen, Self::ThirtyTwo]
+    }
+
+    pub fn count(self) -> usize {
+        match self {
+            Self::Eight => 8,
+            Self::Sixteen => 16,
+            Self::ThirtyTwo => 32,
+        }
+    }
+}
+
+impl From<CompassPoints> for usize {
+    fn from(item: CompassPoints) -> Self {
+        item.count()
+    }
+}
+
+impl TryFrom<usize> for CompassPoints {
+    type Error = Error;
+    fn try_from(item: usize) -> Result<Self, Self::Error> {
+        Self::all()
+            .iter()
+            .find(|points| points.count() == item)
+            .copied()
+            .ok_or_else(|| {
+                format_err!(
+                    "{} is not a valid number of compass points (8, 16, 32)",
+                    item
+                )
+            })
+    }
+}
+
+impl fmt::Display for CompassPoints {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.count())
+    }
+}
+
+impl FromStr for CompassPoints {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count: usize = s.trim().parse().map_err(|_| {
+            format_err!("{} is not a valid number of compass points (8, 16, 32)", s)
+        })?;
+        Self::try_from(count)
+    }
+}
 
 /// Direction in degrees
 #[derive(Into, Debug, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize, Display)]
@@ -34,4 +102,66 @@ impl Direction {
     pub fn radian(self) -> f64 {
         self.0 * PI / 180.0
     }
+
+    /// Name of the nearest of the 8 principal compass points
+    /// ```
+    /// use weather_util_rust::direction::Direction;
+    /// assert_eq!(Direction::from_deg(280.0).cardinal_name(), "west");
+    /// assert_eq!(Direction::from_deg(350.0).cardinal_name(), "north");
+    /// ```
+    pub fn cardinal_name(self) -> &'static str {
+        const NAMES: [&str; 8] = [
+            "north",
+            "northeast",
+            "east",
+            "southeast",
+            "south",
+            "southwest",
+            "west",
+            "northwest",
+        ];
+        NAMES[((self.0 / 45.0).round() as usize) % 8]
+    }
+
+    /// Abbreviation of the nearest point of a compass with `points` points,
+    /// e.g. "NE" or "NNE"
+    /// ```
+    /// use weather_util_rust::direction::{CompassPoints, Direction};
+    /// let deg = Direction::from_deg(30.0);
+    /// assert_eq!(deg.compass_point(CompassPoints::Eight), "NE");
+    /// assert_eq!(deg.compass_point(CompassPoints::Sixteen), "NNE");
+    /// ```
+    pub fn compass_point(self, points: CompassPoints) -> &'static str {
+        let count = points.count();
+        let index = (self.0 / (360.0 / count as f64)).round() as usize % count;
+        COMPASS_POINTS[index * COMPASS_POINTS.len() / count]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::direction::{CompassPoints, Direction};
+
+    #[test]
+    fn test_compass_point() -> Result<(), Error> {
+        let points = |deg: f64, count: &str| -> Result<&'static str, Error> {
+            Ok(Direction::from_deg(deg).compass_point(count.parse()?))
+        };
+        assert_eq!(points(193.0, "8")?, "S");
+        assert_eq!(points(193.0, "16")?, "SSW");
+        assert_eq!(points(193.0, "32")?, "SbW");
+        assert_eq!(points(310.0, "8")?, "NW");
+        assert_eq!(points(310.0, "16")?, "NW");
+        assert_eq!(points(350.0, "8")?, "N");
+        assert_eq!(points(350.0, "16")?, "N");
+        assert_eq!(points(350.0, "32")?, "NbW");
+        assert_eq!(points(359.0, "32")?, "N");
+
+        assert!("12".parse::<CompassPoints>().is_err());
+        assert!("north".parse::<CompassPoints>().is_err());
+        assert_eq!(CompassPoints::Sixteen.to_string(), "16");
+        Ok(())
+    }
 }
@@ -0,0 +1,60 @@
+use anyhow::Error;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+/// Cache of raw api responses on disk, one file per request (ignoring the
+/// api key), used to serve `--offline` requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskCache {
+    directory: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    /// `${HOME}/.cache/weather_util` (or the platform equivalent)
+    pub fn default_directory() -> Option<PathBuf> {
+        dirs::cache_dir().map(|d| d.join("weather_util"))
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn get_path(&self, command: &str, options: &[(&'static str, String)]) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        command.hash(&mut hasher);
+        options.hash(&mut hasher);
+        let fname = format!(
+            "{}_{:016x}.json",
+            command.replace('/', "_"),
+            hasher.finish()
+        );
+        self.directory.join(fname)
+    }
+
+    /// Cached response for `command` with `options`, if there is one
+    pub fn get(&self, command: &str, options: &[(&'static str, String)]) -> Option<String> {
+        fs::read_to_string(self.get_path(command, options)).ok()
+    }
+
+    /// Store the response to `command` with `options`
+    pub fn put(
+        &self,
+        command: &str,
+        options: &[(&'static str, String)],
+        text: &str,
+    ) -> Result<(), Error> {
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.get_path(command, options), text)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,469 @@
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, Utc};
+use std::io::Write;
+
+use crate::{
+    color::TemperatureColors,
+    conditions::Condition,
+    direction::CompassPoints,
+    elevation::Elevation,
+    forecast_summary::TemperatureField,
+    last_run::LastRun,
+    locale::Locale,
+    precipitation::{Precipitation, PrecipitationUnit},
+    pressure::Pressure,
+    speed::{Speed, SpeedUnit},
+    temperature::Temperature,
+    units::Units,
+};
+
+/// Decimal places for wind speeds unless `wind_precision` is set
+pub const DEFAULT_WIND_PRECISION: usize = 1;
+
+/// Decimal places for pressures in hPa unless `pressure_precision` is set
+pub const DEFAULT_PRESSURE_PRECISION: usize = 0;
+
+/// Decimal places coordinates are rounded to unless `coord_precision` is set
+pub const DEFAULT_COORD_PRECISION: usize = 4;
+
+/// `value` (a latitude or longitude) rounded to `precision` decimal places,
+/// for display only
+/// ```
+/// use weather_util_rust::display_opts::round_coordinate;
+/// assert_eq!(round_coordinate(40.712776, 4), 40.7128);
+/// assert_eq!(round_coordinate(-74.005974, 2), -74.01);
+/// assert_eq!(round_coordinate(40.76, 4).to_string(), "40.76");
+/// ```
+pub fn round_coordinate(value: f64, precision: usize) -> f64 {
+    let factor = 10_f64.powi(precision as i32);
+    // adding zero turns a rounded -0 into 0
+    (value * factor).round() / factor + 0.0
+}
+
+/// Kind of icon written next to the conditions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconStyle {
+    Emoji,
+    /// Nerd Font glyphs
+    Glyph,
+}
+
+/// Options controlling how current conditions and forecasts are written out
+#[derive(Default, Debug, Clone)]
+pub struct DisplayOpts {
+    /// Color temperatures using these bands, `None` disables color
+    pub temperature_colors: Option<TemperatureColors>,
+    /// Units for temperatures and precipitation
+    pub units: Units,
+    /// Unit used for wind speeds
+    pub wind_unit: SpeedUnit,
+    /// Label wind directions with the nearest point of this compass
+    pub compass_points: Option<CompassPoints>,
+    /// Unit used for rain and snow, the default of `units` if unset
+    pub precipitation_unit: Option<PrecipitationUnit>,
+    /// Write " F"/" C" rather than "°F"/"°C"
+    pub ascii: bool,
+    /// Write out additional details, e.g. the source of the data
+    pub verbose: bool,
+    /// Leave out the location name and coordinates
+    pub hide_location: bool,
+    /// Group forecast days by week
+    pub compact_forecast: bool,
+    /// Temperature the forecast highs and lows are taken from
+    pub forecast_field: TemperatureField,
+    /// Also write out the pressure at this elevation
+    pub elevation: Option<Elevation>,
+    /// Write an icon for the conditions
+    pub icons: Option<IconStyle>,
+    /// Decimal places for all temperatures, by default this depends on the
+    /// output
+    pub precision: Option<usize>,
+    /// Decimal places for wind speeds, `DEFAULT_WIND_PRECISION` if unset
+    pub wind_precision: Option<usize>,
+    /// Decimal places for pressures, `DEFAULT_PRESSURE_PRECISION` if unset
+    pub pressure_precision: Option<usize>,
+    /// Decimal places coordinates are rounded to, `DEFAULT_COORD_PRECISION`
+    /// if unset
+    pub coord_precision: Option<usize>,
+    /// Time that "Today" and "Tomorrow" in the forecast refer to, the
+    /// current time if unset
+    pub now: Option<DateTime<Utc>>,
+    /// Previous conditions to annotate the changes of the current
+    /// conditions against
+    pub baseline: Option<LastRun>,
+    /// Write out at most this many forecast days, none at all for 0.  The
+    /// aggregates (the week ahead range, best day and trend) cover the
+    /// forecast as given, which may only have been requested for these days.
+    pub forecast_days: Option<usize>,
+    /// Only write out the forecast days where rain or snow is likely
+    pub rain_days_only: bool,
+    /// Follow local times with the same time in UTC
+    pub show_utc: bool,
+    /// Also write out the aviation flight category
+    pub aviation: bool,
+    /// Heading written (followed by a colon) before the forecast in place
+    /// of the one of `forecast_field`
+    pub forecast_heading: Option<String>,
+    /// Leave out the heading before the forecast
+    pub hide_forecast_banner: bool,
+    /// Leave out the note on forecast days too far ahead to be reliable
+    pub hide_confidence: bool,
+    /// Percentile of the current wind speed among the forecast's, see
+    /// `WeatherForecast::wind_percentile`.  Never set for historical
+    /// conditions, which have no forecast to compare against.
+    pub wind_percentile: Option<f64>,
+    /// Locale the forecast weekdays are named in
+    pub locale: Locale,
+}
+
+impl DisplayOpts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_temperature_colors(mut self, temperature_colors: TemperatureColors) -> Self {
+        self.temperature_colors = Some(temperature_colors);
+        self
+    }
+
+    pub fn with_units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    pub fn with_wind_unit(mut self, wind_unit: SpeedUnit) -> Self {
+        self.wind_unit = wind_unit;
+        self
+    }
+
+    pub fn with_compass_points(mut self, compass_points: CompassPoints) -> Self {
+        self.compass_points = Some(compass_points);
+        self
+    }
+
+    pub fn with_precipitation_unit(mut self, precipitation_unit: PrecipitationUnit) -> Self {
+        self.precipitation_unit = Some(precipitation_unit);
+        self
+    }
+
+    pub fn with_ascii(mut self, ascii: bool) -> Self {
+        self.ascii = ascii;
+        self
+    }
+
+    pub fn with_verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn with_hide_location(mut self, hide_location: bool) -> Self {
+        self.hide_location = hide_location;
+        self
+    }
+
+    pub fn with_forecast_field(mut self, forecast_field: TemperatureField) -> Self {
+        self.forecast_field = forecast_field;
+        self
+    }
+
+    pub fn with_compact_forecast(mut self, compact_forecast: bool) -> Self {
+        self.compact_forecast = compact_forecast;
+        self
+    }
+
+    pub fn with_elevation(mut self, elevation: Elevation) -> Self {
+        self.elevation = Some(elevation);
+        self
+    }
+
+    pub fn with_icons(mut self, icons: IconStyle) -> Self {
+        self.icons = Some(icons);
+        self
+    }
+
+    pub fn with_precision(mut self, precision: usize) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    pub fn with_wind_precision(mut self, wind_precision: usize) -> Self {
+        self.wind_precision = Some(wind_precision);
+        self
+    }
+
+    pub fn with_pressure_precision(mut self, pressure_precision: usize) -> Self {
+        self.pressure_precision = Some(pressure_precision);
+        self
+    }
+
+    pub fn with_coord_precision(mut self, coord_precision: usize) -> Self {
+        self.coord_precision = Some(coord_precision);
+        self
+    }
+
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.now = Some(now);
+        self
+    }
+
+    pub fn with_baseline(mut self, baseline: LastRun) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    pub fn with_forecast_days(mut self, forecast_days: usize) -> Self {
+        self.forecast_days = Some(forecast_days);
+        self
+    }
+
+    pub fn with_rain_days_only(mut self, rain_days_only: bool) -> Self {
+        self.rain_days_only = rain_days_only;
+        self
+    }
+
+    pub fn with_show_utc(mut self, show_utc: bool) -> Self {
+        self.show_utc = show_utc;
+        self
+    }
+
+    pub fn with_aviation(mut self, aviation: bool) -> Self {
+        self.aviation = aviation;
+        self
+    }
+
+    pub fn with_forecast_heading(mut self, forecast_heading: &str) -> Self {
+        self.forecast_heading = Some(forecast_heading.into());
+        self
+    }
+
+    pub fn with_hide_forecast_banner(mut self, hide_forecast_banner: bool) -> Self {
+        self.hide_forecast_banner = hide_forecast_banner;
+        self
+    }
+
+    pub fn with_hide_confidence(mut self, hide_confidence: bool) -> Self {
+        self.hide_confidence = hide_confidence;
+        self
+    }
+
+    pub fn with_wind_percentile(mut self, wind_percentile: f64) -> Self {
+        self.wind_percentile = Some(wind_percentile);
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// `coord_precision` if set, otherwise `DEFAULT_COORD_PRECISION`
+    pub fn coord_precision(&self) -> usize {
+        self.coord_precision.unwrap_or(DEFAULT_COORD_PRECISION)
+    }
+
+    /// `now` if set, otherwise the current time
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now.unwrap_or_else(Utc::now)
+    }
+
+    fn degrees(&self) -> &'static str {
+        if self.ascii {
+            " "
+        } else {
+            "°"
+        }
+    }
+
+    /// Format `temp` in Fahrenheit with `precision` decimal places, see
+    /// `Temperature::clamp_display_nan` for NaN and infinite values
+    /// ```
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # use weather_util_rust::temperature::Temperature;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let temp = Temperature::from_celcius(5.0)?;
+    /// assert_eq!(DisplayOpts::default().fahrenheit(temp, 1), "41.0°F");
+    /// assert_eq!(DisplayOpts::default().with_ascii(true).fahrenheit(temp, 1), "41.0 F");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fahrenheit(&self, temp: Temperature, precision: usize) -> String {
+        format!(
+            "{}{}F",
+            Temperature::clamp_display_nan(temp.fahrenheit(), precision),
+            self.degrees()
+        )
+    }
+
+    /// Format `temp` in Celcius with `precision` decimal places
+    pub fn celcius(&self, temp: Temperature, precision: usize) -> String {
+        format!(
+            "{}{}C",
+            Temperature::clamp_display_nan(temp.celcius(), precision),
+            self.degrees()
+        )
+    }
+
+    /// Format `temp` in the primary then the secondary unit, with
+    /// `precision` decimal places unless overridden by the `precision` option
+    /// ```
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// use weather_util_rust::units::Units;
+    /// # use weather_util_rust::temperature::Temperature;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let temp = Temperature::from_celcius(5.0)?;
+    /// let (primary, secondary) = DisplayOpts::default().with_units(Units::Metric).temperatures(temp, 1);
+    /// assert_eq!((primary.as_str(), secondary.as_str()), ("5.0°C", "41.0°F"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn temperatures(&self, temp: Temperature, precision: usize) -> (String, String) {
+        let precision = self.precision.unwrap_or(precision);
+        let fahrenheit = self.fahrenheit(temp, precision);
+        let celcius = self.celcius(temp, precision);
+        match self.units {
+            Units::Imperial => (fahrenheit, celcius),
+            Units::Metric => (celcius, fahrenheit),
+        }
+    }
+
+    /// `elev 1655 m / 5430 ft`, in feet first for imperial units
+    pub fn elevation(&self, elevation: Elevation) -> String {
+        let meters = format!("{:.0} m", elevation.meters());
+        let feet = format!("{:.0} ft", elevation.feet());
+        match self.units {
+            Units::Imperial => format!("elev {} / {}", feet, meters),
+            Units::Metric => format!("elev {} / {}", meters, feet),
+        }
+    }
+
+    /// Format a local time, followed by the same time in UTC if `show_utc`
+    /// is set
+    /// ```
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # use chrono::{FixedOffset, TimeZone};
+    /// let dt = FixedOffset::west_opt(5 * 3600)
+    ///     .unwrap()
+    ///     .with_ymd_and_hms(2020, 1, 19, 14, 0, 0)
+    ///     .unwrap();
+    /// assert_eq!(DisplayOpts::default().local_time(dt), "2020-01-19 14:00:00 -05:00");
+    /// assert_eq!(
+    ///     DisplayOpts::default().with_show_utc(true).local_time(dt),
+    ///     "2020-01-19 14:00:00 -05:00 (2020-01-19 19:00:00 UTC)"
+    /// );
+    /// ```
+    pub fn local_time(&self, dt: DateTime<FixedOffset>) -> String {
+        if self.show_utc {
+            format!(
+                "{} ({})",
+                dt,
+                dt.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S UTC")
+            )
+        } else {
+            dt.to_string()
+        }
+    }
+
+    /// Write the banner preceding the forecast days, `Forecast:` by default
+    /// ```
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut buf = Vec::new();
+    /// DisplayOpts::default().write_forecast_banner(&mut buf)?;
+    /// DisplayOpts::default().with_forecast_heading("Outlook").write_forecast_banner(&mut buf)?;
+    /// DisplayOpts::default().with_hide_forecast_banner(true).write_forecast_banner(&mut buf)?;
+    /// assert_eq!(String::from_utf8(buf)?, "\nForecast:\n\nOutlook:\n");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_forecast_banner<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
+        if self.hide_forecast_banner {
+            return Ok(());
+        }
+        let heading = self
+            .forecast_heading
+            .as_deref()
+            .unwrap_or_else(|| self.forecast_field.heading());
+        writeln!(buf, "\n{}:", heading)?;
+        Ok(())
+    }
+
+    /// Format `precipitation` in `precipitation_unit`
+    pub fn precipitation(&self, precipitation: Precipitation) -> String {
+        let unit = self
+            .precipitation_unit
+            .unwrap_or_else(|| self.units.precipitation_unit());
+        format!(
+            "{:.*} {}",
+            unit.precision(),
+            precipitation.value(unit),
+            unit
+        )
+    }
+
+    /// Format `speed` in `wind_unit`
+    /// ```
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # use weather_util_rust::speed::Speed;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let speed = Speed::from_mph(12.34)?;
+    /// assert_eq!(DisplayOpts::default().wind_speed(speed), "12.3 mph");
+    /// assert_eq!(DisplayOpts::default().with_wind_precision(0).wind_speed(speed), "12 mph");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wind_speed(&self, speed: Speed) -> String {
+        format!(
+            "{:.*} {}",
+            self.wind_precision.unwrap_or(DEFAULT_WIND_PRECISION),
+            speed.value(self.wind_unit),
+            self.wind_unit.suffix()
+        )
+    }
+
+    /// Format `pressure` in hPa
+    /// ```
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # use weather_util_rust::pressure::Pressure;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let pressure = Pressure::from_hpa(1013.25)?;
+    /// assert_eq!(DisplayOpts::default().pressure(pressure), "1013 hPa");
+    /// assert_eq!(DisplayOpts::default().with_pressure_precision(2).pressure(pressure), "1013.25 hPa");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pressure(&self, pressure: Pressure) -> String {
+        format!(
+            "{:.*} hPa",
+            self.pressure_precision
+                .unwrap_or(DEFAULT_PRESSURE_PRECISION),
+            pressure.hpa()
+        )
+    }
+
+    /// Icon for the condition `id` followed by a space, or an empty string
+    /// if icons are disabled (always the case with `ascii`) or it isn't a
+    /// known condition
+    pub fn condition_icon(&self, id: u32) -> String {
+        if self.ascii {
+            return "".to_string();
+        }
+        match (self.icons, Condition::find(id)) {
+            (Some(IconStyle::Emoji), Some(cond)) => format!("{} ", cond.icon),
+            (Some(IconStyle::Glyph), Some(cond)) => format!("{} ", cond.glyph),
+            _ => "".to_string(),
+        }
+    }
+
+    /// Paint `text` with the color for `temp` if color is enabled
+    pub fn paint_temperature(&self, temp: Temperature, text: &str) -> String {
+        match &self.temperature_colors {
+            Some(colors) => colors.paint(temp, text),
+            None => text.to_string(),
+        }
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 
 const METERS_PER_MILE: f64 = 1609.344;
+const METERS_PER_FOOT: f64 = 0.3048;
 
 /// Distance in meters
 #[derive(Into, Debug, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize)]
@@ -29,6 +30,10 @@ impl Distance {
         Self::try_from(miles * METERS_PER_MILE)
     }
 
+    pub fn from_feet(feet: f64) -> Result<Self, Error> {
+        Self::try_from(feet * METERS_PER_FOOT)
+    }
+
     pub fn meters(self) -> f64 {
         self.0
     }
@@ -36,6 +41,10 @@ impl Distance {
     pub fn miles(self) -> f64 {
         self.0 / METERS_PER_MILE
     }
+
+    pub fn feet(self) -> f64 {
+        self.0 / METERS_PER_FOOT
+    }
 }
 
 #[cfg(test)]
@@ -50,6 +59,8 @@ mod tests {
         let s = Distance::from_miles(1.0)?;
         assert_abs_diff_eq!(s.miles(), 1.0);
         assert_abs_diff_eq!(s.meters(), 1609.344);
+        assert_abs_diff_eq!(s.feet(), 5280.0, epsilon = 1e-9);
+        assert_abs_diff_eq!(Distance::from_feet(1000.0)?.meters(), 304.8);
         Ok(())
     }
 }
@@ -0,0 +1,72 @@
+use anyhow::{format_err, Error};
+use derive_more::{Display, Into};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, str::FromStr};
+
+/// Lowest accepted elevation in meters, a little below the Dead Sea shore
+pub const MIN_ELEVATION: f64 = -500.0;
+
+/// Highest accepted elevation in meters, a little above Mount Everest and
+/// within the troposphere where the barometric formula in
+/// `Pressure::at_elevation` holds
+pub const MAX_ELEVATION: f64 = 9_000.0;
+
+const METERS_PER_FOOT: f64 = 0.3048;
+
+/// Elevation above sea level in meters, required to be within
+/// `MIN_ELEVATION` to `MAX_ELEVATION`
+#[derive(Into, Clone, Copy, Display, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(into = "f64", try_from = "f64")]
+pub struct Elevation(f64);
+
+impl TryFrom<f64> for Elevation {
+    type Error = Error;
+    fn try_from(item: f64) -> Result<Self, Self::Error> {
+        if (MIN_ELEVATION..=MAX_ELEVATION).contains(&item) {
+            Ok(Self(item))
+        } else {
+            Err(format_err!(
+                "{} is not a valid elevation, must be between {} and {} meters",
+                item,
+                MIN_ELEVATION,
+                MAX_ELEVATION
+            ))
+        }
+    }
+}
+
+impl FromStr for Elevation {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.trim().parse::<f64>()?)
+    }
+}
+
+impl Elevation {
+    pub fn meters(self) -> f64 {
+        self.0
+    }
+
+    pub fn feet(self) -> f64 {
+        self.0 / METERS_PER_FOOT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::elevation::Elevation;
+
+    #[test]
+    fn test_elevation() -> Result<(), Error> {
+        let elevation: Elevation = "1500".parse()?;
+        assert_eq!(elevation.meters(), 1500.0);
+        assert!((elevation.feet() - 4921.26).abs() < 0.01);
+        assert!("-430".parse::<Elevation>().is_ok());
+        assert!("-1000".parse::<Elevation>().is_err());
+        assert!("10000".parse::<Elevation>().is_err());
+        assert!("high".parse::<Elevation>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{distance::Distance, humidity::Humidity, temperature::Temperature};
+
+/// Ceilings below this many feet are LIFR
+pub const LIFR_CEILING_FEET: f64 = 500.0;
+/// Ceilings below this many feet are IFR
+pub const IFR_CEILING_FEET: f64 = 1000.0;
+/// Ceilings up to this many feet are MVFR, higher ones VFR
+pub const MVFR_CEILING_FEET: f64 = 3000.0;
+/// Visibilities below this many miles are LIFR
+pub const LIFR_VISIBILITY_MILES: f64 = 1.0;
+/// Visibilities below this many miles are IFR
+pub const IFR_VISIBILITY_MILES: f64 = 3.0;
+/// Visibilities up to this many miles are MVFR, further ones VFR
+pub const MVFR_VISIBILITY_MILES: f64 = 5.0;
+
+/// Cloud cover in percent from which the clouds form a ceiling, a broken
+/// layer covering at least 5/8 of the sky
+pub const CEILING_CLOUD_COVER: u8 = 63;
+
+/// The cloud base rises about 400 feet for each degree Celcius between the
+/// temperature and the dew point
+pub const CLOUD_BASE_FEET_PER_DEGREE: f64 = 400.0;
+
+/// Height of the lowest broken or overcast cloud layer above the ground
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ceiling {
+    /// The clouds don't cover enough of the sky to form a ceiling
+    Unlimited,
+    Height(Distance),
+}
+
+impl Ceiling {
+    /// The api reports no cloud heights, so the ceiling is estimated from
+    /// `cloud_cover` (in percent) and the cloud base from the spread between
+    /// `temp` and its dew point, which is only accurate for convective
+    /// clouds.  `None` when the dew point can't be computed (zero
+    /// humidity).
+    pub fn estimate(temp: Temperature, humidity: Humidity, cloud_cover: u8) -> Option<Self> {
+        if cloud_cover < CEILING_CLOUD_COVER {
+            return Some(Self::Unlimited);
+        }
+        let spread = temp.celcius() - dew_point(temp, humidity)?;
+        let feet = spread.max(0.0) * CLOUD_BASE_FEET_PER_DEGREE;
+        Distance::from_feet(feet).ok().map(Self::Height)
+    }
+}
+
+/// Dew point in degrees Celcius by the Magnus formula, `None` for zero
+/// humidity
+fn dew_point(temp: Temperature, humidity: Humidity) -> Option<f64> {
+    const B: f64 = 17.62;
+    const C: f64 = 243.12;
+    let humidity = i64::from(humidity) as f64;
+    if humidity <= 0.0 {
+        return None;
+    }
+    let t = temp.celcius();
+    let gamma = (humidity / 100.0).ln() + B * t / (C + t);
+    Some(C * gamma / (B - gamma))
+}
+
+/// Aviation flight category from the ceiling and visibility, using the FAA
+/// thresholds:
+///
+/// | Category | Ceiling (ft) | | Visibility (mi) |
+/// |----------|--------------|----|-----------------|
+/// | LIFR     | below 500    | or | below 1         |
+/// | IFR      | 500 to 999   | or | 1 to below 3    |
+/// | MVFR     | 1000 to 3000 | or | 3 to 5          |
+/// | VFR      | above 3000   | and | above 5        |
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FlightCategory {
+    /// Low instrument flight rules
+    #[serde(rename = "LIFR")]
+    Lifr,
+    /// Instrument flight rules
+    #[serde(rename = "IFR")]
+    Ifr,
+    /// Marginal visual flight rules
+    #[serde(rename = "MVFR")]
+    Mvfr,
+    /// Visual flight rules
+    #[serde(rename = "VFR")]
+    Vfr,
+    /// The ceiling or visibility is missing
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+impl FlightCategory {
+    fn from_ceiling(ceiling: Ceiling) -> Self {
+        let feet = match ceiling {
+            Ceiling::Unlimited => return Self::Vfr,
+            Ceiling::Height(height) => height.feet(),
+        };
+        if feet < LIFR_CEILING_FEET {
+            Self::Lifr
+        } else if feet < IFR_CEILING_FEET {
+            Self::Ifr
+        } else if feet <= MVFR_CEILING_FEET {
+            Self::Mvfr
+        } else {
+            Self::Vfr
+        }
+    }
+
+    fn from_visibility(visibility: Distance) -> Self {
+        let miles = visibility.miles();
+        if miles < LIFR_VISIBILITY_MILES {
+            Self::Lifr
+        } else if miles < IFR_VISIBILITY_MILES {
+            Self::Ifr
+        } else if miles <= MVFR_VISIBILITY_MILES {
+            Self::Mvfr
+        } else {
+            Self::Vfr
+        }
+    }
+
+    /// The worse of the categories of `ceiling` and `visibility`.  With
+    /// either one missing this is `Unknown`, unless the other is already
+    /// `Lifr`.
+    /// ```
+    /// use weather_util_rust::distance::Distance;
+    /// use weather_util_rust::flight_category::{Ceiling, FlightCategory};
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let ceiling = Ceiling::Height(Distance::from_feet(2000.0)?);
+    /// let visibility = Distance::from_miles(10.0)?;
+    /// assert_eq!(
+    ///     FlightCategory::from_conditions(Some(ceiling), Some(visibility)),
+    ///     FlightCategory::Mvfr
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_conditions(ceiling: Option<Ceiling>, visibility: Option<Distance>) -> Self {
+        let ceiling = ceiling.map(Self::from_ceiling);
+        let visibility = visibility.map(Self::from_visibility);
+        match (ceiling, visibility) {
+            (Some(ceiling), Some(visibility)) => ceiling.min(visibility),
+            (Some(Self::Lifr), None) | (None, Some(Self::Lifr)) => Self::Lifr,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for FlightCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Lifr => "LIFR",
+            Self::Ifr => "IFR",
+            Self::Mvfr => "MVFR",
+            Self::Vfr => "VFR",
+            Self::Unknown => "unknown",
+        };
+        f.write_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::convert::TryFrom;
+
+    use crate::{
+        distance::Distance,
+        flight_category::{Ceiling, FlightCategory},
+        humidity::Humidity,
+        temperature::Temperature,
+    };
+
+    fn category(feet: f64, miles: f64) -> Result<FlightCategory, Error> {
+        Ok(FlightCategory::from_conditions(
+            Some(Ceiling::Height(Distance::from_feet(feet)?)),
+            Some(Distance::from_miles(miles)?),
+        ))
+    }
+
+    #[test]
+    fn test_ceiling_boundaries() -> Result<(), Error> {
+        for (feet, expected) in &[
+            (0.0, FlightCategory::Lifr),
+            (499.0, FlightCategory::Lifr),
+            (500.0, FlightCategory::Ifr),
+            (999.0, FlightCategory::Ifr),
+            (1000.0, FlightCategory::Mvfr),
+            (3000.0, FlightCategory::Mvfr),
+            (3001.0, FlightCategory::Vfr),
+        ] {
+            assert_eq!(category(*feet, 10.0)?, *expected, "{} ft", feet);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_visibility_boundaries() -> Result<(), Error> {
+        for (miles, expected) in &[
+            (0.5, FlightCategory::Lifr),
+            (0.99, FlightCategory::Lifr),
+            (1.0, FlightCategory::Ifr),
+            (2.99, FlightCategory::Ifr),
+            (3.0, FlightCategory::Mvfr),
+            (5.0, FlightCategory::Mvfr),
+            (5.01, FlightCategory::Vfr),
+        ] {
+            assert_eq!(category(5000.0, *miles)?, *expected, "{} mi", miles);
+        }
+        // The worse of the two applies
+        assert_eq!(category(800.0, 4.0)?, FlightCategory::Ifr);
+        assert_eq!(category(2500.0, 0.5)?, FlightCategory::Lifr);
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown() -> Result<(), Error> {
+        let visibility = Distance::from_miles(10.0)?;
+        assert_eq!(
+            FlightCategory::from_conditions(None, Some(visibility)),
+            FlightCategory::Unknown
+        );
+        assert_eq!(
+            FlightCategory::from_conditions(Some(Ceiling::Unlimited), None),
+            FlightCategory::Unknown
+        );
+        assert_eq!(
+            FlightCategory::from_conditions(None, None),
+            FlightCategory::Unknown
+        );
+        // Nothing missing could make it worse than LIFR
+        let fog = Distance::from_miles(0.25)?;
+        assert_eq!(
+            FlightCategory::from_conditions(None, Some(fog)),
+            FlightCategory::Lifr
+        );
+        assert_eq!(
+            FlightCategory::from_conditions(Some(Ceiling::Unlimited), Some(visibility)),
+            FlightCategory::Vfr
+        );
+        assert_eq!(FlightCategory::Unknown.to_string(), "unknown");
+        assert_eq!(serde_json::to_string(&FlightCategory::Mvfr)?, "\"MVFR\"");
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_ceiling() -> Result<(), Error> {
+        let temp = Temperature::from_celcius(20.0)?;
+        assert_eq!(
+            Ceiling::estimate(temp, Humidity::try_from(50)?, 40),
+            Some(Ceiling::Unlimited)
+        );
+        // Saturated air has its cloud base at the ground
+        match Ceiling::estimate(temp, Humidity::try_from(100)?, 100) {
+            Some(Ceiling::Height(height)) => assert!(height.feet() < 1.0),
+            ceiling => panic!("{:?}", ceiling),
+        }
+        // 20°C at 50% humidity has a dew point of about 9.3°C
+        match Ceiling::estimate(temp, Humidity::try_from(50)?, 90) {
+            Some(Ceiling::Height(height)) => {
+                assert!((height.feet() - 4290.0).abs() < 20.0, "{}", height.feet())
+            }
+            ceiling => panic!("{:?}", ceiling),
+        }
+        assert_eq!(Ceiling::estimate(temp, Humidity::try_from(0)?, 90), None);
+        Ok(())
+    }
+}
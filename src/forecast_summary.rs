@@ -0,0 +1,830 @@
+use anyhow::Error;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::{
+    conditions::Condition, display_opts::DisplayOpts, locale::Locale, precipitation::Precipitation,
+    speed::Speed, temperature::Temperature, weather_forecast::FORECAST_DAYS,
+};
+
+/// Most comfortable daily high in Celcius, used when picking the best day
+const IDEAL_CELCIUS: f64 = 21.0;
+
+/// Penalty for a 100% chance of precipitation, in the same units as one
+/// degree Celcius away from `IDEAL_CELCIUS`
+const POP_PENALTY: f64 = 20.0;
+
+/// Penalty per mm of expected precipitation
+const PRECIPITATION_PENALTY: f64 = 1.0;
+
+/// Penalty per meter per second of wind
+const WIND_PENALTY: f64 = 1.0;
+
+/// Number of days after today considered for the trend
+pub const TREND_DAYS: usize = 3;
+
+/// Slope of the daily highs, in degrees Celcius per day, below which the
+/// trend is considered steady
+const STEADY_CELCIUS_PER_DAY: f64 = 0.5;
+
+/// Days with at least this chance of precipitation are kept by
+/// `ForecastSummary::rain_days`
+pub const RAIN_DAY_POP: f64 = 0.3;
+
+/// Days expecting more than this much rain and snow, in mm, are kept by
+/// `ForecastSummary::rain_days`
+pub const RAIN_DAY_MILLIMETERS: f64 = 0.5;
+
+/// Written by `write_days` with `rain_days_only` when no day qualifies
+pub const NO_RAIN_DAYS: &str = "No rain expected in the forecast window.";
+
+/// Forecast days this many or more days after today are noted as low
+/// confidence, i.e. those past the `FORECAST_DAYS` of the default forecast
+/// (whose last slots fall on today + `FORECAST_DAYS`).  This is only a rule
+/// of thumb for how quickly forecasts lose skill with their horizon, not a
+/// measure of their accuracy.
+pub const LOW_CONFIDENCE_DAYS: i64 = FORECAST_DAYS as i64 + 1;
+
+/// Written after the days from `LOW_CONFIDENCE_DAYS` on
+pub const LOW_CONFIDENCE_NOTE: &str = "(low confidence)";
+
+/// Overall direction of the daily highs
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Warming,
+    Cooling,
+    Steady,
+}
+
+impl Trend {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Warming => "warming",
+            Self::Cooling => "cooling",
+            Self::Steady => "steady",
+        }
+    }
+
+    pub fn arrow(self, ascii: bool) -> &'static str {
+        match (self, ascii) {
+            (Self::Warming, false) => "↑",
+            (Self::Cooling, false) => "↓",
+            (Self::Steady, false) => "→",
+            (Self::Warming, true) => "^",
+            (Self::Cooling, true) => "v",
+            (Self::Steady, true) => "->",
+        }
+    }
+}
+
+/// Temperature aggregated into the daily highs and lows
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TemperatureField {
+    #[default]
+    Temperature,
+    /// The apparent temperature
+    FeelsLike,
+}
+
+impl TemperatureField {
+    /// Heading written before the forecast
+    pub fn heading(self) -> &'static str {
+        match self {
+            Self::Temperature => "Forecast",
+            Self::FeelsLike => "Forecast (feels like)",
+        }
+    }
+}
+
+/// Conditions aggregated over a single (local) day
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DaySummary {
+    pub date: NaiveDate,
+    pub high: Temperature,
+    pub low: Temperature,
+    /// Mean temperature over the day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average: Option<Temperature>,
+    pub rain: Precipitation,
+    pub snow: Precipitation,
+    /// Highest probability of precipitation during the day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pop: Option<f64>,
+    /// Highest wind speed during the day
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_speed: Option<Speed>,
+    /// Number of forecast slots aggregated into the day, `None` for daily
+    /// forecasts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observations: Option<usize>,
+    /// Most severe condition code during the day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<u32>,
+}
+
+fn max_option<T: PartialOrd + Copy>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if b > a { b } else { a }),
+        (a, b) => a.or(b),
+    }
+}
+
+impl DaySummary {
+    /// Fold the conditions of `other`, a later part of the same day, into
+    /// this summary.  `average` can't be combined this way and is left as
+    /// is.
+    pub fn merge(&mut self, other: &Self) {
+        if other.high > self.high {
+            self.high = other.high;
+        }
+        if other.low < self.low {
+            self.low = other.low;
+        }
+        self.rain = self.rain + other.rain;
+        self.snow = self.snow + other.snow;
+        self.pop = max_option(self.pop, other.pop);
+        self.wind_speed = max_option(self.wind_speed, other.wind_speed);
+        self.observations = match (self.observations, other.observations) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(b),
+        };
+        self.condition = match (self.condition, other.condition) {
+            (Some(a), Some(b)) => Some(if Condition::severity(b) > Condition::severity(a) {
+                b
+            } else {
+                a
+            }),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// "Today" or "Tomorrow" relative to `today` (a date in the timezone of
+    /// the forecast), the abbreviated weekday for any other day
+    pub fn label(&self, today: NaiveDate) -> String {
+        self.localized_label(today, &Locale::default())
+    }
+
+    /// `label` with the weekday abbreviated in `locale`, in English if it
+    /// isn't supported so that the label still fits a table column
+    pub fn localized_label(&self, today: NaiveDate, locale: &Locale) -> String {
+        if self.date == today {
+            "Today".to_string()
+        } else if self.date == today + Duration::days(1) {
+            "Tomorrow".to_string()
+        } else {
+            locale.short_weekday(self.date)
+        }
+    }
+
+    /// Whether the day is `LOW_CONFIDENCE_DAYS` or more after `today`
+    pub fn is_low_confidence(&self, today: NaiveDate) -> bool {
+        self.date >= today + Duration::days(LOW_CONFIDENCE_DAYS)
+    }
+
+    /// Write out a single line with the high, low and any precipitation,
+    /// followed by the number of slots in verbose mode and the
+    /// `LOW_CONFIDENCE_NOTE` unless `hide_confidence` is set
+    fn write_day<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+        today: NaiveDate,
+    ) -> Result<(), Error> {
+        let (h, l, r, s) = (self.high, self.low, self.rain, self.snow);
+        let high = opts.temperatures(h, 1);
+        let high = format!("High: {} / {}", high.0, high.1);
+        let low = opts.temperatures(l, 1);
+        let low = format!("Low: {} / {}", low.0, low.1);
+        writeln!(
+            buf,
+            "\t{:8} {} {} {:25}{}{}",
+            self.localized_label(today, &opts.locale),
+            opts.paint_temperature(h, &format!("{:25}", high)),
+            opts.paint_temperature(l, &format!("{:25}", low)),
+            format!(
+                "{}{}",
+                if r.millimeters() > 0.0 {
+                    format!("Rain {}", opts.precipitation(r))
+                } else {
+                    "".to_string()
+                },
+                if s.millimeters() > 0.0 {
+                    format!("Snow {}", opts.precipitation(s))
+                } else {
+                    "".to_string()
+                },
+            ),
+            match self.observations {
+                Some(n) if opts.verbose => format!(" ({} obs)", n),
+                _ => "".to_string(),
+            },
+            if !opts.hide_confidence && self.is_low_confidence(today) {
+                format!(" {}", LOW_CONFIDENCE_NOTE)
+            } else {
+                "".to_string()
+            }
+        )
+        .map_err(Error::from)
+    }
+
+    /// Whether precipitation is likely (`RAIN_DAY_POP`) or expected to
+    /// amount to more than `RAIN_DAY_MILLIMETERS`
+    pub fn is_rain_day(&self) -> bool {
+        self.pop.is_some_and(|pop| pop >= RAIN_DAY_POP)
+            || self.rain.millimeters() + self.snow.millimeters() > RAIN_DAY_MILLIMETERS
+    }
+
+    /// How unpleasant the day is expected to be, lower is better: a mild
+    /// high with little chance of precipitation and a light wind scores
+    /// close to zero
+    pub fn discomfort(&self) -> f64 {
+        let temperature = (self.high.celcius() - IDEAL_CELCIUS).abs();
+        let pop = self.pop.unwrap_or(0.0) * POP_PENALTY;
+        let precipitation =
+            (self.rain.millimeters() + self.snow.millimeters()) * PRECIPITATION_PENALTY;
+        let wind = self.wind_speed.map_or(0.0, Speed::mps) * WIND_PENALTY;
+        temperature + pop + precipitation + wind
+    }
+}
+
+/// Per day summary of a forecast, in date order
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ForecastSummary {
+    pub days: Vec<DaySummary>,
+}
+
+impl ForecastSummary {
+    /// Write out the high, low and precipitation of each day followed by
+    /// the best, warmest and coldest days.  Days are labelled relative to `today`, the current
+    /// date in the timezone of the forecast.  With `compact_forecast` the
+    /// days are grouped into weeks (starting on Monday), each labelled with
+    /// its first day.  With `rain_days_only` only the `rain_days` are
+    /// written out.
+    pub fn write_days<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+        today: NaiveDate,
+    ) -> Result<(), Error> {
+        let rain_days;
+        let days = if opts.rain_days_only {
+            rain_days = self.rain_days();
+            if rain_days.days.is_empty() {
+                writeln!(buf, "\t{}", NO_RAIN_DAYS)?;
+            }
+            &rain_days.days
+        } else {
+            &self.days
+        };
+        let mut week = None;
+        let shown = opts.forecast_days.unwrap_or(days.len());
+        days.iter().take(shown).try_for_each(|day| {
+            if opts.compact_forecast {
+                let monday =
+                    day.date - Duration::days(day.date.weekday().num_days_from_monday().into());
+                if week != Some(monday) {
+                    if week.is_some() {
+                        writeln!(buf)?;
+                    }
+                    writeln!(
+                        buf,
+                        "\tWeek of {} {}",
+                        opts.locale.weekday_name(day.date),
+                        day.date.format("%Y-%m-%d")
+                    )?;
+                    week = Some(monday);
+                }
+            }
+            day.write_day(buf, opts, today)
+        })?;
+        if let Some(best) = self.best_day() {
+            writeln!(buf, "\nBest day: {}", opts.locale.weekday_name(best.date))?;
+        }
+        if let Some((warmest, coldest)) = self.extremes() {
+            writeln!(
+                buf,
+                "Warmest: {}, Coldest: {}",
+                opts.locale.weekday(warmest),
+                opts.locale.weekday(coldest)
+            )?;
+        }
+        if !self.days.is_empty() {
+            writeln!(
+                buf,
+                "Total precip this week: {}",
+                opts.precipitation(self.total_precipitation())
+            )?;
+        }
+        if let Some((trend, days)) = self.trend(TREND_DAYS) {
+            writeln!(
+                buf,
+                "Trend: {} {} over the next {} day{}",
+                trend.to_str(),
+                trend.arrow(opts.ascii),
+                days,
+                if days == 1 { "" } else { "s" }
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Only the days where `DaySummary::is_rain_day`
+    pub fn rain_days(&self) -> Self {
+        Self {
+            days: self
+                .days
+                .iter()
+                .filter(|day| day.is_rain_day())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Least squares slope of the daily highs of the first day and up to
+    /// `days` following days, in degrees Celcius per day
+    pub fn high_slope(&self, days: usize) -> Option<f64> {
+        let highs: Vec<_> = self
+            .days
+            .iter()
+            .take(days + 1)
+            .map(|day| day.high.celcius())
+            .collect();
+        if highs.len() < 2 {
+            return None;
+        }
+        let n = highs.len() as f64;
+        let mean_x = (n - 1.0) / 2.0;
+        let mean_y = highs.iter().sum::<f64>() / n;
+        let (cov, var) = highs
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(cov, var), (x, y)| {
+                let dx = x as f64 - mean_x;
+                (cov + dx * (y - mean_y), var + dx * dx)
+            });
+        Some(cov / var)
+    }
+
+    /// Direction of the daily highs over the next `days` days compared with
+    /// the first, along with the number of days actually available
+    pub fn trend(&self, days: usize) -> Option<(Trend, usize)> {
+        let slope = self.high_slope(days)?;
+        let trend = if slope >= STEADY_CELCIUS_PER_DAY {
+            Trend::Warming
+        } else if slope <= -STEADY_CELCIUS_PER_DAY {
+            Trend::Cooling
+        } else {
+            Trend::Steady
+        };
+        Some((trend, days.min(self.days.len() - 1)))
+    }
+
+    /// Lowest low and highest high of all days, `None` without any days
+    pub fn temperature_range(&self) -> Option<(Temperature, Temperature)> {
+        self.days.iter().fold(None, |range, day| {
+            Some(match range {
+                Some((low, high)) => (
+                    if day.low < low { day.low } else { low },
+                    if day.high > high { day.high } else { high },
+                ),
+                None => (day.low, day.high),
+            })
+        })
+    }
+
+    /// The most pleasant day of the forecast (see `DaySummary::discomfort`),
+    /// ties go to the earlier day
+    pub fn best_day(&self) -> Option<&DaySummary> {
+        self.days
+            .iter()
+            .fold(None, |best: Option<&DaySummary>, day| match best {
+                Some(best) if best.discomfort() <= day.discomfort() => Some(best),
+                _ => Some(day),
+            })
+    }
+
+    /// Percentile of `speed` among the (highest) wind speeds of the days,
+    /// like `WeatherForecast::wind_percentile` for forecasts that only
+    /// have days
+    pub fn wind_percentile(&self, speed: Speed) -> Option<f64> {
+        let speeds: Vec<_> = self.days.iter().filter_map(|day| day.wind_speed).collect();
+        speed.percentile(&speeds)
+    }
+
+    /// Rain and snow expected over all the days of the forecast
+    pub fn total_precipitation(&self) -> Precipitation {
+        self.days
+            .iter()
+            .fold(Precipitation::default(), |total, day| {
+                total + day.rain + day.snow
+            })
+    }
+
+    /// Dates of the day with the highest high and the day with the lowest
+    /// low, ties go to the earlier day
+    pub fn extremes(&self) -> Option<(NaiveDate, NaiveDate)> {
+        let first = self.days.first()?;
+        let (warmest, coldest) =
+            self.days
+                .iter()
+                .skip(1)
+                .fold((first, first), |(warmest, coldest), day| {
+                    (
+                        if day.high > warmest.high {
+                            day
+                        } else {
+                            warmest
+                        },
+                        if day.low < coldest.low { day } else { coldest },
+                    )
+                });
+        Some((warmest.date, coldest.date))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{Duration, NaiveDate};
+    use std::convert::TryFrom;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        forecast_summary::{DaySummary, ForecastSummary, Trend, LOW_CONFIDENCE_DAYS, TREND_DAYS},
+        locale::Locale,
+        precipitation::Precipitation,
+        speed::Speed,
+        temperature::Temperature,
+        units::Units,
+        weather_daily_forecast::WeatherDailyForecast,
+        weather_forecast::{Rain, Snow, WeatherForecast},
+        weather_onecall::WeatherOneCall,
+    };
+
+    fn summary_from_highs(highs: &[f64]) -> Result<ForecastSummary, Error> {
+        let first = NaiveDate::from_ymd_opt(2020, 1, 19).unwrap();
+        let days = highs
+            .iter()
+            .enumerate()
+            .map(|(idx, high)| {
+                Ok(DaySummary {
+                    date: first + Duration::days(idx as i64),
+                    high: Temperature::from_celcius(*high)?,
+                    low: Temperature::from_celcius(high - 10.0)?,
+                    average: None,
+                    rain: Precipitation::default(),
+                    snow: Precipitation::default(),
+                    pop: None,
+                    wind_speed: None,
+                    observations: None,
+                    condition: None,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(ForecastSummary { days })
+    }
+
+    #[test]
+    fn test_best_day() -> Result<(), Error> {
+        let mut onecall: WeatherOneCall =
+            serde_json::from_str(include_str!("../tests/onecall.json"))?;
+        let summary = onecall.get_forecast_summary();
+        assert_eq!(summary.days.len(), 8);
+        let best = summary.best_day().unwrap();
+        assert_eq!(best.date, NaiveDate::from_ymd_opt(2020, 1, 19).unwrap());
+        assert_eq!(best.date.format("%A").to_string(), "Sunday");
+
+        // A warm, dry Thursday
+        let daily = onecall.daily.as_mut().unwrap();
+        daily[4].temp.max = Temperature::from_celcius(20.0)?;
+        daily[4].pop = Some(0.0);
+        daily[4].rain = None;
+        let summary = onecall.get_forecast_summary();
+        let best = summary.best_day().unwrap();
+        assert_eq!(best.date.format("%A").to_string(), "Thursday");
+
+        // Saturday is identical, the earlier day wins
+        let daily = onecall.daily.as_mut().unwrap();
+        daily[6] = daily[4].clone();
+        daily[6].dt += Duration::days(2);
+        let summary = onecall.get_forecast_summary();
+        assert_eq!(summary.days[4].discomfort(), summary.days[6].discomfort());
+        let best = summary.best_day().unwrap();
+        assert_eq!(best.date.format("%A").to_string(), "Thursday");
+        Ok(())
+    }
+
+    #[test]
+    fn test_extremes() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let summary = forecast.get_forecast_summary();
+        let (warmest, coldest) = summary.extremes().unwrap();
+        assert_eq!(warmest, NaiveDate::from_ymd_opt(2020, 1, 24).unwrap());
+        assert_eq!(coldest, NaiveDate::from_ymd_opt(2020, 1, 20).unwrap());
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &DisplayOpts::default(), warmest)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\nWarmest: Fri, Coldest: Mon\n"), "{}", buf);
+
+        // The lows are ten degrees below the highs, the first of the tied
+        // days wins
+        let summary = summary_from_highs(&[10.0, 14.0, 8.0, 14.0, 8.0])?;
+        let (warmest, coldest) = summary.extremes().unwrap();
+        assert_eq!(warmest, summary.days[1].date);
+        assert_eq!(coldest, summary.days[2].date);
+
+        assert!(ForecastSummary::default().extremes().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_forecast_summary() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let summary = forecast.get_forecast_summary();
+        assert_eq!(summary.days.len(), 6);
+        let day = &summary.days[2];
+        assert_eq!(day.date, NaiveDate::from_ymd_opt(2020, 1, 21).unwrap());
+        assert_eq!(day.high, Temperature::try_from(272.65)?);
+        assert_eq!(day.rain, Precipitation::default());
+        assert!(day.wind_speed.is_some());
+        let average = day.average.unwrap();
+        assert!(average > day.low && average < day.high);
+        assert!(summary.days.iter().all(|day| day.average.is_some()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wind_percentile() -> Result<(), Error> {
+        let mut summary = summary_from_highs(&[10.0, 11.0, 12.0, 13.0])?;
+        assert!(summary.wind_percentile(Speed::from_mps(5.0)?).is_none());
+        for (day, mps) in summary.days.iter_mut().zip(&[2.0, 4.0, 6.0, 8.0]) {
+            day.wind_speed = Some(Speed::from_mps(*mps)?);
+        }
+        assert_eq!(summary.wind_percentile(Speed::from_mps(5.0)?), Some(50.0));
+        assert_eq!(summary.wind_percentile(Speed::from_mps(9.0)?), Some(100.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_total_precipitation() -> Result<(), Error> {
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let today = NaiveDate::from_ymd_opt(2020, 1, 19).unwrap();
+        let opts = DisplayOpts::default().with_units(Units::Metric);
+
+        // The fixture is a dry week
+        let summary = forecast.get_forecast_summary();
+        assert_eq!(summary.total_precipitation(), Precipitation::default());
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &opts, today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("\nTotal precip this week: 0.0 mm\n"),
+            "{}",
+            buf
+        );
+
+        forecast.list[5].rain = Some(Rain {
+            three_hour: Some(Precipitation::from_millimeters(2.0)?),
+        });
+        forecast.list[20].rain = Some(Rain {
+            three_hour: Some(Precipitation::from_millimeters(20.5)?),
+        });
+        forecast.list[30].snow = Some(Snow {
+            three_hour: Some(Precipitation::from_millimeters(1.5)?),
+        });
+        let summary = forecast.get_forecast_summary();
+        assert_eq!(
+            summary.total_precipitation(),
+            Precipitation::from_millimeters(24.0)?
+        );
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &opts, today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("\nTotal precip this week: 24.0 mm\n"),
+            "{}",
+            buf
+        );
+
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &DisplayOpts::default(), today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("\nTotal precip this week: 0.94 in\n"),
+            "{}",
+            buf
+        );
+
+        assert_eq!(
+            ForecastSummary::default().total_precipitation(),
+            Precipitation::default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_label() -> Result<(), Error> {
+        let summary = summary_from_highs(&[10.0, 11.0, 12.0, 13.0])?;
+        let today = summary.days[0].date;
+        let labels: Vec<_> = summary.days.iter().map(|day| day.label(today)).collect();
+        assert_eq!(labels, vec!["Today", "Tomorrow", "Tue", "Wed"]);
+
+        // An old forecast only gets weekdays
+        let today = today + Duration::days(7);
+        assert_eq!(summary.days[0].label(today), "Sun");
+        assert_eq!(summary.days[1].label(today), "Mon");
+
+        let french: Locale = "fr_FR".parse()?;
+        assert_eq!(summary.days[0].localized_label(today, &french), "dim.");
+        assert_eq!(summary.days[1].localized_label(today, &french), "lun.");
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &DisplayOpts::default().with_locale(french), today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.starts_with("\tdim.     High: "), "{}", buf);
+
+        // The weekday and date of an unsupported locale don't fit the table
+        let japanese: Locale = "ja_JP".parse()?;
+        assert_eq!(summary.days[0].localized_label(today, &japanese), "Sun");
+        let mut buf = Vec::new();
+        summary.write_days(
+            &mut buf,
+            &DisplayOpts::default().with_locale(japanese),
+            today,
+        )?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.starts_with("\tSun      High: "), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trend() -> Result<(), Error> {
+        let today = NaiveDate::from_ymd_opt(2020, 1, 19).unwrap();
+        // Later days beyond TREND_DAYS are ignored
+        let warming = summary_from_highs(&[10.0, 12.0, 11.0, 15.0, -20.0])?;
+        assert_eq!(warming.trend(TREND_DAYS), Some((Trend::Warming, 3)));
+        let mut buf = Vec::new();
+        warming.write_days(&mut buf, &DisplayOpts::default(), today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.ends_with("Trend: warming ↑ over the next 3 days\n"),
+            "{}",
+            buf
+        );
+
+        let cooling = summary_from_highs(&[15.0, 14.0, 12.0, 10.0])?;
+        assert_eq!(cooling.trend(TREND_DAYS), Some((Trend::Cooling, 3)));
+        let mut buf = Vec::new();
+        cooling.write_days(&mut buf, &DisplayOpts::default().with_ascii(true), today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.ends_with("Trend: cooling v over the next 3 days\n"),
+            "{}",
+            buf
+        );
+
+        // Small ups and downs cancel out
+        let steady = summary_from_highs(&[10.0, 10.5, 9.8, 10.2])?;
+        assert_eq!(steady.trend(TREND_DAYS), Some((Trend::Steady, 3)));
+        assert!(steady.high_slope(TREND_DAYS).unwrap().abs() < 0.5);
+
+        let short = summary_from_highs(&[10.0, 8.0])?;
+        assert_eq!(short.trend(TREND_DAYS), Some((Trend::Cooling, 1)));
+        assert_eq!(summary_from_highs(&[10.0])?.trend(TREND_DAYS), None);
+        let mut buf = Vec::new();
+        summary_from_highs(&[10.0])?.write_days(&mut buf, &DisplayOpts::default(), today)?;
+        assert!(!String::from_utf8(buf)?.contains("Trend"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_forecast() -> Result<(), Error> {
+        let forecast: WeatherDailyForecast =
+            serde_json::from_str(include_str!("../tests/forecast_daily.json"))?;
+        let summary = forecast.get_forecast_summary();
+        let today = summary.days[0].date;
+        let opts = DisplayOpts::default().with_compact_forecast(true);
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &opts, today)?;
+        let buf = String::from_utf8(buf)?;
+
+        // 2020-01-19 is a Sunday and 2020-02-03 a Monday, so a day on its
+        // own at either end and two full weeks in between
+        let weeks: Vec<_> = buf
+            .trim_end()
+            .split("\n\n")
+            .map(|week| week.lines().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(weeks.len(), 5, "{}", buf);
+        assert_eq!(weeks[0][0], "\tWeek of Sunday 2020-01-19");
+        assert_eq!(weeks[0].len(), 2);
+        assert_eq!(weeks[1][0], "\tWeek of Monday 2020-01-20");
+        assert_eq!(weeks[0][1].split(' ').next(), Some("\tToday"));
+        assert!(weeks[1][1].starts_with("\tTomorrow High: "));
+        assert!(weeks[1][2].starts_with("\tTue      High: "));
+        assert!(weeks[1][7].starts_with("\tSun      High: "));
+        assert_eq!(weeks[1].len(), 8);
+        assert_eq!(weeks[2][0], "\tWeek of Monday 2020-01-27");
+        assert_eq!(weeks[2].len(), 8);
+        assert_eq!(weeks[3], vec!["\tWeek of Monday 2020-02-03", weeks[3][1]]);
+        assert!(weeks[4][0].starts_with("Best day: "));
+
+        let mut buf = Vec::new();
+        let french = opts.with_locale("fr_FR".parse()?);
+        summary.write_days(&mut buf, &french, today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.starts_with("\tWeek of dimanche 2020-01-19\n"),
+            "{}",
+            buf
+        );
+        assert!(buf.contains("\tWeek of lundi 2020-01-20\n"), "{}", buf);
+        assert!(buf.contains("\nBest day: vendredi\n"), "{}", buf);
+
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &DisplayOpts::default(), today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(!buf.contains("Week of"), "{}", buf);
+        assert_eq!(buf.matches("\n\n").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rain_days() -> Result<(), Error> {
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let today = NaiveDate::from_ymd_opt(2020, 1, 19).unwrap();
+        let opts = DisplayOpts::default().with_rain_days_only(true);
+
+        let summary = forecast.get_forecast_summary();
+        assert!(summary.rain_days().days.is_empty());
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &opts, today)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.starts_with("\tNo rain expected in the forecast window.\n"),
+            "{}",
+            buf
+        );
+
+        // Likely rain Monday, only a slight chance Tuesday, 2 mm Wednesday
+        forecast.list[5].pop = Some(0.8);
+        forecast.list[13].pop = Some(0.1);
+        forecast.list[20].rain = Some(Rain {
+            three_hour: Some(Precipitation::from_millimeters(2.0)?),
+        });
+        let summary = forecast.get_forecast_summary();
+        let dates: Vec<_> = summary
+            .rain_days()
+            .days
+            .iter()
+            .map(|day| day.date.format("%a").to_string())
+            .collect();
+        assert_eq!(dates, vec!["Mon", "Wed"]);
+
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &opts, today)?;
+        let buf = String::from_utf8(buf)?;
+        let days: Vec<_> = buf.lines().filter(|l| l.contains("High: ")).collect();
+        assert_eq!(days.len(), 2, "{}", buf);
+        assert!(days[0].starts_with("\tTomorrow "), "{}", buf);
+        assert!(
+            days[1].starts_with("\tWed ") && days[1].contains("Rain "),
+            "{}",
+            buf
+        );
+        assert!(!buf.contains("No rain expected"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_low_confidence() -> Result<(), Error> {
+        let summary = summary_from_highs(&[10.0; 8])?;
+        let today = summary.days[0].date;
+        let mut buf = Vec::new();
+        summary.write_days(&mut buf, &DisplayOpts::default(), today)?;
+        let buf = String::from_utf8(buf)?;
+        let days: Vec<_> = buf.lines().filter(|l| l.contains("High: ")).collect();
+        assert_eq!(days.len(), 8, "{}", buf);
+        for (idx, day) in days.iter().enumerate() {
+            let low_confidence = idx as i64 >= LOW_CONFIDENCE_DAYS;
+            assert_eq!(
+                day.ends_with(" (low confidence)"),
+                low_confidence,
+                "{}",
+                day
+            );
+        }
+
+        let mut buf = Vec::new();
+        let opts = DisplayOpts::default().with_hide_confidence(true);
+        summary.write_days(&mut buf, &opts, today)?;
+        assert!(!String::from_utf8(buf)?.contains("low confidence"));
+        Ok(())
+    }
+}
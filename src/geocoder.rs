@@ -0,0 +1,294 @@
+use anyhow::{format_err, Error};
+use std::convert::TryFrom;
+
+use crate::{
+    http_client::{HttpFuture, MaybeSync},
+    latitude::Latitude,
+    longitude::Longitude,
+    weather_api::WeatherApi,
+    weather_data::Coord,
+};
+
+/// Resolves a location query, e.g. a place name or a Plus Code, to
+/// coordinates.  Geocoders are tried in the order they were registered with
+/// `Geocoders`, the first one accepting a query resolves it.
+pub trait Geocoder: MaybeSync {
+    /// Whether the query is in a format this geocoder understands
+    fn accepts(&self, _query: &str) -> bool {
+        true
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> HttpFuture<'a, Result<Coord, Error>>;
+}
+
+/// Registered geocoders, in order of precedence
+#[derive(Default)]
+pub struct Geocoders {
+    geocoders: Vec<Box<dyn Geocoder>>,
+}
+
+impl Geocoders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<G: Geocoder + 'static>(mut self, geocoder: G) -> Self {
+        self.geocoders.push(Box::new(geocoder));
+        self
+    }
+
+    /// Coordinates of `query` from the first geocoder accepting it, `None`
+    /// if none does
+    pub async fn resolve(&self, query: &str) -> Result<Option<Coord>, Error> {
+        match self.geocoders.iter().find(|g| g.accepts(query)) {
+            Some(geocoder) => geocoder.resolve(query).await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The OpenWeatherMap direct geocoding api, resolving place names such as
+/// `London,GB` to the first match
+#[derive(Clone, Debug)]
+pub struct OpenWeatherMapGeocoder {
+    api: WeatherApi,
+}
+
+impl OpenWeatherMapGeocoder {
+    pub fn new(api: WeatherApi) -> Self {
+        Self { api }
+    }
+}
+
+/// Error for a query without geocoding matches, suggesting how to narrow it
+/// down
+pub(crate) fn no_location_found(query: &str) -> Error {
+    let query = query.trim();
+    if query.contains(',') {
+        format_err!(
+            "No location found for '{}'; check the spelling and that the country code is a two \
+             letter ISO 3166 code, e.g. 'London,GB'",
+            query
+        )
+    } else {
+        format_err!(
+            "No location found for '{0}'; try adding a country code, e.g. '{0},US' or '{0},GB'",
+            query
+        )
+    }
+}
+
+impl Geocoder for OpenWeatherMapGeocoder {
+    fn resolve<'a>(&'a self, query: &'a str) -> HttpFuture<'a, Result<Coord, Error>> {
+        Box::pin(async move {
+            let location = self
+                .api
+                .get_geocoding(query)
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| no_location_found(query))?;
+            Ok(Coord {
+                lon: location.lon,
+                lat: location.lat,
+            })
+        })
+    }
+}
+
+/// Digits of an Open Location Code
+const PLUS_CODE_ALPHABET: &str = "23456789CFGHJMPQRVWX";
+
+/// Position of the `+` separator in a full Plus Code
+const PLUS_CODE_SEPARATOR_POSITION: usize = 8;
+
+/// Number of digits encoded as latitude/longitude pairs, any further digits
+/// refine a 4x5 grid
+const PLUS_CODE_PAIR_DIGITS: usize = 10;
+
+/// Decodes full Open Location Codes (Plus Codes) such as `849VCWC8+R9` to
+/// the center of their area, without any requests.  Short codes (e.g.
+/// `CWC8+R9 Mountain View`) need a reference location and aren't accepted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PlusCodeGeocoder;
+
+impl PlusCodeGeocoder {
+    fn digit(c: char) -> Option<usize> {
+        PLUS_CODE_ALPHABET.find(c)
+    }
+
+    /// Digits of `code` without the separator and padding, if it is a valid
+    /// full code
+    fn digits(code: &str) -> Option<Vec<usize>> {
+        let code = code.trim().to_uppercase();
+        if code.find('+') != Some(PLUS_CODE_SEPARATOR_POSITION) || code.matches('+').count() != 1 {
+            return None;
+        }
+        let (head, tail) = code.split_at(PLUS_CODE_SEPARATOR_POSITION);
+        let tail = &tail[1..];
+        let digits = head.trim_end_matches('0');
+        let padded = digits.len() < head.len();
+        if padded && (digits.len() % 2 == 1 || digits.is_empty() || !tail.is_empty()) {
+            return None;
+        }
+        if tail.len() == 1 {
+            return None;
+        }
+        let digits: Vec<_> = digits
+            .chars()
+            .chain(tail.chars())
+            .map(Self::digit)
+            .collect::<Option<_>>()?;
+        // The first pair can't exceed 180 degrees latitude / 360 longitude
+        if digits[0] >= 9 || digits.get(1).is_some_and(|&d| d >= 18) {
+            return None;
+        }
+        Some(digits)
+    }
+
+    /// Latitude and longitude of the center of `code`
+    /// ```
+    /// use weather_util_rust::geocoder::PlusCodeGeocoder;
+    /// let (lat, lon) = PlusCodeGeocoder::decode("8FVC9G8F+6X").unwrap();
+    /// assert!((lat - 47.365562).abs() < 1e-6);
+    /// assert!((lon - 8.524937).abs() < 1e-6);
+    /// ```
+    pub fn decode(code: &str) -> Option<(f64, f64)> {
+        let digits = Self::digits(code)?;
+        let (mut lat, mut lon) = (-90.0, -180.0);
+        let mut resolution = 20.0;
+        let (mut lat_resolution, mut lon_resolution) = (resolution, resolution);
+        for pair in digits[..digits.len().min(PLUS_CODE_PAIR_DIGITS)].chunks(2) {
+            lat += pair[0] as f64 * resolution;
+            lon += pair[1] as f64 * resolution;
+            lat_resolution = resolution;
+            lon_resolution = resolution;
+            resolution /= 20.0;
+        }
+        for &digit in digits.iter().skip(PLUS_CODE_PAIR_DIGITS) {
+            lat_resolution /= 5.0;
+            lon_resolution /= 4.0;
+            lat += (digit / 4) as f64 * lat_resolution;
+            lon += (digit % 4) as f64 * lon_resolution;
+        }
+        let lat = (lat + lat_resolution / 2.0).min(90.0);
+        let lon = lon + lon_resolution / 2.0;
+        Some((lat, lon))
+    }
+}
+
+impl Geocoder for PlusCodeGeocoder {
+    fn accepts(&self, query: &str) -> bool {
+        Self::digits(query).is_some()
+    }
+
+    fn resolve<'a>(&'a self, query: &'a str) -> HttpFuture<'a, Result<Coord, Error>> {
+        Box::pin(async move {
+            let (lat, lon) = Self::decode(query)
+                .ok_or_else(|| format_err!("{} is not a valid plus code", query))?;
+            Ok(Coord {
+                lon: Longitude::try_from(lon)?,
+                lat: Latitude::try_from(lat)?,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    #[cfg(feature = "native-client")]
+    use crate::mock_server::{http_response, serve};
+    use crate::{
+        geocoder::{Geocoder, Geocoders, OpenWeatherMapGeocoder, PlusCodeGeocoder},
+        weather_api::WeatherApi,
+    };
+
+    #[test]
+    fn test_plus_code() {
+        let (lat, lon) = PlusCodeGeocoder::decode("849VCWC8+R9").unwrap();
+        assert!((lat - 37.4220625).abs() < 1e-9);
+        assert!((lon + 122.0840625).abs() < 1e-9);
+        // Lowercase and the grid refinement digit after the pairs
+        let (lat, lon) = PlusCodeGeocoder::decode("849vcwc8+r9c").unwrap();
+        assert!((lat - 37.4220625).abs() < 1e-9);
+        assert!((lon + 122.084109375).abs() < 1e-9);
+        // Padded codes cover a larger area
+        assert_eq!(PlusCodeGeocoder::decode("87G80000+"), Some((40.5, -73.5)));
+
+        let geocoder = PlusCodeGeocoder;
+        assert!(geocoder.accepts("8FVC9G8F+6X"));
+        for query in &[
+            "New York",
+            "CWC8+R9",
+            "849VCWC8+R",
+            "849VCWC8R9",
+            "87G8000+",
+            "87G80000+R9",
+            "X49VCWC8+R9",
+        ] {
+            assert!(!geocoder.accepts(query), "{}", query);
+        }
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_geocoders() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![http_response(
+            "200 OK",
+            &[],
+            include_str!("../tests/geocoding.json"),
+        )])
+        .await?;
+        let api = WeatherApi::new("0123456789abcdef", &endpoint, "data/2.5/").with_retries(0);
+        let geocoders = Geocoders::new()
+            .register(PlusCodeGeocoder)
+            .register(OpenWeatherMapGeocoder::new(api));
+
+        let coord = geocoders.resolve("8FVC9G8F+6X").await?.unwrap();
+        assert!((f64::from(coord.lat) - 47.3655625).abs() < 1e-9);
+
+        let coord = geocoders.resolve("New York,US").await?.unwrap();
+        assert_eq!(f64::from(coord.lat), 40.7127281);
+        assert_eq!(f64::from(coord.lon), -74.0060152);
+        let requests = handle.await?;
+        assert_eq!(requests.len(), 1);
+        assert!(
+            requests[0].starts_with("GET /geo/1.0/direct?q=New+York%2CUS&limit=1&APPID="),
+            "{}",
+            requests[0]
+        );
+
+        assert!(Geocoders::new().resolve("New York").await?.is_none());
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_no_location_found() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![
+            http_response("200 OK", &[], "[]"),
+            http_response("200 OK", &[], "[]"),
+        ])
+        .await?;
+        let api = WeatherApi::new("0123456789abcdef", &endpoint, "data/2.5/").with_retries(0);
+        let geocoders = Geocoders::new().register(OpenWeatherMapGeocoder::new(api));
+
+        let err = geocoders.resolve("Nowhereville").await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No location found for 'Nowhereville'; try adding a country code, e.g. \
+             'Nowhereville,US' or 'Nowhereville,GB'"
+        );
+        let err = geocoders.resolve("Nowhereville,XX").await.unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("No location found for 'Nowhereville,XX'; check the spelling"),
+            "{}",
+            err
+        );
+        assert_eq!(handle.await?.len(), 2);
+        Ok(())
+    }
+}
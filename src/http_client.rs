@@ -0,0 +1,156 @@
+#[cfg(feature = "native-client")]
+use anyhow::format_err;
+use anyhow::Error;
+use std::{collections::HashMap, time::Duration};
+use url::Url;
+
+#[cfg(feature = "native-client")]
+use log::warn;
+
+#[cfg(feature = "native-client")]
+use crate::weather_api::USER_AGENT;
+
+/// Future returned by a `HttpClient`, boxed so that the trait can be used as
+/// a trait object.  Futures of a browser `fetch` aren't `Send`.
+#[cfg(not(target_arch = "wasm32"))]
+pub type HttpFuture<'a, T> = futures::future::BoxFuture<'a, T>;
+#[cfg(target_arch = "wasm32")]
+pub type HttpFuture<'a, T> = futures::future::LocalBoxFuture<'a, T>;
+
+/// `Send + Sync` everywhere but `wasm32`
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSync: Send + Sync {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send + Sync> MaybeSync for T {}
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSync {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSync for T {}
+
+/// Status, headers and body of a response
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    /// Keyed by the lowercase header name
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: HashMap::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn with_header(mut self, key: &str, value: &str) -> Self {
+        self.headers.insert(key.to_lowercase(), value.into());
+        self
+    }
+
+    /// Value of the header `key`, ignoring case
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.get(&key.to_lowercase()).map(String::as_str)
+    }
+}
+
+/// The HTTP layer of `WeatherApi`.  `ReqwestClient` is used natively, under
+/// `wasm32` (without the `native-client` feature) an implementation using
+/// the browser `fetch` can be supplied with `WeatherApi::from_http_client`.
+pub trait HttpClient: MaybeSync {
+    /// Make a GET request, a response with an error status is still `Ok`
+    fn get<'a>(&'a self, url: &'a Url) -> HttpFuture<'a, Result<HttpResponse, Error>>;
+
+    /// Wait for `duration` before a request is retried
+    fn delay(&self, duration: Duration) -> HttpFuture<'_, ()>;
+}
+
+/// `HttpClient` backed by a `reqwest` Client
+#[cfg(feature = "native-client")]
+#[derive(Debug, Clone)]
+pub struct ReqwestClient {
+    client: reqwest::Client,
+}
+
+/// Falls back to a plain `reqwest` Client without the `USER_AGENT` (with a
+/// warning written to stderr by the command line tool) when
+/// `ReqwestClient::new` fails
+#[cfg(feature = "native-client")]
+impl Default for ReqwestClient {
+    fn default() -> Self {
+        Self::new().unwrap_or_else(|e| {
+            warn!("{}, using the default client", e);
+            Self::from_client(reqwest::Client::new())
+        })
+    }
+}
+
+#[cfg(feature = "native-client")]
+impl ReqwestClient {
+    /// Client sending the `USER_AGENT`, failing if it can't be built
+    pub fn new() -> Result<Self, Error> {
+        reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .build()
+            .map(Self::from_client)
+            .map_err(|e| format_err!("Failed to build the HTTP client: {}", e))
+    }
+
+    pub fn from_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg(feature = "native-client")]
+impl HttpClient for ReqwestClient {
+    fn get<'a>(&'a self, url: &'a Url) -> HttpFuture<'a, Result<HttpResponse, Error>> {
+        Box::pin(async move {
+            let res = self.client.get(url.clone()).send().await?;
+            let status = res.status().as_u16();
+            let headers = res
+                .headers()
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((key.as_str().to_lowercase(), value.to_str().ok()?.into()))
+                })
+                .collect();
+            let body = res.text().await?;
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+
+    fn delay(&self, duration: Duration) -> HttpFuture<'_, ()> {
+        Box::pin(tokio::time::delay_for(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "native-client")]
+    use anyhow::Error;
+
+    use crate::http_client::HttpResponse;
+    #[cfg(feature = "native-client")]
+    use crate::http_client::ReqwestClient;
+
+    #[test]
+    fn test_header() {
+        let res = HttpResponse::new(429, "").with_header("Retry-After", "60");
+        assert_eq!(res.header("retry-after"), Some("60"));
+        assert_eq!(res.header("RETRY-AFTER"), Some("60"));
+        assert_eq!(res.header("x-ratelimit-limit"), None);
+    }
+
+    #[cfg(feature = "native-client")]
+    #[test]
+    fn test_reqwest_client() -> Result<(), Error> {
+        ReqwestClient::new()?;
+        Ok(())
+    }
+}
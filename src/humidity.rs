@@ -11,7 +11,7 @@ pub struct Humidity(i64);
 impl TryFrom<i64> for Humidity {
     type Error = Error;
     fn try_from(item: i64) -> Result<Self, Self::Error> {
-        if item >= 0 && item <= 100 {
+        if (0..=100).contains(&item) {
             Ok(Self(item))
         } else {
             Err(format_err!("{} is not a valid relative humidity", item))
@@ -0,0 +1,43 @@
+use anyhow::Error;
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::{
+    latitude::Latitude,
+    longitude::Longitude,
+    weather_api::{WeatherLocation, USER_AGENT},
+};
+
+/// Geolocation service used by `--here` unless `IP_LOCATION_URL` is set
+pub const DEFAULT_IP_LOCATION_URL: &str = "http://ip-api.com/json";
+
+/// Give up on the geolocation service after this long
+const IP_LOCATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Response of the geolocation service, both `lat`/`lon` (ip-api.com) and
+/// `latitude`/`longitude` (ipapi.co) are understood
+#[derive(Deserialize, Debug)]
+struct IpLocation {
+    #[serde(alias = "latitude")]
+    lat: Latitude,
+    #[serde(alias = "longitude")]
+    lon: Longitude,
+}
+
+/// Approximate location of the caller's public IP address, as reported by
+/// the geolocation service at `url`
+pub async fn get_ip_location(url: &str) -> Result<WeatherLocation, Error> {
+    let client = Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(IP_LOCATION_TIMEOUT)
+        .build()?;
+    let location: IpLocation = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(WeatherLocation::from_lat_lon(location.lat, location.lon))
+}
@@ -0,0 +1,346 @@
+use anyhow::{format_err, Error};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+
+use crate::{
+    display_opts::round_coordinate,
+    weather_daily_forecast::WeatherDailyForecast,
+    weather_data::WeatherData,
+    weather_forecast::{HourlyTemperature, WeatherForecast},
+    weather_onecall::WeatherOneCall,
+};
+
+/// Version of the `--json` output structure, bump this whenever the
+/// structure changes so that downstream consumers can adapt.
+pub const SCHEMA_VERSION: u32 = 4;
+
+/// Fields holding timestamps, these are epoch seconds in the api responses
+/// but are written out as RFC 3339 strings
+const TIMESTAMP_FIELDS: [&str; 3] = ["dt", "sunrise", "sunset"];
+
+/// Fields holding coordinates, rounded with `coord_precision`
+const COORDINATE_FIELDS: [&str; 2] = ["lat", "lon"];
+
+/// Document written out by `--json`, `schema_version` is always the first
+/// field.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JsonOutput {
+    pub schema_version: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<WeatherData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forecast: Option<WeatherForecast>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily_forecast: Option<WeatherDailyForecast>,
+    /// Hourly temperatures interpolated from `forecast`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hourly_temperatures: Option<Vec<HourlyTemperature>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub onecall: Option<WeatherOneCall>,
+    /// Round coordinates to this many decimal places when written out, the
+    /// full precision is kept if unset.  Not part of the document.
+    #[serde(skip)]
+    pub coord_precision: Option<usize>,
+}
+
+impl Default for JsonOutput {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            current: None,
+            forecast: None,
+            daily_forecast: None,
+            hourly_temperatures: None,
+            onecall: None,
+            coord_precision: None,
+        }
+    }
+}
+
+impl JsonOutput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_current(mut self, current: WeatherData) -> Self {
+        self.current = Some(current);
+        self
+    }
+
+    pub fn with_forecast(mut self, forecast: WeatherForecast) -> Self {
+        self.forecast = Some(forecast);
+        self
+    }
+
+    pub fn with_daily_forecast(mut self, daily_forecast: WeatherDailyForecast) -> Self {
+        self.daily_forecast = Some(daily_forecast);
+        self
+    }
+
+    pub fn with_hourly_temperatures(mut self, hourly_temperatures: Vec<HourlyTemperature>) -> Self {
+        self.hourly_temperatures = Some(hourly_temperatures);
+        self
+    }
+
+    pub fn with_onecall(mut self, onecall: WeatherOneCall) -> Self {
+        self.onecall = Some(onecall);
+        self
+    }
+
+    pub fn with_coord_precision(mut self, coord_precision: usize) -> Self {
+        self.coord_precision = Some(coord_precision);
+        self
+    }
+
+    /// Write out pretty printed JSON followed by a newline, with timestamps
+    /// as RFC 3339 strings
+    pub fn write_json<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
+        let mut value = serde_json::to_value(self)?;
+        timestamps_to_rfc3339(&mut value);
+        if let Some(precision) = self.coord_precision {
+            round_coordinates(&mut value, precision);
+        }
+        serde_json::to_writer_pretty(&mut *buf, &value)?;
+        writeln!(buf)?;
+        Ok(())
+    }
+
+    /// Parse a document written by `write_json`.  Only the current
+    /// `SCHEMA_VERSION` is accepted, as any other version has a different
+    /// structure.
+    pub fn from_json(text: &str) -> Result<Self, Error> {
+        let mut value: Value = serde_json::from_str(text)
+            .map_err(|e| format_err!("Failed to parse JSON output: {}", e))?;
+        match value.get("schema_version").and_then(Value::as_u64) {
+            Some(version) if version == u64::from(SCHEMA_VERSION) => {}
+            Some(version) => {
+                return Err(format_err!(
+                    "JSON output schema version {} is not supported, expected {}",
+                    version,
+                    SCHEMA_VERSION
+                ))
+            }
+            None => {
+                return Err(format_err!(
+                    "JSON output has no schema_version, expected {}",
+                    SCHEMA_VERSION
+                ))
+            }
+        }
+        timestamps_from_rfc3339(&mut value);
+        serde_json::from_value(value).map_err(|e| format_err!("Failed to parse JSON output: {}", e))
+    }
+}
+
+/// Replace epoch seconds in any of `TIMESTAMP_FIELDS` with RFC 3339 strings
+fn timestamps_to_rfc3339(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let timestamp = if TIMESTAMP_FIELDS.contains(&key.as_str()) {
+                    value
+                        .as_i64()
+                        .and_then(|t| Utc.timestamp_opt(t, 0).single())
+                } else {
+                    None
+                };
+                match timestamp {
+                    Some(dt) => *value = dt.to_rfc3339_opts(SecondsFormat::Secs, true).into(),
+                    None => timestamps_to_rfc3339(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(timestamps_to_rfc3339),
+        _ => {}
+    }
+}
+
+/// Round numbers in any of `COORDINATE_FIELDS` to `precision` decimal places
+fn round_coordinates(value: &mut Value, precision: usize) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let coordinate = if COORDINATE_FIELDS.contains(&key.as_str()) {
+                    value.as_f64()
+                } else {
+                    None
+                };
+                match coordinate {
+                    Some(c) => *value = round_coordinate(c, precision).into(),
+                    None => round_coordinates(value, precision),
+                }
+            }
+        }
+        Value::Array(values) => values
+            .iter_mut()
+            .for_each(|value| round_coordinates(value, precision)),
+        _ => {}
+    }
+}
+
+/// Reverse of `timestamps_to_rfc3339`, RFC 3339 strings in any of
+/// `TIMESTAMP_FIELDS` become epoch seconds again
+fn timestamps_from_rfc3339(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let timestamp = if TIMESTAMP_FIELDS.contains(&key.as_str()) {
+                    value
+                        .as_str()
+                        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                } else {
+                    None
+                };
+                match timestamp {
+                    Some(dt) => *value = dt.timestamp().into(),
+                    None => timestamps_from_rfc3339(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(timestamps_from_rfc3339),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use serde_json::Value;
+    use std::convert::TryFrom;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        json_output::{JsonOutput, SCHEMA_VERSION},
+        latitude::Latitude,
+        longitude::Longitude,
+        output_format::{OutputFormat, TextFormat},
+        weather_data::WeatherData,
+        weather_forecast::WeatherForecast,
+    };
+
+    #[test]
+    fn test_schema_version() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+
+        let output = JsonOutput::new().with_current(data.clone());
+        let s = serde_json::to_string(&output)?;
+        assert!(s.starts_with(r#"{"schema_version":4,"current":"#), "{}", s);
+
+        let output = JsonOutput::new().with_current(data).with_forecast(forecast);
+        let mut buf = Vec::new();
+        output.write_json(&mut buf)?;
+        let value: Value = serde_json::from_slice(&buf)?;
+        assert_eq!(value["schema_version"], SCHEMA_VERSION);
+        assert_eq!(value["current"]["name"], "Astoria");
+        assert_eq!(value["forecast"]["list"].as_array().map(Vec::len), Some(40));
+        assert!(value.get("onecall").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rfc3339_timestamps() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+
+        let mut buf = Vec::new();
+        JsonOutput::new()
+            .with_current(data.clone())
+            .with_forecast(forecast)
+            .write_json(&mut buf)?;
+        let s = String::from_utf8(buf)?;
+        assert!(s.starts_with("{\n  \"schema_version\": 4,"), "{}", s);
+        let value: Value = serde_json::from_str(&s)?;
+        assert_eq!(value["current"]["dt"], "2020-01-19T18:23:42Z");
+        assert_eq!(value["current"]["sys"]["sunrise"], "2020-01-19T12:15:59Z");
+        assert_eq!(value["current"]["sys"]["sunset"], "2020-01-19T21:56:14Z");
+        assert_eq!(value["forecast"]["list"][0]["dt"], "2020-01-19T21:00:00Z");
+        assert_eq!(value["current"]["timezone"], -18000);
+
+        // Serializing the data directly keeps the api's epoch seconds
+        let value = serde_json::to_value(&data)?;
+        assert_eq!(value["dt"], 1_579_458_222);
+        Ok(())
+    }
+
+    #[test]
+    fn test_coord_precision() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        data.coord.lat = Latitude::try_from(40.712_776)?;
+        data.coord.lon = Longitude::try_from(-74.005_974)?;
+
+        let mut buf = Vec::new();
+        JsonOutput::new()
+            .with_current(data.clone())
+            .write_json(&mut buf)?;
+        let value: Value = serde_json::from_slice(&buf)?;
+        assert_eq!(value["current"]["coord"]["lat"], 40.712_776);
+        assert_eq!(value["current"]["coord"]["lon"], -74.005_974);
+
+        let mut buf = Vec::new();
+        JsonOutput::new()
+            .with_current(data.clone())
+            .with_coord_precision(2)
+            .write_json(&mut buf)?;
+        let value: Value = serde_json::from_slice(&buf)?;
+        assert_eq!(value["current"]["coord"]["lat"], 40.71);
+        assert_eq!(value["current"]["coord"]["lon"], -74.01);
+        // only the output is rounded
+        assert_eq!(f64::from(data.coord.lat), 40.712_776);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let format = TextFormat::new(DisplayOpts::default()).with_forecast(true);
+        let mut expected = Vec::new();
+        format.render(Some(&data), Some(&forecast), &mut expected)?;
+
+        let mut buf = Vec::new();
+        JsonOutput::new()
+            .with_current(data)
+            .with_forecast(forecast)
+            .write_json(&mut buf)?;
+        let saved = JsonOutput::from_json(&String::from_utf8(buf.clone())?)?;
+        assert_eq!(
+            saved.current.as_ref().map(|data| data.dt.timestamp()),
+            Some(1_579_458_222)
+        );
+        let mut rendered = Vec::new();
+        format.render(
+            saved.current.as_ref(),
+            saved.forecast.as_ref(),
+            &mut rendered,
+        )?;
+        assert_eq!(String::from_utf8(rendered)?, String::from_utf8(expected)?);
+
+        // Writing the parsed document gives the same document
+        let mut again = Vec::new();
+        saved.write_json(&mut again)?;
+        assert_eq!(
+            serde_json::from_slice::<Value>(&again)?,
+            serde_json::from_slice::<Value>(&buf)?
+        );
+
+        let mut value: Value = serde_json::from_slice(&buf)?;
+        value["schema_version"] = (SCHEMA_VERSION + 1).into();
+        let e = JsonOutput::from_json(&value.to_string()).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            format!(
+                "JSON output schema version {} is not supported, expected {}",
+                SCHEMA_VERSION + 1,
+                SCHEMA_VERSION
+            )
+        );
+        assert!(JsonOutput::from_json(include_str!("../tests/weather.json")).is_err());
+        Ok(())
+    }
+}
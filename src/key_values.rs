@@ -0,0 +1,87 @@
+use anyhow::{format_err, Error};
+use std::convert::TryFrom;
+
+use crate::{latitude::Latitude, longitude::Longitude};
+
+/// Parse `key=value` entries separated by semicolons, e.g. `a=1;b=2`,
+/// turning the trimmed key and value of each into an item with `parse`.
+/// Blank entries are skipped.  An entry that is missing the `=` or either
+/// side, or that `parse` rejects, is not a valid `what`, `expected` being
+/// the format it should have.
+/// ```
+/// use weather_util_rust::key_values::parse_key_values;
+/// # use anyhow::Error;
+/// # fn main() -> Result<(), Error> {
+/// let entries = parse_key_values("a = 1; b=2;", "entry", "name=number", |key, value| {
+///     Ok((key.to_string(), value.parse::<u32>()?))
+/// })?;
+/// assert_eq!(entries, vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_key_values<T>(
+    s: &str,
+    what: &str,
+    expected: &str,
+    parse: impl Fn(&str, &str) -> Result<T, Error>,
+) -> Result<Vec<T>, Error> {
+    s.split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let invalid = || format!("{} is not a valid {}, expected {}", entry, what, expected);
+            let (key, value) = entry
+                .split_once('=')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(key, value)| !key.is_empty() && !value.is_empty())
+                .ok_or_else(|| format_err!("{}", invalid()))?;
+            parse(key, value).map_err(|e| format_err!("{}: {}", invalid(), e))
+        })
+        .collect()
+}
+
+/// Parse a `lat,lon` pair, e.g. `40.76,-73.93`
+pub fn parse_lat_lon(s: &str) -> Result<(Latitude, Longitude), Error> {
+    let (lat, lon) = s
+        .split_once(',')
+        .ok_or_else(|| format_err!("expected a latitude and longitude"))?;
+    let lat = Latitude::try_from(lat.trim().parse::<f64>()?)?;
+    let lon = Longitude::try_from(lon.trim().parse::<f64>()?)?;
+    Ok((lat, lon))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::key_values::{parse_key_values, parse_lat_lon};
+
+    #[test]
+    fn test_parse_key_values() -> Result<(), Error> {
+        let parse = |s: &str| {
+            parse_key_values(s, "entry", "name=number", |key, value| {
+                Ok((key.to_string(), value.parse::<u32>()?))
+            })
+        };
+        assert_eq!(parse("")?, Vec::new());
+        assert_eq!(parse(" a=1 ;; b = 2 ")?.len(), 2);
+        for invalid in &["a", "=1", "a=", "a=x"] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            parse("a=1;b").unwrap_err().to_string(),
+            "b is not a valid entry, expected name=number"
+        );
+        assert!(parse("a=x")
+            .unwrap_err()
+            .to_string()
+            .starts_with("a=x is not a valid entry, expected name=number: "));
+
+        let (lat, lon) = parse_lat_lon("40.76, -73.93")?;
+        assert_eq!((f64::from(lat), f64::from(lon)), (40.76, -73.93));
+        for invalid in &["40.76", "north,-73.93", "91,0"] {
+            assert!(parse_lat_lon(invalid).is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use anyhow::{format_err, Error};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    display_opts::DisplayOpts,
+    humidity::Humidity,
+    speed::Speed,
+    temperature::{Temperature, TemperatureDelta},
+    timestamp,
+    units::Units,
+    weather_data::{Coord, WeatherData},
+};
+
+/// Runs within this many degrees of latitude and longitude are considered
+/// to be for the same location
+pub const SAME_LOCATION_DEGREES: f64 = 0.01;
+
+/// Decimal places of the changes unless `DisplayOpts::precision` is set
+const DELTA_PRECISION: usize = 1;
+
+/// Current conditions of the previous `--diff` run, kept in a state file so
+/// that the next run can show what changed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LastRun {
+    pub name: String,
+    pub coord: Coord,
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    pub temp: Temperature,
+    pub humidity: Humidity,
+    pub wind_speed: Speed,
+}
+
+impl From<&WeatherData> for LastRun {
+    fn from(data: &WeatherData) -> Self {
+        Self {
+            name: data.name.clone(),
+            coord: data.coord.clone(),
+            dt: data.dt,
+            temp: data.main.temp,
+            humidity: data.main.humidity,
+            wind_speed: data.wind.speed,
+        }
+    }
+}
+
+/// Changes of the current conditions since the `LastRun`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deltas {
+    pub temp: TemperatureDelta,
+    /// Difference in percentage points
+    pub humidity: i64,
+    /// Difference in meters per second
+    pub wind_speed: f64,
+}
+
+impl LastRun {
+    /// `${HOME}/.local/share/weather_util/last_run.json` (or the platform
+    /// equivalent)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|d| d.join("weather_util").join("last_run.json"))
+    }
+
+    /// The previous run stored at `path`, `None` before the first run
+    pub fn read(path: &Path) -> Result<Option<Self>, Error> {
+        match fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| format_err!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Whether `data` is for (about) the same place as this run
+    pub fn same_location(&self, data: &WeatherData) -> bool {
+        let lat = f64::from(self.coord.lat) - f64::from(data.coord.lat);
+        let lon = f64::from(self.coord.lon) - f64::from(data.coord.lon);
+        lat.abs() < SAME_LOCATION_DEGREES && lon.abs() < SAME_LOCATION_DEGREES
+    }
+
+    /// Changes from this run to `data`, `None` if `data` is for another
+    /// location
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::last_run::LastRun;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_json(&buf)?;
+    /// let last_run = LastRun::from(&data);
+    /// let deltas = last_run.deltas(&data).unwrap();
+    /// assert_eq!(deltas.humidity, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn deltas(&self, data: &WeatherData) -> Option<Deltas> {
+        if !self.same_location(data) {
+            return None;
+        }
+        Some(Deltas {
+            temp: data.main.temp - self.temp,
+            humidity: i64::from(data.main.humidity) - i64::from(self.humidity),
+            wind_speed: data.wind.speed.mps() - self.wind_speed.mps(),
+        })
+    }
+}
+
+/// `" (+3.0 since last check)"`, or nothing if the rounded change is zero
+fn since_last_check(delta: f64, precision: usize) -> String {
+    let text = format!("{:+.*}", precision, delta);
+    if text.parse::<f64>().ok() == Some(0.0) {
+        String::new()
+    } else {
+        format!(" ({} since last check)", text)
+    }
+}
+
+impl Deltas {
+    /// Annotation of the temperature line, in the primary units of `opts`
+    pub fn temperature(&self, opts: &DisplayOpts) -> String {
+        let delta = match opts.units {
+            Units::Imperial => self.temp.fahrenheit(),
+            Units::Metric => self.temp.celcius(),
+        };
+        since_last_check(delta, opts.precision.unwrap_or(DELTA_PRECISION))
+    }
+
+    /// Annotation of the humidity line
+    pub fn humidity(&self) -> String {
+        since_last_check(self.humidity as f64, 0)
+    }
+
+    /// Annotation of the wind line, in the wind unit of `opts`
+    pub fn wind_speed(&self, opts: &DisplayOpts) -> String {
+        let delta =
+            Speed::from_mps(self.wind_speed.abs()).map_or(0.0, |speed| speed.value(opts.wind_unit));
+        since_last_check(
+            delta.copysign(self.wind_speed),
+            opts.wind_precision.unwrap_or(DELTA_PRECISION),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::{convert::TryFrom, env::temp_dir, fs};
+
+    use crate::{
+        display_opts::DisplayOpts, humidity::Humidity, last_run::LastRun, latitude::Latitude,
+        speed::Speed, temperature::Temperature, units::Units, weather_data::WeatherData,
+    };
+
+    #[test]
+    fn test_deltas() -> Result<(), Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let path = temp_dir()
+            .join(format!("weather_util_last_run_{}", std::process::id()))
+            .join("last_run.json");
+        assert!(LastRun::read(&path)?.is_none());
+
+        let mut baseline = LastRun::from(&data);
+        baseline.temp = Temperature::from_fahrenheit(data.main.temp.fahrenheit() - 3.0)?;
+        baseline.humidity = Humidity::try_from(i64::from(data.main.humidity) + 5)?;
+        baseline.wind_speed = data.wind.speed;
+        baseline.write(&path)?;
+
+        let baseline = LastRun::read(&path)?.unwrap();
+        let deltas = baseline.deltas(&data).unwrap();
+        assert!((deltas.temp.fahrenheit() - 3.0).abs() < 1e-9);
+        assert_eq!(deltas.humidity, -5);
+        assert_eq!(deltas.wind_speed, 0.0);
+
+        let opts = DisplayOpts::default();
+        assert_eq!(deltas.temperature(&opts), " (+3.0 since last check)");
+        assert_eq!(
+            deltas.temperature(&opts.clone().with_units(Units::Metric)),
+            " (+1.7 since last check)"
+        );
+        assert_eq!(deltas.humidity(), " (-5 since last check)");
+        assert_eq!(deltas.wind_speed(&opts), "");
+
+        let mut elsewhere = baseline.clone();
+        elsewhere.coord.lat = Latitude::try_from(f64::from(data.coord.lat) + 1.0)?;
+        assert!(elsewhere.deltas(&data).is_none());
+
+        let mut calmer = baseline;
+        calmer.wind_speed = Speed::from_mph(data.wind.speed.mph() + 2.0)?;
+        let deltas = calmer.deltas(&data).unwrap();
+        assert_eq!(deltas.wind_speed(&opts), " (-2.0 since last check)");
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+}
@@ -18,10 +18,10 @@ pub struct Latitude(f64);
 impl TryFrom<f64> for Latitude {
     type Error = Error;
     fn try_from(item: f64) -> Result<Self, Self::Error> {
-        if item >= -90.0 && item <= 90.0 {
+        if (-90.0..=90.0).contains(&item) {
             Ok(Self(item))
         } else {
-            Err(format_err!("{} is not a valid latitude"))
+            Err(format_err!("{} is not a valid latitude", item))
         }
     }
 }
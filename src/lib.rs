@@ -30,38 +30,117 @@
 //!     --lon <lon>                      Longitude (must also specify Latitude)
 //! -z, --zipcode <zipcode>              Zipcode (optional)
 
+/// Suggestions of what to wear for the current conditions
+pub mod advice;
+/// Coordinates of airports by ICAO or IATA code
+pub mod airports;
+/// Terminal colors and temperature color bands
+pub mod color;
+/// OpenWeatherMap condition codes with their names and icons
+pub mod conditions;
 /// Configuration data
 pub mod config;
 /// Direction in degrees
 pub mod direction;
+/// Cache of api responses on disk
+pub mod disk_cache;
+/// Options controlling the formatted output
+pub mod display_opts;
 /// Distance in meters
 pub mod distance;
+/// Elevation above sea level
+pub mod elevation;
+/// Aviation flight categories from the ceiling and visibility
+pub mod flight_category;
+/// Per day summary of a forecast
+pub mod forecast_summary;
+/// Resolving location queries to coordinates
+pub mod geocoder;
+/// HTTP layer used by `WeatherApi`
+pub mod http_client;
 /// Relative Humidity in percent
 pub mod humidity;
+/// Approximate location from the public IP address
+#[cfg(feature = "native-client")]
+pub mod ip_location;
+/// Versioned JSON output
+pub mod json_output;
+/// Parsing `key=value` entries of the configuration
+pub mod key_values;
+/// Conditions of the previous run, for `--diff`
+pub mod last_run;
 /// Latitude
 pub mod latitude;
+/// Weekday names for `--locale`
+pub mod locale;
+/// Friendly names for coordinates
+pub mod location_names;
+/// Named locations for `--preset`
+pub mod location_presets;
+/// Writing log messages to stderr for the command line tool
+pub mod logger;
 /// Longitude
 pub mod longitude;
+/// Cache of api responses shared within a process
+pub mod memory_cache;
+#[cfg(all(test, feature = "native-client"))]
+mod mock_server;
+/// Formats of the current conditions and forecast
+pub mod output_format;
 /// Precipitation (rain/snow) in mm
 pub mod precipitation;
 /// Pressure module: conversions between hPa, kPa, Pa
 pub mod pressure;
+/// Current conditions as Prometheus metrics
+pub mod prometheus;
+/// Place a location query resolved to
+pub mod resolved_location;
+/// Check for response fields lost by deserialization
+pub mod round_trip;
+/// Validating the configuration with `--check`
+pub mod self_check;
 /// Speed as meters per second
 pub mod speed;
+/// Natural language summary of the current conditions
+pub mod summary;
 /// Temperature module: conversions between Kelvin, Ceclius and Fahrenheit
 pub mod temperature;
+/// Output presets
+pub mod theme;
 /// Serialize/Deserialize Unix Timetstamp to/from `DateTime`
 pub mod timestamp;
 /// Timezone offset as seconds before / after UTC
 pub mod timezone;
-/// Reqwest Client
+/// Interactive terminal interface for `--tui`
+#[cfg(feature = "tui")]
+pub mod tui;
+/// Systems of units used for display
+pub mod units;
+/// Detecting condition changes between polls of `--watch`
+pub mod watch;
+/// Client for the openweathermap.org api
 pub mod weather_api;
+/// Representation of the stations in a bounding box from openweathermap.org
+pub mod weather_box;
+/// Representation of the daily (up to 16 day) Weather Forecast from
+/// openweathermap.org
+pub mod weather_daily_forecast;
 /// Representation of Weather Data from openweathermap.org
 pub mod weather_data;
+/// Representation of the city search results from openweathermap.org
+pub mod weather_find;
 /// Representation of Weather Forecast from openweathermap.org
 pub mod weather_forecast;
+/// Representation of One Call data from openweathermap.org
+pub mod weather_onecall;
 /// CLI App Options and implementation
+#[cfg(feature = "native-client")]
 pub mod weather_opts;
+/// Parsing the XML (`mode=xml`) current conditions from openweathermap.org
+#[cfg(feature = "xml")]
+pub mod weather_xml;
+/// Daily forecast summary exported for dashboards
+pub mod week_export;
 
 use anyhow::{format_err, Error};
 use log::error;
@@ -0,0 +1,295 @@
+use anyhow::{format_err, Error};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+/// Abbreviated weekday names from Monday to Sunday by language
+const WEEKDAY_ABBREVIATIONS: [(&str, [&str; 7]); 7] = [
+    ("en", ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]),
+    ("de", ["Mo.", "Di.", "Mi.", "Do.", "Fr.", "Sa.", "So."]),
+    ("es", ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"]),
+    (
+        "fr",
+        ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    ),
+    ("it", ["lun", "mar", "mer", "gio", "ven", "sab", "dom"]),
+    ("nl", ["ma", "di", "wo", "do", "vr", "za", "zo"]),
+    (
+        "pt",
+        ["seg.", "ter.", "qua.", "qui.", "sex.", "sáb.", "dom."],
+    ),
+];
+
+/// Full weekday names from Monday to Sunday by language, in the order of
+/// `WEEKDAY_ABBREVIATIONS`
+const WEEKDAY_NAMES: [(&str, [&str; 7]); 7] = [
+    (
+        "en",
+        [
+            "Monday",
+            "Tuesday",
+            "Wednesday",
+            "Thursday",
+            "Friday",
+            "Saturday",
+            "Sunday",
+        ],
+    ),
+    (
+        "de",
+        [
+            "Montag",
+            "Dienstag",
+            "Mittwoch",
+            "Donnerstag",
+            "Freitag",
+            "Samstag",
+            "Sonntag",
+        ],
+    ),
+    (
+        "es",
+        [
+            "lunes",
+            "martes",
+            "miércoles",
+            "jueves",
+            "viernes",
+            "sábado",
+            "domingo",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+        ],
+    ),
+    (
+        "it",
+        [
+            "lunedì",
+            "martedì",
+            "mercoledì",
+            "giovedì",
+            "venerdì",
+            "sabato",
+            "domenica",
+        ],
+    ),
+    (
+        "nl",
+        [
+            "maandag",
+            "dinsdag",
+            "woensdag",
+            "donderdag",
+            "vrijdag",
+            "zaterdag",
+            "zondag",
+        ],
+    ),
+    (
+        "pt",
+        [
+            "segunda-feira",
+            "terça-feira",
+            "quarta-feira",
+            "quinta-feira",
+            "sexta-feira",
+            "sábado",
+            "domingo",
+        ],
+    ),
+];
+
+/// Locale the forecast weekdays are named in, written like `LANG`, e.g.
+/// `fr`, `fr_FR` or `fr-FR.UTF-8`.  Only the language is used, `C` and
+/// `POSIX` are English.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(into = "String", try_from = "String")]
+pub struct Locale {
+    tag: String,
+    language: String,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self {
+            tag: "en".into(),
+            language: "en".into(),
+        }
+    }
+}
+
+impl Locale {
+    /// Lowercase language code, e.g. `fr`
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    fn weekday_abbreviations(&self) -> Option<&'static [&'static str; 7]> {
+        Self::find_names(&WEEKDAY_ABBREVIATIONS, &self.language)
+    }
+
+    fn find_names(
+        table: &'static [(&str, [&'static str; 7])],
+        language: &str,
+    ) -> Option<&'static [&'static str; 7]> {
+        table
+            .iter()
+            .find(|(entry, _)| *entry == language)
+            .map(|(_, names)| names)
+    }
+
+    /// Whether weekdays can be named in the language of the locale
+    pub fn is_supported(&self) -> bool {
+        self.weekday_abbreviations().is_some()
+    }
+
+    /// Abbreviated weekday of `date`, for a locale that isn't supported the
+    /// English abbreviation followed by the ISO date
+    /// ```
+    /// use weather_util_rust::locale::Locale;
+    /// # use chrono::NaiveDate;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let date = NaiveDate::from_ymd_opt(2020, 1, 20).unwrap();
+    /// assert_eq!("fr_FR.UTF-8".parse::<Locale>()?.weekday(date), "lun.");
+    /// assert_eq!("ja_JP".parse::<Locale>()?.weekday(date), "Mon 2020-01-20");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn weekday(&self, date: NaiveDate) -> String {
+        if self.is_supported() {
+            self.short_weekday(date)
+        } else {
+            format!("{} {}", date.format("%a"), date.format("%Y-%m-%d"))
+        }
+    }
+
+    /// Abbreviated weekday of `date`, in English for a locale that isn't
+    /// supported
+    pub fn short_weekday(&self, date: NaiveDate) -> String {
+        let index = date.weekday().num_days_from_monday() as usize;
+        match self.weekday_abbreviations() {
+            Some(names) => names[index].to_string(),
+            None => date.format("%a").to_string(),
+        }
+    }
+
+    /// Full weekday name of `date`, in English for a locale that isn't
+    /// supported
+    /// ```
+    /// use weather_util_rust::locale::Locale;
+    /// # use chrono::NaiveDate;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let date = NaiveDate::from_ymd_opt(2020, 1, 20).unwrap();
+    /// assert_eq!("de".parse::<Locale>()?.weekday_name(date), "Montag");
+    /// assert_eq!("ja_JP".parse::<Locale>()?.weekday_name(date), "Monday");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn weekday_name(&self, date: NaiveDate) -> String {
+        let index = date.weekday().num_days_from_monday() as usize;
+        match Self::find_names(&WEEKDAY_NAMES, &self.language) {
+            Some(names) => names[index].to_string(),
+            None => date.format("%A").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.tag)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tag = s.trim();
+        let language = tag.split(['_', '-', '.', '@']).next().unwrap_or_default();
+        if language.is_empty() || !language.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(format_err!(
+                "{} is not a valid locale, expected e.g. fr or fr_FR",
+                s
+            ));
+        }
+        let language = match language.to_ascii_lowercase().as_str() {
+            "c" | "posix" => "en".to_string(),
+            language => language.to_string(),
+        };
+        Ok(Self {
+            tag: tag.into(),
+            language,
+        })
+    }
+}
+
+impl TryFrom<String> for Locale {
+    type Error = Error;
+    fn try_from(item: String) -> Result<Self, Self::Error> {
+        item.parse()
+    }
+}
+
+impl From<Locale> for String {
+    fn from(item: Locale) -> Self {
+        item.tag
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{Duration, NaiveDate};
+
+    use crate::locale::{Locale, WEEKDAY_ABBREVIATIONS, WEEKDAY_NAMES};
+
+    #[test]
+    fn test_locale() -> Result<(), Error> {
+        let monday = NaiveDate::from_ymd_opt(2020, 1, 20).unwrap();
+        let week = |locale: &Locale| -> Vec<String> {
+            (0..7)
+                .map(|i| locale.weekday(monday + Duration::days(i)))
+                .collect()
+        };
+
+        let english = Locale::default();
+        assert_eq!(
+            week(&english),
+            vec!["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]
+        );
+        assert_eq!("C".parse::<Locale>()?.language(), "en");
+
+        let french: Locale = "fr_FR".parse()?;
+        assert_eq!(french.language(), "fr");
+        assert_eq!(
+            week(&french),
+            vec!["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."]
+        );
+        assert_eq!("FR-ca".parse::<Locale>()?.weekday(monday), "lun.");
+        assert_eq!(french.to_string(), "fr_FR");
+
+        let unsupported: Locale = "ja_JP".parse()?;
+        assert!(!unsupported.is_supported());
+        assert_eq!(unsupported.weekday(monday), "Mon 2020-01-20");
+        assert_eq!(unsupported.short_weekday(monday), "Mon");
+
+        // Every supported language has full names as well
+        for (language, _) in WEEKDAY_ABBREVIATIONS.iter() {
+            let locale: Locale = language.parse()?;
+            assert!(Locale::find_names(&WEEKDAY_NAMES, language).is_some());
+            assert_ne!(locale.weekday_name(monday), locale.short_weekday(monday));
+        }
+        assert_eq!(english.weekday_name(monday), "Monday");
+        assert_eq!(french.weekday_name(monday), "lundi");
+
+        for invalid in &["", "_FR", "fr1"] {
+            assert!(invalid.parse::<Locale>().is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+}
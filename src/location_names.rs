@@ -0,0 +1,128 @@
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, str::FromStr};
+
+use crate::{
+    key_values::{parse_key_values, parse_lat_lon},
+    latitude::Latitude,
+    longitude::Longitude,
+    weather_data::WeatherData,
+};
+
+/// Coordinates within this many degrees of latitude and longitude of an
+/// entry get its name
+pub const NAME_TOLERANCE_DEGREES: f64 = 0.01;
+
+/// Friendly names for coordinates, replacing the (sometimes wrong or blank)
+/// name the api reports for them.  Written as `lat,lon=name` entries
+/// separated by semicolons, e.g. `40.76,-73.93=Home;51.51,-0.13=Office`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(into = "String", try_from = "String")]
+pub struct LocationNames {
+    names: Vec<(Latitude, Longitude, String)>,
+}
+
+impl LocationNames {
+    /// Name of the first entry within `NAME_TOLERANCE_DEGREES` of `lat` and
+    /// `lon`
+    /// ```
+    /// use weather_util_rust::location_names::LocationNames;
+    /// # use std::convert::TryFrom;
+    /// # use weather_util_rust::{latitude::Latitude, longitude::Longitude};
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let names: LocationNames = "40.76,-73.93=Home".parse()?;
+    /// let (lat, lon) = (Latitude::try_from(40.763)?, Longitude::try_from(-73.925)?);
+    /// assert_eq!(names.get_name(lat, lon), Some("Home"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_name(&self, lat: Latitude, lon: Longitude) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|(entry_lat, entry_lon, _)| {
+                (f64::from(*entry_lat) - f64::from(lat)).abs() <= NAME_TOLERANCE_DEGREES
+                    && (f64::from(*entry_lon) - f64::from(lon)).abs() <= NAME_TOLERANCE_DEGREES
+            })
+            .map(|(_, _, name)| name.as_str())
+    }
+
+    /// Replace the name of `data` if its coordinates have an entry
+    pub fn apply(&self, data: &mut WeatherData) {
+        if let Some(name) = self.get_name(data.coord.lat, data.coord.lon) {
+            data.name = name.into();
+        }
+    }
+}
+
+impl FromStr for LocationNames {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let names = parse_key_values(s, "location name", "lat,lon=name", |coord, name| {
+            let (lat, lon) = parse_lat_lon(coord)?;
+            Ok((lat, lon, name.to_string()))
+        })?;
+        Ok(Self { names })
+    }
+}
+
+impl TryFrom<String> for LocationNames {
+    type Error = Error;
+    fn try_from(item: String) -> Result<Self, Self::Error> {
+        item.parse()
+    }
+}
+
+impl From<LocationNames> for String {
+    fn from(item: LocationNames) -> Self {
+        item.names
+            .iter()
+            .map(|(lat, lon, name)| format!("{},{}={}", lat, lon, name))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::convert::TryFrom;
+
+    use crate::{
+        latitude::Latitude, location_names::LocationNames, longitude::Longitude,
+        weather_data::WeatherData,
+    };
+
+    #[test]
+    fn test_location_names() -> Result<(), Error> {
+        let names: LocationNames = "40.76,-73.93=Home; 51.51,-0.13 = The Office ".parse()?;
+        let mut data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        assert_eq!(data.name, "Astoria");
+        names.apply(&mut data);
+        assert_eq!(data.name, "Home");
+
+        let (lat, lon) = (Latitude::try_from(51.505)?, Longitude::try_from(-0.125)?);
+        assert_eq!(names.get_name(lat, lon), Some("The Office"));
+        let lat = Latitude::try_from(51.48)?;
+        assert_eq!(names.get_name(lat, lon), None);
+
+        // Nothing matches elsewhere, the api name is kept
+        let mut data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        LocationNames::default().apply(&mut data);
+        assert_eq!(data.name, "Astoria");
+
+        let s: String = names.into();
+        assert_eq!(s, "40.76,-73.93=Home;51.51,-0.13=The Office");
+
+        for invalid in &[
+            "40.76,-73.93",
+            "40.76=Home",
+            "40.76,-73.93=",
+            "north,-73.93=Home",
+            "91,0=Pole",
+        ] {
+            assert!(invalid.parse::<LocationNames>().is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+}
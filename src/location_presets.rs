@@ -0,0 +1,130 @@
+use anyhow::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, io::Write, str::FromStr};
+
+use crate::key_values::parse_key_values;
+
+/// Named locations for `--preset`, each a `lat,lon` pair, zipcode or city
+/// name.  Written as `name=query` entries separated by semicolons, e.g.
+/// `home=40.71,-74.01;work=Boston,MA,US`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(into = "String", try_from = "String")]
+pub struct LocationPresets {
+    presets: Vec<(String, String)>,
+}
+
+impl LocationPresets {
+    /// Query stored for the preset `name`
+    /// ```
+    /// use weather_util_rust::location_presets::LocationPresets;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let presets: LocationPresets = "home=40.71,-74.01;work=Boston,MA,US".parse()?;
+    /// assert_eq!(presets.get("work"), Some("Boston,MA,US"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.presets
+            .iter()
+            .find(|(preset, _)| preset == name.trim())
+            .map(|(_, query)| query.as_str())
+    }
+
+    /// Same as `get`, but an unknown `name` is an error listing the known
+    /// presets
+    pub fn resolve(&self, name: &str) -> Result<&str, Error> {
+        self.get(name).ok_or_else(|| {
+            if self.presets.is_empty() {
+                format_err!(
+                    "Unknown preset '{}', no presets are configured",
+                    name.trim()
+                )
+            } else {
+                let names: Vec<_> = self.presets.iter().map(|(name, _)| name.as_str()).collect();
+                format_err!(
+                    "Unknown preset '{}', expected one of {}",
+                    name.trim(),
+                    names.join(", ")
+                )
+            }
+        })
+    }
+
+    /// Write out each preset with its query, in the configured order
+    pub fn write_presets<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
+        if self.presets.is_empty() {
+            writeln!(buf, "No presets configured")?;
+        }
+        for (name, query) in &self.presets {
+            writeln!(buf, "{}\t{}", name, query)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for LocationPresets {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let presets = parse_key_values(s, "preset", "name=location", |name, query| {
+            Ok((name.to_string(), query.to_string()))
+        })?;
+        Ok(Self { presets })
+    }
+}
+
+impl TryFrom<String> for LocationPresets {
+    type Error = Error;
+    fn try_from(item: String) -> Result<Self, Self::Error> {
+        item.parse()
+    }
+}
+
+impl From<LocationPresets> for String {
+    fn from(item: LocationPresets) -> Self {
+        item.presets
+            .iter()
+            .map(|(name, query)| format!("{}={}", name, query))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::location_presets::LocationPresets;
+
+    #[test]
+    fn test_location_presets() -> Result<(), Error> {
+        let presets: LocationPresets = "home = 40.71,-74.01; work=Boston,MA,US;".parse()?;
+        assert_eq!(presets.resolve("home")?, "40.71,-74.01");
+        assert_eq!(presets.resolve(" work ")?, "Boston,MA,US");
+        assert_eq!(
+            presets.resolve("gym").unwrap_err().to_string(),
+            "Unknown preset 'gym', expected one of home, work"
+        );
+        assert_eq!(
+            LocationPresets::default()
+                .resolve("home")
+                .unwrap_err()
+                .to_string(),
+            "Unknown preset 'home', no presets are configured"
+        );
+
+        let mut buf = Vec::new();
+        presets.write_presets(&mut buf)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "home\t40.71,-74.01\nwork\tBoston,MA,US\n"
+        );
+
+        let s: String = presets.into();
+        assert_eq!(s, "home=40.71,-74.01;work=Boston,MA,US");
+        for invalid in &["home", "=Boston", "home="] {
+            assert!(invalid.parse::<LocationPresets>().is_err(), "{}", invalid);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::{format_err, Error};
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes the message of every record up to `log::max_level` to stderr
+#[derive(Debug, Clone, Copy)]
+pub struct StderrLogger;
+
+static LOGGER: StderrLogger = StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install `StderrLogger` for records up to `level`, e.g. warnings about
+/// rotated api keys with `LevelFilter::Warn`
+pub fn init(level: LevelFilter) -> Result<(), Error> {
+    log::set_logger(&LOGGER).map_err(|e| format_err!("Failed to set up logging: {}", e))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+/// Messages logged by the tests, which share a single logger
+#[cfg(test)]
+static CAPTURED: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+#[cfg(test)]
+struct CaptureLogger;
+
+#[cfg(test)]
+static CAPTURE_LOGGER: CaptureLogger = CaptureLogger;
+
+#[cfg(test)]
+impl Log for CaptureLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        CAPTURED.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+/// Messages logged so far, keeping every record from the first call on
+#[cfg(test)]
+pub(crate) fn captured_logs() -> Vec<String> {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        log::set_logger(&CAPTURE_LOGGER).ok();
+        log::set_max_level(LevelFilter::Debug);
+    });
+    CAPTURED.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use log::{Level, Log, Metadata};
+
+    use crate::logger::{captured_logs, StderrLogger};
+
+    #[test]
+    fn test_stderr_logger() {
+        // the tests log everything up to debug
+        captured_logs();
+        let enabled = |level| StderrLogger.enabled(&Metadata::builder().level(level).build());
+        assert!(enabled(Level::Warn));
+        assert!(enabled(Level::Debug));
+        assert!(!enabled(Level::Trace));
+    }
+}
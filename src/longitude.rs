@@ -11,10 +11,10 @@ pub struct Longitude(f64);
 impl TryFrom<f64> for Longitude {
     type Error = Error;
     fn try_from(item: f64) -> Result<Self, Self::Error> {
-        if item >= -180.0 && item <= 180.0 {
+        if (-180.0..=180.0).contains(&item) {
             Ok(Self(item))
         } else {
-            Err(format_err!("{} is not a valid latitude"))
+            Err(format_err!("{} is not a valid latitude", item))
         }
     }
 }
@@ -1,11 +1,23 @@
 use anyhow::Error;
+use log::LevelFilter;
 
-use weather_util_rust::{config::Config, weather_opts::WeatherOpts};
+use weather_util_rust::{
+    config::Config,
+    logger,
+    watch::{ConditionsChanged, CONDITIONS_CHANGED_EXIT_CODE},
+    weather_opts::WeatherOpts,
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    logger::init(LevelFilter::Warn)?;
     let config = Config::init_config()?;
 
-    WeatherOpts::parse_opts(&config).await?;
+    if let Err(e) = WeatherOpts::parse_opts(&config).await {
+        if e.downcast_ref::<ConditionsChanged>().is_some() {
+            std::process::exit(CONDITIONS_CHANGED_EXIT_CODE);
+        }
+        return Err(e);
+    }
     Ok(())
 }
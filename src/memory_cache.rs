@@ -0,0 +1,202 @@
+use anyhow::Error;
+use chrono::{DateTime, Duration, Utc};
+use futures::{lock::Mutex as AsyncMutex, Future};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// Responses are kept for 10 minutes by default, about how often
+/// openweathermap.org updates the current conditions
+pub const DEFAULT_TTL_SECONDS: i64 = 600;
+
+type CacheKey = (String, Vec<(&'static str, String)>);
+
+/// Response text and when it stops being served
+type CacheSlot = Arc<AsyncMutex<Option<(DateTime<Utc>, String)>>>;
+
+/// In process cache of raw api responses for programs making many requests,
+/// e.g. servers embedding `WeatherApi`.  Clones share the same entries, so a
+/// single cache can be handed to several `WeatherApi`s (or tasks).
+/// Concurrent requests for the same key wait for the first one instead of
+/// each making a request.
+#[derive(Clone)]
+pub struct MemoryCache {
+    entries: Arc<Mutex<HashMap<CacheKey, CacheSlot>>>,
+    ttl: Duration,
+}
+
+impl Default for MemoryCache {
+    fn default() -> Self {
+        Self {
+            entries: Arc::default(),
+            ttl: Duration::seconds(DEFAULT_TTL_SECONDS),
+        }
+    }
+}
+
+impl fmt::Debug for MemoryCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MemoryCache(entries={},ttl={})", self.len(), self.ttl)
+    }
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve responses for `ttl` after they were fetched
+    pub fn with_ttl(self, ttl: Duration) -> Self {
+        Self { ttl, ..self }
+    }
+
+    fn slot(&self, command: &str, options: &[(&'static str, String)]) -> CacheSlot {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .entry((command.into(), options.to_vec()))
+            .or_default()
+            .clone()
+    }
+
+    /// Cached response to `command` with `options` if it hasn't expired,
+    /// otherwise the result of `fetch`, which is stored if it succeeded.
+    /// Callers asking for the same response meanwhile wait for `fetch`.
+    pub async fn get_or_fetch<F>(
+        &self,
+        command: &str,
+        options: &[(&'static str, String)],
+        fetch: F,
+    ) -> Result<String, Error>
+    where
+        F: Future<Output = Result<String, Error>>,
+    {
+        let slot = self.slot(command, options);
+        let mut entry = slot.lock().await;
+        if let Some((expires, text)) = entry.as_ref() {
+            if *expires > Utc::now() {
+                return Ok(text.clone());
+            }
+        }
+        let text = fetch.await?;
+        *entry = Some((Utc::now() + self.ttl, text.clone()));
+        Ok(text)
+    }
+
+    /// Drop the response to `command` with `options`, e.g. because it
+    /// couldn't be parsed
+    pub fn remove(&self, command: &str, options: &[(&'static str, String)]) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(&(command.into(), options.to_vec()));
+    }
+
+    /// Drop every response
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Number of requests with an entry (which may have expired)
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{format_err, Error};
+    use chrono::Duration;
+    use futures::future::join_all;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::memory_cache::MemoryCache;
+
+    #[tokio::test]
+    async fn test_get_or_fetch() -> Result<(), Error> {
+        let cache = MemoryCache::new();
+        let options = vec![("zip", "11106".to_string())];
+        let text = cache
+            .get_or_fetch("weather", &options, async { Ok("first".into()) })
+            .await?;
+        assert_eq!(text, "first");
+        let text = cache
+            .get_or_fetch("weather", &options, async { Ok("second".into()) })
+            .await?;
+        assert_eq!(text, "first");
+        let text = cache
+            .get_or_fetch("forecast", &options, async { Ok("forecast".into()) })
+            .await?;
+        assert_eq!(text, "forecast");
+        assert_eq!(cache.len(), 2);
+
+        // Failures aren't cached
+        let result = cache
+            .get_or_fetch("onecall", &options, async { Err(format_err!("offline")) })
+            .await;
+        assert!(result.is_err());
+        let text = cache
+            .get_or_fetch("onecall", &options, async { Ok("onecall".into()) })
+            .await?;
+        assert_eq!(text, "onecall");
+
+        cache.remove("weather", &options);
+        let text = cache
+            .get_or_fetch("weather", &options, async { Ok("third".into()) })
+            .await?;
+        assert_eq!(text, "third");
+        cache.clear();
+        assert!(cache.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expired() -> Result<(), Error> {
+        let cache = MemoryCache::new().with_ttl(Duration::seconds(-1));
+        let options = vec![];
+        cache
+            .get_or_fetch("weather", &options, async { Ok("first".into()) })
+            .await?;
+        let text = cache
+            .get_or_fetch("weather", &options, async { Ok("second".into()) })
+            .await?;
+        assert_eq!(text, "second");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_fetches() -> Result<(), Error> {
+        let cache = MemoryCache::new();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    let options = vec![("zip", "11106".to_string())];
+                    cache
+                        .get_or_fetch("weather", &options, async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::delay_for(std::time::Duration::from_millis(50)).await;
+                            Ok("weather".to_string())
+                        })
+                        .await
+                })
+            })
+            .collect();
+        for result in join_all(tasks).await {
+            assert_eq!(result??, "weather");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Minimal HTTP server for exercising `WeatherApi` without network access.
+//! Each accepted connection is answered with the next canned response, the
+//! raw requests are returned when the server task completes.
+
+use anyhow::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    task::JoinHandle,
+};
+
+/// Format a complete HTTP/1.1 response
+pub fn http_response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    let headers: String = headers
+        .iter()
+        .map(|(key, value)| format!("{}: {}\r\n", key, value))
+        .collect();
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: \
+         close\r\n{}\r\n{}",
+        status,
+        body.len(),
+        headers,
+        body
+    )
+}
+
+/// Serve `responses` in order, one per connection, returning the base url
+/// of the server (suitable for `WeatherApi::with_endpoint`) and a handle
+/// resolving to the requests received.
+pub async fn serve(responses: Vec<String>) -> Result<(String, JoinHandle<Vec<String>>), Error> {
+    serve_routes(
+        responses
+            .into_iter()
+            .map(|response| ("", response))
+            .collect(),
+    )
+    .await
+}
+
+/// Like `serve`, but for concurrent requests: each connection is answered
+/// with the first remaining response whose pattern appears in the request
+/// line, or a 404 if there is none.
+pub async fn serve_routes(
+    routes: Vec<(&'static str, String)>,
+) -> Result<(String, JoinHandle<Vec<String>>), Error> {
+    let mut listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    let handle = tokio::spawn(async move {
+        let mut routes = routes;
+        let mut requests = Vec::new();
+        while !routes.is_empty() {
+            let (mut socket, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(_) => break,
+            };
+            let mut buf = vec![0; 16384];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+            let request_line = request.lines().next().unwrap_or("");
+            let response = match routes
+                .iter()
+                .position(|(pattern, _)| request_line.contains(pattern))
+            {
+                Some(idx) => routes.remove(idx).1,
+                None => http_response("404 Not Found", &[], "{}"),
+            };
+            requests.push(request);
+            socket.write_all(response.as_bytes()).await.unwrap_or(());
+        }
+        requests
+    });
+    Ok((format!("http://{}", addr), handle))
+}
@@ -0,0 +1,401 @@
+use anyhow::Error;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::io::Write;
+
+use crate::{
+    advice::get_advice,
+    display_opts::DisplayOpts,
+    humidity::Humidity,
+    json_output::JsonOutput,
+    pressure::Pressure,
+    speed::Speed,
+    summary::get_summary,
+    temperature::Temperature,
+    units::Units,
+    weather_data::{WeatherCond, WeatherData},
+    weather_forecast::WeatherForecast,
+};
+
+/// Renders the current conditions and forecast, either of which may be
+/// unavailable.  The CLI picks one of `TextFormat`, `JsonFormat`,
+/// `CsvFormat` or `OneLineFormat` from its flags, library users can supply
+/// their own.
+pub trait OutputFormat {
+    fn render(
+        &self,
+        current: Option<&WeatherData>,
+        forecast: Option<&WeatherForecast>,
+        w: &mut dyn Write,
+    ) -> Result<(), Error>;
+}
+
+/// The default human readable output
+#[derive(Default, Debug, Clone)]
+pub struct TextFormat {
+    pub opts: DisplayOpts,
+    /// Write out the forecast, otherwise only the current conditions
+    pub forecast: bool,
+    /// Write a one sentence summary instead of the current conditions and
+    /// forecast
+    pub summary: bool,
+    /// Also write the hourly temperatures interpolated from the forecast
+    pub interpolate: bool,
+    /// Also suggest what to wear
+    pub advice: bool,
+}
+
+impl TextFormat {
+    pub fn new(opts: DisplayOpts) -> Self {
+        Self {
+            opts,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_forecast(mut self, forecast: bool) -> Self {
+        self.forecast = forecast;
+        self
+    }
+
+    pub fn with_summary(mut self, summary: bool) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    pub fn with_interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    pub fn with_advice(mut self, advice: bool) -> Self {
+        self.advice = advice;
+        self
+    }
+}
+
+impl OutputFormat for TextFormat {
+    fn render(
+        &self,
+        current: Option<&WeatherData>,
+        forecast: Option<&WeatherForecast>,
+        mut w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        if let Some(data) = current {
+            if self.summary {
+                writeln!(w, "{}", get_summary(data, forecast, &self.opts))?;
+            } else {
+                let percentile = forecast.and_then(|f| f.wind_percentile(data.wind.speed));
+                match percentile {
+                    Some(percentile) => data.get_current_conditions(
+                        &mut w,
+                        &self.opts.clone().with_wind_percentile(percentile),
+                    )?,
+                    None => data.get_current_conditions(&mut w, &self.opts)?,
+                }
+            }
+        }
+        if let Some(forecast) = forecast.filter(|_| self.forecast && !self.summary) {
+            forecast.get_forecast(&mut w, &self.opts)?;
+        }
+        if let Some(forecast) = forecast.filter(|_| self.interpolate) {
+            forecast.get_interpolated_hourly(&mut w, &self.opts)?;
+        }
+        if let Some(data) = current.filter(|_| self.advice) {
+            writeln!(w, "{}", get_advice(data, forecast))?;
+        }
+        Ok(())
+    }
+}
+
+/// The versioned `JsonOutput` document
+#[derive(Default, Debug, Clone, Copy)]
+pub struct JsonFormat {
+    /// Include the hourly temperatures interpolated from the forecast
+    pub interpolate: bool,
+    /// Round coordinates to this many decimal places, see
+    /// `JsonOutput::coord_precision`
+    pub coord_precision: Option<usize>,
+}
+
+impl JsonFormat {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    pub fn with_coord_precision(mut self, coord_precision: usize) -> Self {
+        self.coord_precision = Some(coord_precision);
+        self
+    }
+}
+
+impl OutputFormat for JsonFormat {
+    fn render(
+        &self,
+        current: Option<&WeatherData>,
+        forecast: Option<&WeatherForecast>,
+        mut w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let mut output = JsonOutput::new();
+        if let Some(coord_precision) = self.coord_precision {
+            output = output.with_coord_precision(coord_precision);
+        }
+        if let Some(data) = current {
+            output = output.with_current(data.clone());
+        }
+        if let Some(forecast) = forecast {
+            if self.interpolate {
+                output = output.with_hourly_temperatures(forecast.get_hourly_temperatures());
+            }
+            output = output.with_forecast(forecast.clone());
+        }
+        output.write_json(&mut w)
+    }
+}
+
+/// Header of the `CsvFormat` output
+pub const CSV_HEADER: &str =
+    "dt,kind,temperature,feels_like,humidity,pressure,wind_speed,conditions";
+
+/// A row for the current conditions followed by one per forecast slot, with
+/// temperatures in the primary units and wind speeds in the wind unit of
+/// `opts`
+#[derive(Default, Debug, Clone)]
+pub struct CsvFormat {
+    pub opts: DisplayOpts,
+}
+
+impl CsvFormat {
+    pub fn new(opts: DisplayOpts) -> Self {
+        Self { opts }
+    }
+
+    fn temperature(&self, temp: Temperature) -> f64 {
+        match self.opts.units {
+            Units::Imperial => temp.fahrenheit(),
+            Units::Metric => temp.celcius(),
+        }
+    }
+
+    fn write_row(&self, w: &mut dyn Write, row: &CsvRow) -> Result<(), Error> {
+        writeln!(
+            w,
+            "{},{},{:.2},{:.2},{},{:.1},{},{}",
+            row.dt.to_rfc3339_opts(SecondsFormat::Secs, true),
+            row.kind,
+            self.temperature(row.temp),
+            self.temperature(row.feels_like),
+            row.humidity,
+            row.pressure.hpa(),
+            row.wind_speed.map_or_else(String::new, |speed| format!(
+                "{:.1}",
+                speed.value(self.opts.wind_unit)
+            )),
+            csv_field(
+                row.weather
+                    .first()
+                    .map_or("", |cond| cond.description.as_str())
+            ),
+        )?;
+        Ok(())
+    }
+}
+
+/// Fields of a row shared by the current conditions and forecast slots
+struct CsvRow<'a> {
+    dt: DateTime<Utc>,
+    kind: &'static str,
+    temp: Temperature,
+    feels_like: Temperature,
+    humidity: Humidity,
+    pressure: Pressure,
+    wind_speed: Option<Speed>,
+    weather: &'a [WeatherCond],
+}
+
+/// Quote `value` if it contains a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl OutputFormat for CsvFormat {
+    fn render(
+        &self,
+        current: Option<&WeatherData>,
+        forecast: Option<&WeatherForecast>,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        writeln!(w, "{}", CSV_HEADER)?;
+        if let Some(data) = current {
+            let row = CsvRow {
+                dt: data.dt,
+                kind: "current",
+                temp: data.main.temp,
+                feels_like: data.main.feels_like,
+                humidity: data.main.humidity,
+                pressure: data.main.pressure,
+                wind_speed: Some(data.wind.speed),
+                weather: &data.weather,
+            };
+            self.write_row(w, &row)?;
+        }
+        for entry in forecast.iter().flat_map(|forecast| &forecast.list) {
+            let row = CsvRow {
+                dt: entry.dt,
+                kind: "forecast",
+                temp: entry.main.temp,
+                feels_like: entry.main.feels_like,
+                humidity: entry.main.humidity,
+                pressure: entry.main.pressure,
+                wind_speed: entry.wind.as_ref().map(|wind| wind.speed),
+                weather: &entry.weather,
+            };
+            self.write_row(w, &row)?;
+        }
+        Ok(())
+    }
+}
+
+/// A single line for status bars, e.g.
+/// `Astoria: 41.1°F clear sky, 56% humidity, wind 16.1 mph`, with today's
+/// high and low when there is a forecast
+#[derive(Default, Debug, Clone)]
+pub struct OneLineFormat {
+    pub opts: DisplayOpts,
+}
+
+impl OneLineFormat {
+    pub fn new(opts: DisplayOpts) -> Self {
+        Self { opts }
+    }
+}
+
+impl OutputFormat for OneLineFormat {
+    fn render(
+        &self,
+        current: Option<&WeatherData>,
+        forecast: Option<&WeatherForecast>,
+        w: &mut dyn Write,
+    ) -> Result<(), Error> {
+        let mut parts = Vec::new();
+        if let Some(data) = current {
+            let mut conditions = self.opts.temperatures(data.main.temp, 1).0;
+            if let Some(cond) = data.weather.first() {
+                conditions.push_str(&format!(
+                    " {}{}",
+                    self.opts.condition_icon(cond.id),
+                    cond.description
+                ));
+            }
+            parts.push(conditions);
+            parts.push(format!("{}% humidity", data.main.humidity));
+            parts.push(format!("wind {}", self.opts.wind_speed(data.wind.speed)));
+        }
+        if let Some(today) = forecast.and_then(|f| f.get_forecast_summary().days.first().cloned()) {
+            parts.push(format!(
+                "high {} low {}",
+                self.opts.temperatures(today.high, 0).0,
+                self.opts.temperatures(today.low, 0).0
+            ));
+        }
+        match current.filter(|data| !data.name.is_empty() && !self.opts.hide_location) {
+            Some(data) => writeln!(w, "{}: {}", data.name, parts.join(", "))?,
+            None => writeln!(w, "{}", parts.join(", "))?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use serde_json::Value;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        output_format::{
+            CsvFormat, JsonFormat, OneLineFormat, OutputFormat, TextFormat, CSV_HEADER,
+        },
+        weather_data::WeatherData,
+        weather_forecast::WeatherForecast,
+    };
+
+    fn render(format: &dyn OutputFormat) -> Result<String, Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        let mut buf = Vec::new();
+        format.render(Some(&data), Some(&forecast), &mut buf)?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    #[test]
+    fn test_formats() -> Result<(), Error> {
+        let text = render(&TextFormat::new(DisplayOpts::default()).with_forecast(true))?;
+        assert!(text.starts_with("Current conditions Astoria"), "{}", text);
+        assert!(
+            text.contains("\tWind: 310 degrees at 16.1 mph (windier than usual this week)\n"),
+            "{}",
+            text
+        );
+        assert_eq!(text.matches("Forecast:").count(), 1, "{}", text);
+
+        let opts = DisplayOpts::default().with_forecast_heading("Outlook");
+        let text = render(&TextFormat::new(opts).with_forecast(true))?;
+        assert!(text.contains("\nOutlook:\n\tWeek ahead: "), "{}", text);
+        assert!(!text.contains("Forecast:"), "{}", text);
+        let opts = DisplayOpts::default().with_hide_forecast_banner(true);
+        let text = render(&TextFormat::new(opts).with_forecast(true))?;
+        assert!(text.contains("\n\tWeek ahead: "), "{}", text);
+        assert!(!text.contains("Forecast:"), "{}", text);
+
+        let csv = render(&CsvFormat::new(DisplayOpts::default()))?;
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[1],
+            "2020-01-19T18:23:42Z,current,41.05,27.68,56,1008.0,16.1,clear sky"
+        );
+        assert_eq!(lines.len(), 2 + 40);
+        assert!(lines[2..].iter().all(|line| line.contains(",forecast,")));
+        for line in &lines {
+            assert_eq!(line.split(',').count(), 8, "{}", line);
+        }
+
+        let json: Value = serde_json::from_str(&render(&JsonFormat::new())?)?;
+        assert_eq!(json["current"]["name"], "Astoria");
+        assert!(json.get("hourly_temperatures").is_none());
+
+        let line = render(&OneLineFormat::new(DisplayOpts::default()))?;
+        assert!(
+            line.starts_with("Astoria: 41.1°F clear sky, 56% humidity, wind 16.1 mph, high "),
+            "{}",
+            line
+        );
+        assert_eq!(line.lines().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_forecast_unavailable() -> Result<(), Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        OneLineFormat::new(DisplayOpts::default().with_hide_location(true)).render(
+            Some(&data),
+            None,
+            &mut buf,
+        )?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "41.1°F clear sky, 56% humidity, wind 16.1 mph\n"
+        );
+        Ok(())
+    }
+}
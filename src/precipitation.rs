@@ -8,10 +8,13 @@ use std::{
     convert::{From, TryFrom},
     fmt::{self, Formatter},
     io::Write,
+    str::FromStr,
 };
 
 const MM_PER_INCH: f64 = 25.4;
 
+const MM_PER_CM: f64 = 10.0;
+
 /// Precipitation in mm
 #[derive(
     Into,
@@ -34,7 +37,7 @@ impl TryFrom<f64> for Precipitation {
     type Error = Error;
     fn try_from(item: f64) -> Result<Self, Self::Error> {
         if item < 0.0 {
-            Err(format_err!("{} is not a valid precipitation amount"))
+            Err(format_err!("{} is not a valid precipitation amount", item))
         } else {
             Ok(Self(item))
         }
@@ -66,4 +69,105 @@ impl Precipitation {
     pub fn inches(self) -> f64 {
         self.0 / MM_PER_INCH
     }
+
+    pub fn centimeters(self) -> f64 {
+        self.0 / MM_PER_CM
+    }
+
+    /// Precipitation expressed in `unit`
+    pub fn value(self, unit: PrecipitationUnit) -> f64 {
+        match unit {
+            PrecipitationUnit::Millimeters => self.millimeters(),
+            PrecipitationUnit::Inches => self.inches(),
+            PrecipitationUnit::Centimeters => self.centimeters(),
+        }
+    }
+}
+
+/// Unit used to display a `Precipitation`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PrecipitationUnit {
+    #[serde(rename = "mm")]
+    Millimeters,
+    #[serde(rename = "in")]
+    Inches,
+    #[serde(rename = "cm")]
+    Centimeters,
+}
+
+impl PrecipitationUnit {
+    pub fn all() -> [Self; 3] {
+        [Self::Millimeters, Self::Inches, Self::Centimeters]
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Millimeters => "mm",
+            Self::Inches => "in",
+            Self::Centimeters => "cm",
+        }
+    }
+
+    /// Decimal places used when printing a precipitation in this unit
+    pub fn precision(self) -> usize {
+        match self {
+            Self::Millimeters => 1,
+            Self::Inches | Self::Centimeters => 2,
+        }
+    }
+}
+
+impl fmt::Display for PrecipitationUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for PrecipitationUnit {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|unit| unit.to_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| format_err!("{} is not a valid precipitation unit (mm, in, cm)", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        precipitation::{Precipitation, PrecipitationUnit},
+        units::Units,
+    };
+
+    #[test]
+    fn test_precipitation_unit() -> Result<(), Error> {
+        let snow = Precipitation::from_millimeters(63.5)?;
+        assert_abs_diff_eq!(snow.value(PrecipitationUnit::Millimeters), 63.5);
+        assert_abs_diff_eq!(snow.value(PrecipitationUnit::Inches), 2.5);
+        assert_abs_diff_eq!(snow.value(PrecipitationUnit::Centimeters), 6.35);
+
+        for unit in PrecipitationUnit::all().iter() {
+            assert_eq!(unit.to_str().parse::<PrecipitationUnit>()?, *unit);
+        }
+        assert!("ft".parse::<PrecipitationUnit>().is_err());
+
+        // Follows the units unless a unit is given
+        let opts = DisplayOpts::default();
+        assert_eq!(opts.precipitation(snow), "2.50 in");
+        let opts = opts.with_units(Units::Metric);
+        assert_eq!(opts.precipitation(snow), "63.5 mm");
+        let opts = opts.with_precipitation_unit(PrecipitationUnit::Centimeters);
+        assert_eq!(opts.precipitation(snow), "6.35 cm");
+        let opts = opts.with_precipitation_unit(PrecipitationUnit::Inches);
+        assert_eq!(opts.precipitation(snow), "2.50 in");
+        let opts = DisplayOpts::default().with_precipitation_unit(PrecipitationUnit::Millimeters);
+        assert_eq!(opts.precipitation(snow), "63.5 mm");
+        Ok(())
+    }
 }
@@ -3,11 +3,20 @@ use derive_more::Into;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 
+use crate::elevation::Elevation;
+
 const HECTO: f64 = 1.0; // hPa 100 hundred Pa
 const KILO: f64 = 1_000.0 / 100.0;
 const ATM: f64 = 98.0665 * HECTO / KILO;
 const PSI: f64 = 14.223 / (98.0665 * HECTO / KILO);
 
+/// Temperature lapse rate of the standard atmosphere in K/m
+const LAPSE_RATE: f64 = 0.0065;
+/// Sea level temperature of the standard atmosphere in K
+const SEA_LEVEL_KELVIN: f64 = 288.15;
+/// g * M / (R * L) for dry air
+const BAROMETRIC_EXPONENT: f64 = 5.255_88;
+
 /// Pressure struct, data is stored as hPa (100 Pa)
 #[derive(Into, Debug, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize)]
 #[serde(into = "f64", try_from = "f64")]
@@ -19,7 +28,7 @@ impl TryFrom<f64> for Pressure {
         if item > 0.0 {
             Ok(Self(item))
         } else {
-            Err(format_err!("{} is not a valid pressure value"))
+            Err(format_err!("{} is not a valid pressure value", item))
         }
     }
 }
@@ -45,6 +54,41 @@ impl Pressure {
         Self::try_from(psi / PSI)
     }
 
+    /// Station pressure at `elevation` given this (sea level) pressure,
+    /// from the barometric formula
+    ///
+    /// P = P0 * (1 - L * h / T0) ^ (g * M / (R * L))
+    ///
+    /// This assumes the International Standard Atmosphere: a sea level
+    /// temperature T0 of 15 C cooling by L = 6.5 K per km of elevation h,
+    /// which holds up to 11 km.  The actual temperature profile isn't taken
+    /// into account, so expect an error of a few hPa at high elevations.
+    /// ```
+    /// use weather_util_rust::pressure::Pressure;
+    /// # use weather_util_rust::elevation::Elevation;
+    /// # use std::convert::TryFrom;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let sea_level = Pressure::from_hpa(1013.25)?;
+    /// let station = sea_level.at_elevation(Elevation::try_from(1500.0)?);
+    /// assert_eq!(format!("{:.1}", station.hpa()), "845.6");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn at_elevation(self, elevation: Elevation) -> Self {
+        Self(self.0 * Self::barometric_ratio(elevation))
+    }
+
+    /// Sea level pressure given this station pressure at `elevation`, the
+    /// inverse of `at_elevation`
+    pub fn to_sea_level(self, elevation: Elevation) -> Self {
+        Self(self.0 / Self::barometric_ratio(elevation))
+    }
+
+    fn barometric_ratio(elevation: Elevation) -> f64 {
+        (1.0 - LAPSE_RATE * elevation.meters() / SEA_LEVEL_KELVIN).powf(BAROMETRIC_EXPONENT)
+    }
+
     pub fn kpa(self) -> f64 {
         self.0 * KILO / HECTO
     }
@@ -71,7 +115,9 @@ mod tests {
     use anyhow::Error;
     use approx::assert_abs_diff_eq;
 
-    use crate::pressure::Pressure;
+    use std::convert::TryFrom;
+
+    use crate::{elevation::Elevation, pressure::Pressure};
 
     #[test]
     fn test_pressure() -> Result<(), Error> {
@@ -82,4 +128,25 @@ mod tests {
         assert_abs_diff_eq!(p.psi(), 14.223, epsilon = 0.00001);
         Ok(())
     }
+
+    #[test]
+    fn test_at_elevation() -> Result<(), Error> {
+        let sea_level = Pressure::from_hpa(1013.25)?;
+        // Standard atmosphere tables give 898.7 hPa at 1000 m and 701.1 hPa
+        // at 3000 m
+        let station = sea_level.at_elevation(Elevation::try_from(1000.0)?);
+        assert_abs_diff_eq!(station.hpa(), 898.7, epsilon = 0.1);
+        let station = sea_level.at_elevation(Elevation::try_from(3000.0)?);
+        assert_abs_diff_eq!(station.hpa(), 701.1, epsilon = 0.1);
+        assert_abs_diff_eq!(
+            station.to_sea_level(Elevation::try_from(3000.0)?).hpa(),
+            1013.25,
+            epsilon = 1e-9
+        );
+
+        let elevation = Elevation::try_from(0.0)?;
+        assert_eq!(sea_level.at_elevation(elevation), sea_level);
+        assert!(sea_level.at_elevation(Elevation::try_from(-400.0)?) > sea_level);
+        Ok(())
+    }
 }
@@ -0,0 +1,165 @@
+use anyhow::Error;
+use std::io::Write;
+
+use crate::{weather_data::WeatherData, weather_forecast::WeatherForecast};
+
+/// Escape a label value for the Prometheus text exposition format
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn write_metric<T: Write>(
+    buf: &mut T,
+    name: &str,
+    help: &str,
+    labels: &str,
+    value: f64,
+) -> Result<(), Error> {
+    writeln!(buf, "# HELP {} {}", name, help)?;
+    writeln!(buf, "# TYPE {} gauge", name)?;
+    writeln!(buf, "{}{{{}}} {}", name, labels, value)?;
+    Ok(())
+}
+
+/// Write the current conditions as Prometheus gauges, e.g. for the node
+/// exporter textfile collector.  The probability of precipitation is taken
+/// from the first entry of `forecast` and left out without one.
+/// ```
+/// # use anyhow::Error;
+/// # use std::io::Read;
+/// # use std::fs::File;
+/// use weather_util_rust::weather_data::WeatherData;
+/// use weather_util_rust::prometheus::write_metrics;
+/// # fn main() -> Result<(), Error> {
+/// # let mut buf = String::new();
+/// # let mut f = File::open("tests/weather.json")?;
+/// # f.read_to_string(&mut buf)?;
+/// let data: WeatherData = serde_json::from_str(&buf)?;
+/// let mut buf = Vec::new();
+/// write_metrics(&mut buf, &data, None)?;
+/// let buf = String::from_utf8(buf)?;
+/// assert!(buf.contains("\nweather_humidity_percent{location=\"Astoria\"} 56\n"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn write_metrics<T: Write>(
+    buf: &mut T,
+    data: &WeatherData,
+    forecast: Option<&WeatherForecast>,
+) -> Result<(), Error> {
+    let location = if data.name.is_empty() {
+        format!("{},{}", data.coord.lat, data.coord.lon)
+    } else {
+        data.name.clone()
+    };
+    let labels = format!("location=\"{}\"", escape_label(&location));
+    write_metric(
+        buf,
+        "weather_temperature_celsius",
+        "Current temperature",
+        &labels,
+        data.main.temp.celcius(),
+    )?;
+    write_metric(
+        buf,
+        "weather_humidity_percent",
+        "Current relative humidity",
+        &labels,
+        i64::from(data.main.humidity) as f64,
+    )?;
+    write_metric(
+        buf,
+        "weather_pressure_hpa",
+        "Current sea level pressure",
+        &labels,
+        data.main.pressure.hpa(),
+    )?;
+    write_metric(
+        buf,
+        "weather_wind_speed_meters_per_second",
+        "Current wind speed",
+        &labels,
+        data.wind.speed.mps(),
+    )?;
+    if let Some(pop) = forecast
+        .and_then(|forecast| forecast.list.first())
+        .and_then(|entry| entry.pop)
+    {
+        write_metric(
+            buf,
+            "weather_precipitation_probability",
+            "Probability of precipitation in the next forecast period",
+            &labels,
+            pop,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{
+        prometheus::{escape_label, write_metrics},
+        weather_data::WeatherData,
+        weather_forecast::WeatherForecast,
+    };
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("Astoria"), "Astoria");
+        assert_eq!(
+            escape_label("say \"hi\"\\\nbye"),
+            "say \\\"hi\\\"\\\\\\nbye"
+        );
+    }
+
+    #[test]
+    fn test_write_metrics() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        forecast.list[0].pop = Some(0.25);
+        data.name = "St. John's \"NL\"".into();
+
+        let mut buf = Vec::new();
+        write_metrics(&mut buf, &data, Some(&forecast))?;
+        let buf = String::from_utf8(buf)?;
+        let samples: Vec<_> = buf.lines().filter(|l| !l.starts_with('#')).collect();
+        let labels = r#"{location="St. John's \"NL\""}"#;
+        assert_eq!(
+            samples,
+            vec![
+                format!(
+                    "weather_temperature_celsius{} {}",
+                    labels,
+                    data.main.temp.celcius()
+                ),
+                format!("weather_humidity_percent{} 56", labels),
+                format!("weather_pressure_hpa{} 1008", labels),
+                format!(
+                    "weather_wind_speed_meters_per_second{} {}",
+                    labels,
+                    data.wind.speed.mps()
+                ),
+                format!("weather_precipitation_probability{} 0.25", labels),
+            ]
+        );
+        // Every sample is preceded by its HELP and TYPE lines
+        let lines: Vec<_> = buf.lines().collect();
+        for chunk in lines.chunks(3) {
+            let name = chunk[2].split('{').next().unwrap();
+            assert_eq!(chunk[0].split(' ').nth(2), Some(name));
+            assert_eq!(chunk[1], format!("# TYPE {} gauge", name));
+        }
+
+        let mut buf = Vec::new();
+        write_metrics(&mut buf, &data, None)?;
+        assert!(!String::from_utf8(buf)?.contains("precipitation"));
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::{
+    display_opts::{round_coordinate, DEFAULT_COORD_PRECISION},
+    elevation::Elevation,
+    latitude::Latitude,
+    longitude::Longitude,
+    weather_data::WeatherData,
+};
+
+/// Place a query resolved to, written out as a header so that users can
+/// confirm the right place was found, e.g.
+/// `New York, New York, US (40.7127, -74.006)`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ResolvedLocation {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    pub lat: Latitude,
+    pub lon: Longitude,
+    /// Elevation in meters, only in some responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<Elevation>,
+}
+
+impl From<&WeatherData> for ResolvedLocation {
+    fn from(data: &WeatherData) -> Self {
+        Self {
+            name: data.name.clone(),
+            state: None,
+            country: data.sys.country.clone(),
+            lat: data.coord.lat,
+            lon: data.coord.lon,
+            elevation: data.elevation,
+        }
+    }
+}
+
+impl ResolvedLocation {
+    /// The header with the coordinates rounded to `precision` decimal
+    /// places, `to_string` uses `DEFAULT_COORD_PRECISION`
+    pub fn describe(&self, precision: usize) -> String {
+        let parts: Vec<_> = Some(self.name.as_str())
+            .into_iter()
+            .chain(self.state.as_deref())
+            .chain(self.country.as_deref())
+            .filter(|part| !part.is_empty())
+            .collect();
+        let (lat, lon): (f64, f64) = (self.lat.into(), self.lon.into());
+        let coords = format!(
+            "({}, {})",
+            round_coordinate(lat, precision),
+            round_coordinate(lon, precision)
+        );
+        if parts.is_empty() {
+            coords
+        } else {
+            format!("{} {}", parts.join(", "), coords)
+        }
+    }
+}
+
+impl fmt::Display for ResolvedLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe(DEFAULT_COORD_PRECISION))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{resolved_location::ResolvedLocation, weather_data::WeatherData};
+
+    #[test]
+    fn test_resolved_location() -> Result<(), Error> {
+        let locations: Vec<ResolvedLocation> =
+            serde_json::from_str(include_str!("../tests/geocoding.json"))?;
+        assert_eq!(
+            locations[0].to_string(),
+            "New York, New York, US (40.7127, -74.006)"
+        );
+        assert_eq!(
+            locations[0].describe(2),
+            "New York, New York, US (40.71, -74.01)"
+        );
+        assert_eq!(locations[0].describe(0), "New York, New York, US (41, -74)");
+
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut location = ResolvedLocation::from(&data);
+        assert_eq!(location.to_string(), "Astoria, US (40.76, -73.93)");
+
+        location.name = String::new();
+        location.country = None;
+        assert_eq!(location.to_string(), "(40.76, -73.93)");
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Error;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Keys of `text`, a JSON api response, that are lost when it's
+/// deserialized into `T` and serialized again, e.g. `main.temp_kf` or
+/// `list[0].main.sea_level`.  Only keys are compared, values are expected to
+/// change format (e.g. `1008` becoming `1008.0`).
+/// ```
+/// # use anyhow::Error;
+/// # use std::io::Read;
+/// # use std::fs::File;
+/// use weather_util_rust::round_trip::unknown_keys;
+/// use weather_util_rust::weather_data::WeatherData;
+/// # fn main() -> Result<(), Error> {
+/// # let mut text = String::new();
+/// # let mut f = File::open("tests/weather.json")?;
+/// # f.read_to_string(&mut text)?;
+/// let keys = unknown_keys::<WeatherData>(&text)?;
+/// assert!(keys.contains(&"sys.id".to_string()));
+/// # Ok(())
+/// # }
+/// ```
+pub fn unknown_keys<T: DeserializeOwned + Serialize>(text: &str) -> Result<Vec<String>, Error> {
+    let original: Value = serde_json::from_str(text)?;
+    let data: T = serde_json::from_value(original.clone())?;
+    let round_trip = serde_json::to_value(&data)?;
+    let mut keys = Vec::new();
+    missing_keys(&original, &round_trip, "", &mut keys);
+    Ok(keys)
+}
+
+fn missing_keys(original: &Value, round_trip: &Value, path: &str, keys: &mut Vec<String>) {
+    match (original, round_trip) {
+        (Value::Object(original), Value::Object(round_trip)) => {
+            for (key, value) in original {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match round_trip.get(key) {
+                    Some(other) => missing_keys(value, other, &key_path, keys),
+                    None if value.is_null() => {}
+                    None => keys.push(key_path),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(round_trip)) => {
+            for (idx, (value, other)) in original.iter().zip(round_trip).enumerate() {
+                missing_keys(value, other, &format!("{}[{}]", path, idx), keys);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{round_trip::unknown_keys, weather_data::WeatherData};
+
+    #[test]
+    fn test_unknown_keys() -> Result<(), Error> {
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let text = json.to_string();
+        let known = unknown_keys::<WeatherData>(&text)?;
+        assert!(!known.contains(&"main.air_quality".to_string()));
+
+        json["main"]["air_quality"] = 42.into();
+        json["weather"][0]["severity"] = "low".into();
+        let keys = unknown_keys::<WeatherData>(&json.to_string())?;
+        let new: Vec<_> = keys.iter().filter(|key| !known.contains(key)).collect();
+        assert_eq!(new, vec!["weather[0].severity", "main.air_quality"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::{format_err, Error};
+use std::{convert::TryFrom, fs, io::Write, path::Path};
+
+use crate::{
+    latitude::Latitude,
+    longitude::Longitude,
+    resolved_location::ResolvedLocation,
+    weather_api::{WeatherApi, WeatherLocation},
+};
+
+/// Name of the file written (and removed again) to confirm the cache
+/// directory is writable
+const CACHE_PROBE_FILE: &str = ".write_check";
+
+/// Outcome of a single check, with a detail to print on success
+#[derive(Debug)]
+pub struct CheckResult {
+    pub name: String,
+    pub result: Result<String, Error>,
+}
+
+/// Results of the `--check` self-check, in the order they ran
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl CheckReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, name: &str, result: Result<String, Error>) {
+        self.checks.push(CheckResult {
+            name: name.into(),
+            result,
+        });
+    }
+
+    /// Whether every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.result.is_ok())
+    }
+
+    /// Number of checks which failed
+    pub fn failures(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.result.is_err())
+            .count()
+    }
+
+    /// Write out a line per check, e.g. `PASS  API key: valid`
+    /// ```
+    /// # use anyhow::{format_err, Error};
+    /// use weather_util_rust::self_check::CheckReport;
+    /// # fn main() -> Result<(), Error> {
+    /// let mut report = CheckReport::new();
+    /// report.push("API key", Ok("valid".into()));
+    /// report.push("Cache directory", Err(format_err!("read-only")));
+    ///
+    /// let mut buf = Vec::new();
+    /// report.write_report(&mut buf)?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert_eq!(buf, "PASS  API key: valid\nFAIL  Cache directory: read-only\n");
+    /// assert!(!report.passed());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_report<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
+        for check in &self.checks {
+            match &check.result {
+                Ok(detail) => writeln!(buf, "PASS  {}: {}", check.name, detail)?,
+                Err(e) => writeln!(buf, "FAIL  {}: {}", check.name, e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Make a single current conditions request (for 0, 0) to confirm the api
+/// accepts the key
+pub async fn check_api_key(api: &WeatherApi) -> Result<String, Error> {
+    let loc = WeatherLocation::from_lat_lon(Latitude::try_from(0.0)?, Longitude::try_from(0.0)?);
+    api.get_weather_data(&loc).await?;
+    Ok("valid".into())
+}
+
+/// Create `directory` if needed, then write and remove a file in it
+pub fn check_cache_dir(directory: &Path) -> Result<String, Error> {
+    let probe = directory.join(CACHE_PROBE_FILE);
+    fs::create_dir_all(directory)
+        .and_then(|_| fs::write(&probe, b""))
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| format_err!("{} is not writable: {}", directory.display(), e))?;
+    Ok(format!("{} is writable", directory.display()))
+}
+
+/// Confirm the api finds `loc`, returning the place it resolved to
+pub async fn check_location(api: &WeatherApi, loc: &WeatherLocation) -> Result<String, Error> {
+    let data = api.get_weather_data(loc).await?;
+    Ok(ResolvedLocation::from(&data).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::{format_err, Error};
+    use std::{env::temp_dir, fs};
+
+    #[cfg(feature = "native-client")]
+    use crate::mock_server::{http_response, serve};
+    use crate::{
+        self_check::{check_api_key, check_cache_dir, check_location, CheckReport},
+        weather_api::{WeatherApi, WeatherLocation},
+    };
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_check_api_key() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![
+            http_response("200 OK", &[], include_str!("../tests/weather.json")),
+            http_response(
+                "401 Unauthorized",
+                &[],
+                r#"{"cod":401,"message":"Invalid API key."}"#,
+            ),
+        ])
+        .await?;
+        let api = WeatherApi::new("0123456789abcdef", &endpoint, "data/2.5/").with_retries(0);
+        assert_eq!(check_api_key(&api).await?, "valid");
+        assert!(check_api_key(&api).await.is_err());
+
+        let requests = handle.await?;
+        assert!(
+            requests[0].starts_with("GET /data/2.5/weather?lat=0&lon=0&"),
+            "{}",
+            requests[0]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_check_location() -> Result<(), Error> {
+        let (endpoint, _) = serve(vec![http_response(
+            "200 OK",
+            &[],
+            include_str!("../tests/weather.json"),
+        )])
+        .await?;
+        let api = WeatherApi::new("0123456789abcdef", &endpoint, "data/2.5/").with_retries(0);
+        let loc = WeatherLocation::from_zipcode(11106);
+        assert_eq!(
+            check_location(&api, &loc).await?,
+            "Astoria, US (40.76, -73.93)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_cache_dir() -> Result<(), Error> {
+        let directory = temp_dir().join(format!("weather_util_check_{}", std::process::id()));
+        let detail = check_cache_dir(&directory.join("cache"))?;
+        assert!(detail.ends_with(" is writable"), "{}", detail);
+        assert_eq!(fs::read_dir(directory.join("cache"))?.count(), 0);
+
+        // A file where the directory should be
+        fs::write(directory.join("file"), b"")?;
+        let e = check_cache_dir(&directory.join("file")).unwrap_err();
+        assert!(e.to_string().contains(" is not writable: "), "{}", e);
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_report() -> Result<(), Error> {
+        let mut report = CheckReport::new();
+        report.push("API key", Ok("valid".into()));
+        assert!(report.passed());
+        report.push("Location", Err(format_err!("city not found")));
+        assert!(!report.passed());
+        assert_eq!(report.failures(), 1);
+
+        let mut buf = Vec::new();
+        report.write_report(&mut buf)?;
+        let buf = String::from_utf8(buf)?;
+        assert_eq!(buf.lines().nth(1), Some("FAIL  Location: city not found"));
+        Ok(())
+    }
+}
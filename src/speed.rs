@@ -1,13 +1,15 @@
 use anyhow::{format_err, Error};
 use derive_more::Into;
 use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use std::{convert::TryFrom, fmt, str::FromStr};
 
 const SECONDS_PER_HOUR: f64 = 3600.;
 const METERS_PER_MILE: f64 = 1609.344;
+const METERS_PER_KILOMETER: f64 = 1000.;
+const MPS_PER_KNOT: f64 = 0.514_444;
 
 /// Speed in meters per second
-#[derive(Into, Debug, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize)]
+#[derive(Into, Debug, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize, Default)]
 pub struct Speed(f64);
 
 impl TryFrom<f64> for Speed {
@@ -30,6 +32,14 @@ impl Speed {
         Self::try_from(mph * METERS_PER_MILE / SECONDS_PER_HOUR)
     }
 
+    pub fn from_kmh(kmh: f64) -> Result<Self, Error> {
+        Self::try_from(kmh * METERS_PER_KILOMETER / SECONDS_PER_HOUR)
+    }
+
+    pub fn from_knots(knots: f64) -> Result<Self, Error> {
+        Self::try_from(knots * MPS_PER_KNOT)
+    }
+
     pub fn mps(self) -> f64 {
         self.0
     }
@@ -37,6 +47,108 @@ impl Speed {
     pub fn mph(self) -> f64 {
         self.0 * SECONDS_PER_HOUR / METERS_PER_MILE
     }
+
+    pub fn kmh(self) -> f64 {
+        self.0 * SECONDS_PER_HOUR / METERS_PER_KILOMETER
+    }
+
+    pub fn knots(self) -> f64 {
+        self.0 / MPS_PER_KNOT
+    }
+
+    /// Percentage of `speeds` slower than this one, `None` without any
+    /// ```
+    /// use weather_util_rust::speed::Speed;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let speeds = [Speed::from_mps(1.0)?, Speed::from_mps(3.0)?];
+    /// assert_eq!(Speed::from_mps(2.0)?.percentile(&speeds), Some(50.0));
+    /// assert_eq!(Speed::from_mps(2.0)?.percentile(&[]), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn percentile(self, speeds: &[Speed]) -> Option<f64> {
+        if speeds.is_empty() {
+            return None;
+        }
+        let below = speeds.iter().filter(|s| **s < self).count();
+        Some(100.0 * below as f64 / speeds.len() as f64)
+    }
+
+    /// Description of the wind speed loosely following the Beaufort scale
+    pub fn wind_description(self) -> &'static str {
+        match self.0 {
+            x if x < 0.5 => "calm",
+            x if x < 3.4 => "light",
+            x if x < 8.0 => "moderate",
+            x if x < 13.9 => "strong",
+            _ => "gale force",
+        }
+    }
+
+    /// Speed expressed in `unit`
+    pub fn value(self, unit: SpeedUnit) -> f64 {
+        match unit {
+            SpeedUnit::Mph => self.mph(),
+            SpeedUnit::Kmh => self.kmh(),
+            SpeedUnit::Mps => self.mps(),
+            SpeedUnit::Knots => self.knots(),
+        }
+    }
+}
+
+/// Unit used to display a `Speed`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedUnit {
+    #[default]
+    Mph,
+    Kmh,
+    #[serde(rename = "ms")]
+    Mps,
+    Knots,
+}
+
+impl SpeedUnit {
+    pub fn all() -> [Self; 4] {
+        [Self::Mph, Self::Kmh, Self::Mps, Self::Knots]
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Mph => "mph",
+            Self::Kmh => "kmh",
+            Self::Mps => "ms",
+            Self::Knots => "knots",
+        }
+    }
+
+    /// Suffix used when printing a speed in this unit
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Mph => "mph",
+            Self::Kmh => "km/h",
+            Self::Mps => "m/s",
+            Self::Knots => "knots",
+        }
+    }
+}
+
+impl fmt::Display for SpeedUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for SpeedUnit {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|unit| unit.to_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| format_err!("{} is not a valid wind unit (mph, kmh, ms, knots)", s))
+    }
 }
 
 #[cfg(test)]
@@ -44,7 +156,7 @@ mod tests {
     use anyhow::Error;
     use approx::assert_abs_diff_eq;
 
-    use crate::speed::Speed;
+    use crate::speed::{Speed, SpeedUnit};
 
     #[test]
     fn test_speed() -> Result<(), Error> {
@@ -53,4 +165,35 @@ mod tests {
         assert_abs_diff_eq!(s.mps(), 1609.344 / 3600.);
         Ok(())
     }
+
+    #[test]
+    fn test_speed_kmh() -> Result<(), Error> {
+        let s = Speed::from_kmh(36.0)?;
+        assert_abs_diff_eq!(s.mps(), 10.0);
+        assert_abs_diff_eq!(s.kmh(), 36.0);
+        assert_abs_diff_eq!(s.value(SpeedUnit::Kmh), 36.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_speed_knots() -> Result<(), Error> {
+        let s = Speed::from_knots(1.0)?;
+        assert_abs_diff_eq!(s.mps(), 0.514444);
+        assert_abs_diff_eq!(s.knots(), 1.0);
+        assert_abs_diff_eq!(s.value(SpeedUnit::Knots), 1.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_speed_units() -> Result<(), Error> {
+        let s = Speed::from_mps(10.0)?;
+        assert_abs_diff_eq!(s.value(SpeedUnit::Mps), 10.0);
+        assert_abs_diff_eq!(s.value(SpeedUnit::Mph), 22.369363, epsilon = 1e-6);
+        assert_abs_diff_eq!(s.value(SpeedUnit::Kmh), 36.0);
+        assert_abs_diff_eq!(s.value(SpeedUnit::Knots), 19.438461, epsilon = 1e-6);
+        assert_eq!("ms".parse::<SpeedUnit>()?, SpeedUnit::Mps);
+        assert_eq!("Knots".parse::<SpeedUnit>()?, SpeedUnit::Knots);
+        assert!("furlongs".parse::<SpeedUnit>().is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,198 @@
+use chrono::{DateTime, Duration, FixedOffset, Timelike};
+
+use crate::{
+    conditions::Condition,
+    display_opts::DisplayOpts,
+    weather_data::{WeatherCond, WeatherData},
+    weather_forecast::{ForecastEntry, WeatherForecast},
+};
+
+/// How far ahead of the current observation the forecast is searched for
+/// precipitation
+const SUMMARY_HOURS: i64 = 12;
+
+/// Chance of precipitation below which it isn't mentioned
+const MIN_POP: f64 = 0.1;
+
+/// Short adjective describing a condition, e.g. "partly cloudy"
+fn condition_phrase(cond: &WeatherCond) -> String {
+    Condition::find(cond.id).map_or_else(
+        || cond.description.to_lowercase(),
+        |condition| condition.phrase.to_string(),
+    )
+}
+
+/// Part of the day `dt` falls in relative to `now`, e.g. "this afternoon"
+fn period_phrase(dt: DateTime<FixedOffset>, now: DateTime<FixedOffset>) -> String {
+    let part = match dt.hour() {
+        5..=11 => "morning",
+        12..=17 => "afternoon",
+        18..=23 => "evening",
+        _ => return "overnight".to_string(),
+    };
+    if dt.date_naive() == now.date_naive() {
+        format!("this {}", part)
+    } else {
+        format!("tomorrow {}", part)
+    }
+}
+
+fn is_snow(entry: &ForecastEntry) -> bool {
+    entry.snow.is_some() || entry.weather.iter().any(|cond| cond.main == "Snow")
+}
+
+fn precipitation_phrase(data: &WeatherData, forecast: &WeatherForecast) -> Option<String> {
+    let fo: FixedOffset = forecast.city.timezone.into();
+    let now = data.dt.with_timezone(&fo);
+    let end = data.dt + Duration::hours(SUMMARY_HOURS);
+    let upcoming: Vec<_> = forecast
+        .list
+        .iter()
+        .filter(|entry| entry.dt >= data.dt && entry.dt <= end)
+        .collect();
+
+    let kind = |entry: &ForecastEntry| if is_snow(entry) { "snow" } else { "rain" };
+
+    let likeliest = upcoming
+        .iter()
+        .filter_map(|entry| entry.pop.map(|pop| (pop, entry)))
+        .fold(
+            None,
+            |best: Option<(f64, &&ForecastEntry)>, (pop, entry)| match best {
+                Some((best_pop, _)) if best_pop >= pop => best,
+                _ => Some((pop, entry)),
+            },
+        );
+    if let Some((pop, entry)) = likeliest {
+        return if pop >= MIN_POP {
+            Some(format!(
+                "a {:.0}% chance of {} {}",
+                pop * 100.0,
+                kind(entry),
+                period_phrase(entry.dt.with_timezone(&fo), now)
+            ))
+        } else {
+            None
+        };
+    }
+
+    // Older responses have no `pop`, fall back on expected accumulation
+    upcoming
+        .iter()
+        .find(|entry| {
+            let rain = entry.rain.as_ref().and_then(|r| r.three_hour);
+            let snow = entry.snow.as_ref().and_then(|s| s.three_hour);
+            rain.or(snow).is_some_and(|p| p.millimeters() > 0.0)
+        })
+        .map(|entry| {
+            format!(
+                "{} expected {}",
+                kind(entry),
+                period_phrase(entry.dt.with_timezone(&fo), now)
+            )
+        })
+}
+
+/// One sentence description of the current conditions, mentioning any
+/// precipitation expected in the next few hours of `forecast`.  The
+/// temperature is in the units of `opts`.
+/// ```
+/// # use anyhow::Error;
+/// # use std::io::Read;
+/// # use std::fs::File;
+/// use weather_util_rust::weather_data::WeatherData;
+/// use weather_util_rust::summary::get_summary;
+/// use weather_util_rust::display_opts::DisplayOpts;
+/// # fn main() -> Result<(), Error> {
+/// # let mut buf = String::new();
+/// # let mut f = File::open("tests/weather.json")?;
+/// # f.read_to_string(&mut buf)?;
+/// let data: WeatherData = serde_json::from_str(&buf)?;
+/// assert_eq!(
+///     get_summary(&data, None, &DisplayOpts::default()),
+///     "It's 41°F and clear in Astoria, with moderate winds from the northwest."
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn get_summary(
+    data: &WeatherData,
+    forecast: Option<&WeatherForecast>,
+    opts: &DisplayOpts,
+) -> String {
+    let mut summary = format!("It's {}", opts.temperatures(data.main.temp, 0).0);
+    if let Some(cond) = data.weather.first() {
+        summary.push_str(&format!(" and {}", condition_phrase(cond)));
+    }
+    if !data.name.is_empty() {
+        summary.push_str(&format!(" in {}", data.name));
+    }
+
+    let wind = data.wind.speed.wind_description();
+    summary.push_str(&format!(", with {} winds", wind));
+    if let Some(deg) = data.wind.deg {
+        if wind != "calm" {
+            summary.push_str(&format!(" from the {}", deg.cardinal_name()));
+        }
+    }
+
+    if let Some(precip) = forecast.and_then(|forecast| precipitation_phrase(data, forecast)) {
+        summary.push_str(&format!(" and {}", precip));
+    }
+    summary.push('.');
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{
+        display_opts::DisplayOpts, summary::get_summary, units::Units, weather_data::WeatherData,
+        weather_forecast::WeatherForecast,
+    };
+
+    #[test]
+    fn test_summary() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let opts = DisplayOpts::default();
+
+        let summary = get_summary(&data, Some(&forecast), &opts);
+        assert_eq!(
+            summary,
+            "It's 41°F and clear in Astoria, with moderate winds from the northwest."
+        );
+
+        forecast.list[0].pop = Some(0.2);
+        forecast.list[1].pop = Some(0.1);
+        let summary = get_summary(&data, Some(&forecast), &opts);
+        assert_eq!(
+            summary,
+            "It's 41°F and clear in Astoria, with moderate winds from the northwest and a 20% \
+             chance of rain this afternoon."
+        );
+
+        forecast.list[2].pop = Some(0.6);
+        let summary = get_summary(&data, Some(&forecast), &opts);
+        assert!(
+            summary.ends_with("a 60% chance of rain this evening."),
+            "{}",
+            summary
+        );
+
+        // beyond the summary window
+        forecast.list[2].pop = None;
+        forecast.list[0].pop = None;
+        forecast.list[1].pop = None;
+        forecast.list[6].pop = Some(0.9);
+        let summary = get_summary(&data, Some(&forecast), &opts);
+        assert!(summary.ends_with("northwest."), "{}", summary);
+
+        let opts = DisplayOpts::default().with_units(Units::Metric);
+        let summary = get_summary(&data, None, &opts);
+        assert!(summary.starts_with("It's 5°C and clear"), "{}", summary);
+        Ok(())
+    }
+}
@@ -1,24 +1,50 @@
 use anyhow::{format_err, Error};
 use derive_more::{From, Into};
 use serde::{Deserialize, Serialize};
-use std::convert::TryFrom;
+use std::{
+    convert::TryFrom,
+    fmt,
+    ops::{Add, Neg, Sub},
+};
 
 const FREEZING_POINT_KELVIN: f64 = 273.15;
 const FAHRENHEIT_OFFSET: f64 = 459.67;
 const FAHRENHEIT_FACTOR: f64 = 1.8;
 
+/// Written by `Temperature::clamp_display_nan` in place of a NaN or infinite
+/// value
+pub const NON_FINITE_DISPLAY: &str = "—";
+
 /// Temperature struct, data is stored as Kelvin
-#[derive(Into, Debug, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize)]
+#[derive(Into, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize)]
 #[serde(into = "f64", try_from = "f64")]
 pub struct Temperature(f64);
 
+/// Shows the temperature in every unit, e.g.
+/// `Temperature(288.15K / 15.00C / 59.00F)`, to make conversion bugs
+/// easier to spot
+impl fmt::Debug for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Temperature({:.2}K / {:.2}C / {:.2}F)",
+            self.kelvin(),
+            self.celcius(),
+            self.fahrenheit()
+        )
+    }
+}
+
 impl TryFrom<f64> for Temperature {
     type Error = Error;
     fn try_from(item: f64) -> Result<Self, Self::Error> {
-        if item >= 0.0 {
+        if item.is_finite() && item >= 0.0 {
             Ok(Self(item))
         } else {
-            Err(format_err!("{} is not a valid Temperature", item))
+            Err(format_err!(
+                "{} is not a valid Temperature, Kelvin can't be below absolute zero",
+                item
+            ))
         }
     }
 }
@@ -39,16 +65,70 @@ impl Temperature {
         if t >= -FREEZING_POINT_KELVIN {
             Ok(Self(t + FREEZING_POINT_KELVIN))
         } else {
-            Err(format_err!("{} is not a valid temperature in Celcius"))
+            Err(format_err!("{} is not a valid temperature in Celcius", t))
         }
     }
     pub fn from_fahrenheit(t: f64) -> Result<Self, Error> {
         if t >= -FAHRENHEIT_OFFSET {
             Ok(Self((t + FAHRENHEIT_OFFSET) / FAHRENHEIT_FACTOR))
         } else {
-            Err(format_err!("{} is not a valid temperature in Fahrenheit"))
+            Err(format_err!(
+                "{} is not a valid temperature in Fahrenheit",
+                t
+            ))
+        }
+    }
+    /// Mean of `temps`, averaged in Kelvin, `None` if `temps` is empty
+    /// ```
+    /// use weather_util_rust::temperature::Temperature;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let temps = [Temperature::from_celcius(10.0)?, Temperature::from_celcius(20.0)?];
+    /// let average = Temperature::average(&temps).unwrap();
+    /// assert!((average.celcius() - 15.0).abs() < 1e-6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn average(temps: &[Self]) -> Option<Self> {
+        if temps.is_empty() {
+            return None;
+        }
+        let total: f64 = temps.iter().map(|temp| temp.0).sum();
+        Some(Self(total / temps.len() as f64))
+    }
+
+    /// Linear interpolation from `self` (`fraction` 0.0) to `other`
+    /// (`fraction` 1.0)
+    /// ```
+    /// use weather_util_rust::temperature::Temperature;
+    /// # use anyhow::Error;
+    /// # fn main() -> Result<(), Error> {
+    /// let (a, b) = (Temperature::from_celcius(10.0)?, Temperature::from_celcius(16.0)?);
+    /// assert!((a.interpolate(b, 0.5).celcius() - 13.0).abs() < 1e-6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn interpolate(self, other: Self, fraction: f64) -> Self {
+        let fraction = fraction.clamp(0.0, 1.0);
+        Self(self.0 + (other.0 - self.0) * fraction)
+    }
+
+    /// Format `value`, a temperature in any unit, with `precision` decimal
+    /// places, or as `NON_FINITE_DISPLAY` if it is NaN or infinite so that
+    /// a single corrupt field doesn't print as "NaN" or "inf"
+    /// ```
+    /// use weather_util_rust::temperature::Temperature;
+    /// assert_eq!(Temperature::clamp_display_nan(41.04, 1), "41.0");
+    /// assert_eq!(Temperature::clamp_display_nan(f64::NAN, 1), "—");
+    /// ```
+    pub fn clamp_display_nan(value: f64, precision: usize) -> String {
+        if value.is_finite() {
+            format!("{:.*}", precision, value)
+        } else {
+            NON_FINITE_DISPLAY.to_string()
         }
     }
+
     pub fn kelvin(self) -> f64 {
         self.0
     }
@@ -59,3 +139,188 @@ impl Temperature {
         self.0 * FAHRENHEIT_FACTOR - FAHRENHEIT_OFFSET
     }
 }
+
+/// Difference between two temperatures, stored as Kelvin (the same size as
+/// degrees Celcius)
+#[derive(Debug, Default, PartialEq, Copy, Clone, PartialOrd, Serialize, Deserialize)]
+pub struct TemperatureDelta(f64);
+
+impl TemperatureDelta {
+    pub fn from_kelvin(delta: f64) -> Self {
+        Self(delta)
+    }
+    pub fn from_celcius(delta: f64) -> Self {
+        Self(delta)
+    }
+    pub fn from_fahrenheit(delta: f64) -> Self {
+        Self(delta / FAHRENHEIT_FACTOR)
+    }
+
+    pub fn kelvin(self) -> f64 {
+        self.0
+    }
+    pub fn celcius(self) -> f64 {
+        self.0
+    }
+    pub fn fahrenheit(self) -> f64 {
+        self.0 * FAHRENHEIT_FACTOR
+    }
+}
+
+/// ```
+/// use weather_util_rust::temperature::Temperature;
+/// # use anyhow::Error;
+/// # fn main() -> Result<(), Error> {
+/// let delta = Temperature::from_celcius(15.0)? - Temperature::from_celcius(10.0)?;
+/// assert!((delta.fahrenheit() - 9.0).abs() < 1e-6);
+/// # Ok(())
+/// # }
+/// ```
+impl Sub for Temperature {
+    type Output = TemperatureDelta;
+    fn sub(self, other: Self) -> Self::Output {
+        TemperatureDelta(self.0 - other.0)
+    }
+}
+
+/// Stops at absolute zero
+impl Add<TemperatureDelta> for Temperature {
+    type Output = Self;
+    fn add(self, delta: TemperatureDelta) -> Self::Output {
+        Self((self.0 + delta.0).max(0.0))
+    }
+}
+
+/// Stops at absolute zero
+impl Sub<TemperatureDelta> for Temperature {
+    type Output = Self;
+    fn sub(self, delta: TemperatureDelta) -> Self::Output {
+        self + -delta
+    }
+}
+
+impl Add for TemperatureDelta {
+    type Output = Self;
+    fn add(self, other: Self) -> Self::Output {
+        Self(self.0 + other.0)
+    }
+}
+
+impl Sub for TemperatureDelta {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self::Output {
+        Self(self.0 - other.0)
+    }
+}
+
+impl Neg for TemperatureDelta {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self(-self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::convert::TryFrom;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        temperature::{Temperature, TemperatureDelta},
+        weather_data::WeatherData,
+    };
+
+    #[test]
+    fn test_average() -> Result<(), Error> {
+        assert_eq!(Temperature::average(&[]), None);
+
+        let temp = Temperature::try_from(280.0)?;
+        assert_eq!(Temperature::average(&[temp]), Some(temp));
+
+        let temps = [
+            Temperature::try_from(270.0)?,
+            Temperature::try_from(280.0)?,
+            Temperature::try_from(293.0)?,
+        ];
+        assert_eq!(
+            Temperature::average(&temps),
+            Some(Temperature::try_from(281.0)?)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_temperature() -> Result<(), Error> {
+        assert!(Temperature::try_from(-0.1).is_err());
+        assert!(Temperature::try_from(f64::NAN).is_err());
+        assert!(Temperature::try_from(f64::INFINITY).is_err());
+        assert!(Temperature::from_celcius(-500.0).is_err());
+
+        let temp: Temperature = serde_json::from_str("0.0")?;
+        assert_eq!(temp.kelvin(), 0.0);
+        let err = serde_json::from_str::<Temperature>("-226.85").unwrap_err();
+        assert!(err.to_string().contains("absolute zero"), "{}", err);
+
+        let buf = include_str!("../tests/weather.json").replace("\"temp\": 278.18", "\"temp\": -1");
+        assert_ne!(buf, include_str!("../tests/weather.json"));
+        assert!(serde_json::from_str::<WeatherData>(&buf).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug() -> Result<(), Error> {
+        let temp = Temperature::from_celcius(15.0)?;
+        assert_eq!(
+            format!("{:?}", temp),
+            "Temperature(288.15K / 15.00C / 59.00F)"
+        );
+        assert_eq!(serde_json::to_string(&temp)?, "288.15");
+        Ok(())
+    }
+
+    #[test]
+    fn test_delta() -> Result<(), Error> {
+        let before = Temperature::from_fahrenheit(50.0)?;
+        let after = Temperature::from_celcius(before.celcius() + 5.0)?;
+        let delta = after - before;
+        assert!((delta.celcius() - 5.0).abs() < 1e-9);
+        assert!((delta.kelvin() - (after.kelvin() - before.kelvin())).abs() < 1e-9);
+        assert!((delta.fahrenheit() - (after.fahrenheit() - before.fahrenheit())).abs() < 1e-9);
+        assert!((delta.fahrenheit() - 9.0).abs() < 1e-9);
+
+        let rise = TemperatureDelta::from_celcius(5.0);
+        assert!(((before + rise).fahrenheit() - 59.0).abs() < 1e-9);
+        assert!(((after - rise).kelvin() - before.kelvin()).abs() < 1e-9);
+        assert!(
+            (TemperatureDelta::from_fahrenheit(9.0) - rise)
+                .kelvin()
+                .abs()
+                < 1e-9
+        );
+        assert_eq!((before - after), -delta);
+        assert_eq!(rise + rise, TemperatureDelta::from_kelvin(10.0));
+
+        let cold = Temperature::try_from(2.0)?;
+        assert_eq!((cold - rise).kelvin(), 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_clamp_display_nan() -> Result<(), Error> {
+        let a = Temperature::from_celcius(10.0)?;
+        let b = Temperature::from_celcius(16.0)?;
+        let nan = a.interpolate(b, f64::NAN);
+        assert!(nan.kelvin().is_nan());
+        let opts = DisplayOpts::default();
+        assert_eq!(opts.fahrenheit(nan, 1), "—°F");
+        assert_eq!(opts.celcius(nan, 2), "—°C");
+        let (primary, secondary) = opts.temperatures(nan, 1);
+        assert!(!primary.contains("NaN") && !secondary.contains("NaN"));
+
+        let inf = Temperature::from_celcius(f64::INFINITY)?;
+        assert_eq!(opts.celcius(inf, 1), "—°C");
+        assert_eq!(opts.celcius(a, 1), "10.0°C");
+        Ok(())
+    }
+}
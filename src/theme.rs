@@ -0,0 +1,112 @@
+use anyhow::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::display_opts::IconStyle;
+
+/// Output preset selected with `--theme`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Few decimals, no location and the forecast grouped by week
+    Minimal,
+    /// Colors, condition icons and every available detail
+    Detailed,
+    /// Plain ASCII without color, for terminals lacking unicode support
+    Ascii,
+    /// Colors and Nerd Font weather glyphs
+    Nerd,
+}
+
+/// Options set by a `Theme`, any flag given on the command line takes
+/// precedence
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ThemeDefaults {
+    pub color: bool,
+    pub ascii: bool,
+    pub verbose: bool,
+    pub hide_location: bool,
+    pub compact_forecast: bool,
+    pub icons: Option<IconStyle>,
+    pub precision: Option<usize>,
+}
+
+impl Theme {
+    pub fn all() -> [Self; 4] {
+        [Self::Minimal, Self::Detailed, Self::Ascii, Self::Nerd]
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Detailed => "detailed",
+            Self::Ascii => "ascii",
+            Self::Nerd => "nerd",
+        }
+    }
+
+    pub fn defaults(self) -> ThemeDefaults {
+        match self {
+            Self::Minimal => ThemeDefaults {
+                hide_location: true,
+                compact_forecast: true,
+                precision: Some(0),
+                ..ThemeDefaults::default()
+            },
+            Self::Detailed => ThemeDefaults {
+                color: true,
+                verbose: true,
+                icons: Some(IconStyle::Emoji),
+                ..ThemeDefaults::default()
+            },
+            Self::Ascii => ThemeDefaults {
+                ascii: true,
+                ..ThemeDefaults::default()
+            },
+            Self::Nerd => ThemeDefaults {
+                color: true,
+                icons: Some(IconStyle::Glyph),
+                ..ThemeDefaults::default()
+            },
+        }
+    }
+}
+
+impl fmt::Display for Theme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for Theme {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|theme| theme.to_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| {
+                format_err!(
+                    "{} is not a valid theme (minimal, detailed, ascii, nerd)",
+                    s
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::theme::Theme;
+
+    #[test]
+    fn test_theme_from_str() -> Result<(), Error> {
+        for theme in Theme::all().iter() {
+            assert_eq!(theme.to_str().parse::<Theme>()?, *theme);
+        }
+        assert_eq!(" Nerd ".parse::<Theme>()?, Theme::Nerd);
+        assert!("fancy".parse::<Theme>().is_err());
+        Ok(())
+    }
+}
@@ -1,5 +1,5 @@
 use chrono::{DateTime, TimeZone, Utc};
-use serde::{self, Deserialize, Deserializer, Serializer};
+use serde::{self, de, Deserialize, Deserializer, Serializer};
 
 /// ! serialize function required by `#[serde(with=timestamp)]`
 pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
@@ -14,5 +14,126 @@ pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    i64::deserialize(deserializer).map(|t| Utc.timestamp(t, 0))
+    i64::deserialize(deserializer).and_then(|t| {
+        Utc.timestamp_opt(t, 0)
+            .single()
+            .ok_or_else(|| de::Error::custom(format!("{} is not a valid timestamp", t)))
+    })
+}
+
+/// Like the parent module but keeping fractional seconds, e.g.
+/// `1579458222.25`, with `#[serde(with = "timestamp::fractional")]`.
+/// Whole seconds are still written as integers and either form is accepted.
+pub mod fractional {
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{self, de, Deserializer, Serializer};
+    use std::{convert::TryFrom, fmt};
+
+    const NANOS_PER_SECOND: f64 = 1e9;
+
+    pub fn serialize<S>(date: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match date.timestamp_subsec_nanos() {
+            0 => serializer.serialize_i64(date.timestamp()),
+            nanos => serializer
+                .serialize_f64(date.timestamp() as f64 + f64::from(nanos) / NANOS_PER_SECOND),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(TimestampVisitor)
+    }
+
+    struct TimestampVisitor;
+
+    fn invalid<E: de::Error>(t: impl fmt::Display) -> E {
+        E::custom(format!("{} is not a valid timestamp", t))
+    }
+
+    impl<'de> de::Visitor<'de> for TimestampVisitor {
+        type Value = DateTime<Utc>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "seconds since the epoch")
+        }
+
+        fn visit_i64<E: de::Error>(self, t: i64) -> Result<Self::Value, E> {
+            Utc.timestamp_opt(t, 0).single().ok_or_else(|| invalid(t))
+        }
+
+        fn visit_u64<E: de::Error>(self, t: u64) -> Result<Self::Value, E> {
+            let secs = i64::try_from(t).map_err(|_| invalid(t))?;
+            self.visit_i64(secs)
+        }
+
+        fn visit_f64<E: de::Error>(self, t: f64) -> Result<Self::Value, E> {
+            if !t.is_finite() {
+                return Err(invalid(t));
+            }
+            let secs = t.floor();
+            let nanos = ((t - secs) * NANOS_PER_SECOND).round();
+            // Rounding up to a whole second carries over
+            let (secs, nanos) = if nanos >= NANOS_PER_SECOND {
+                (secs + 1.0, 0.0)
+            } else {
+                (secs, nanos)
+            };
+            Utc.timestamp_opt(secs as i64, nanos as u32)
+                .single()
+                .ok_or_else(|| invalid(t))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde::{Deserialize, Serialize};
+
+    use crate::timestamp;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Whole {
+        #[serde(with = "timestamp")]
+        dt: DateTime<Utc>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fractional {
+        #[serde(with = "timestamp::fractional")]
+        dt: DateTime<Utc>,
+    }
+
+    #[test]
+    fn test_fractional_round_trip() -> Result<(), Error> {
+        for text in &[
+            r#"{"dt":1579458222.25}"#,
+            r#"{"dt":1579458222.123456}"#,
+            r#"{"dt":1579458222}"#,
+        ] {
+            let value: Fractional = serde_json::from_str(text)?;
+            assert_eq!(&serde_json::to_string(&value)?, text);
+        }
+        let value: Fractional = serde_json::from_str(r#"{"dt":1579458222.25}"#)?;
+        assert_eq!(value.dt.timestamp(), 1579458222);
+        assert_eq!(value.dt.timestamp_subsec_millis(), 250);
+        let value: Fractional = serde_json::from_str(r#"{"dt":-0.5}"#)?;
+        assert_eq!(value.dt, Utc.timestamp_opt(-1, 500_000_000).unwrap());
+        assert!(serde_json::from_str::<Fractional>(r#"{"dt":"noon"}"#).is_err());
+
+        // The default still truncates to (and requires) whole seconds
+        let dt = Utc.timestamp_opt(1579458222, 250_000_000).unwrap();
+        assert_eq!(
+            serde_json::to_string(&Whole { dt })?,
+            r#"{"dt":1579458222}"#
+        );
+        assert!(serde_json::from_str::<Whole>(r#"{"dt":1579458222.25}"#).is_err());
+        Ok(())
+    }
 }
@@ -15,13 +15,13 @@ impl TryFrom<i32> for TimeZone {
         if item > -86400 && item < 86400 {
             Ok(Self(item))
         } else {
-            Err(format_err!("{} is not a valid timezone"))
+            Err(format_err!("{} is not a valid timezone", item))
         }
     }
 }
 
-impl std::convert::Into<FixedOffset> for TimeZone {
-    fn into(self) -> FixedOffset {
-        FixedOffset::east(self.0)
+impl From<TimeZone> for FixedOffset {
+    fn from(item: TimeZone) -> Self {
+        Self::east_opt(item.0).expect("TimeZone is always within one day of UTC")
     }
 }
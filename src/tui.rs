@@ -0,0 +1,322 @@
+use anyhow::{format_err, Error};
+use chrono::FixedOffset;
+use futures::future::join;
+use ratatui::{
+    backend::Backend,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, Chart, Dataset, GraphType, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use std::time::{Duration, Instant};
+
+use crate::{
+    display_opts::DisplayOpts,
+    temperature::Temperature,
+    units::Units,
+    watch::MIN_WATCH_SECONDS,
+    weather_api::{WeatherApi, WeatherLocation},
+    weather_data::WeatherData,
+    weather_forecast::WeatherForecast,
+};
+
+/// Seconds between refreshes unless another interval is given
+pub const DEFAULT_TUI_REFRESH_SECONDS: u64 = 600;
+
+/// Written at the bottom of the screen
+const HELP: &str = "r: refresh  q: quit";
+
+/// What the TUI shows, replaced as a whole on every refresh
+#[derive(Debug, Clone, Default)]
+pub struct TuiState {
+    pub data: Option<WeatherData>,
+    pub forecast: Option<WeatherForecast>,
+    /// Errors of the last refresh, shown in place of what failed
+    pub errors: Vec<String>,
+}
+
+impl TuiState {
+    /// Fetch the current conditions and the forecast at `location`, either
+    /// failing only leaves out that part
+    pub async fn fetch(api: &WeatherApi, location: &WeatherLocation) -> Self {
+        let (data, forecast) = join(
+            api.get_weather_data(location),
+            api.get_weather_forecast(location),
+        )
+        .await;
+        let mut errors = Vec::new();
+        let data = data
+            .map_err(|e| errors.push(format!("current conditions: {}", e)))
+            .ok();
+        let forecast = forecast
+            .map_err(|e| errors.push(format!("forecast: {}", e)))
+            .ok();
+        Self {
+            data,
+            forecast,
+            errors,
+        }
+    }
+
+    /// Lay out the current conditions above the forecast table and the
+    /// temperature graph, followed by any errors and the key help
+    pub fn draw(&self, frame: &mut Frame, opts: &DisplayOpts) {
+        let conditions = self.current_conditions(opts);
+        let [current, forecast, footer] = Layout::vertical([
+            Constraint::Length(conditions.lines().count() as u16 + 2),
+            Constraint::Min(10),
+            Constraint::Length(1 + self.errors.len() as u16),
+        ])
+        .areas(frame.area());
+        let [table, graph] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(forecast);
+
+        let block = Block::bordered().title("Current conditions");
+        frame.render_widget(Paragraph::new(conditions).block(block), current);
+        self.draw_forecast(frame, opts, table, graph);
+        let footer_lines: Vec<Line> = self
+            .errors
+            .iter()
+            .map(|e| Line::from(format!("Failed to get the {}", e)))
+            .chain(Some(Line::from(HELP)))
+            .collect();
+        frame.render_widget(Paragraph::new(footer_lines), footer);
+    }
+
+    fn current_conditions(&self, opts: &DisplayOpts) -> String {
+        match &self.data {
+            Some(data) => {
+                // escape codes don't belong in a widget
+                let mut opts = opts.clone();
+                opts.temperature_colors = None;
+                let mut buf = Vec::new();
+                match data
+                    .get_current_conditions(&mut buf, &opts)
+                    .and_then(|()| String::from_utf8(buf).map_err(Error::from))
+                {
+                    Ok(text) => text.replace('\t', "  "),
+                    Err(e) => e.to_string(),
+                }
+            }
+            None => "No current conditions".to_string(),
+        }
+    }
+
+    fn draw_forecast(&self, frame: &mut Frame, opts: &DisplayOpts, table: Rect, graph: Rect) {
+        let forecast = match self.forecast.as_ref().filter(|f| !f.list.is_empty()) {
+            Some(forecast) => forecast,
+            None => {
+                let block = Block::bordered().title("Forecast");
+                frame.render_widget(Paragraph::new("No forecast available").block(block), table);
+                return;
+            }
+        };
+        frame.render_widget(forecast_table(forecast, opts), table);
+
+        let points: Vec<(f64, f64)> = forecast
+            .get_hourly_temperatures()
+            .iter()
+            .enumerate()
+            .map(|(hour, t)| (hour as f64, temperature_value(t.temp, opts.units)))
+            .collect();
+        let (low, high) = points
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), (_, t)| {
+                (low.min(*t), high.max(*t))
+            });
+        let (low, high) = ((low - 1.0).floor(), (high + 1.0).ceil());
+        let hours = points.len().saturating_sub(1) as f64;
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .data(&points);
+        let chart = Chart::new(vec![dataset])
+            .block(Block::bordered().title("Temperature"))
+            .x_axis(
+                Axis::default()
+                    .title("hours")
+                    .bounds([0.0, hours])
+                    .labels(vec!["0".to_string(), format!("{}", hours)]),
+            )
+            .y_axis(
+                Axis::default()
+                    .title(units_suffix(opts.units))
+                    .bounds([low, high])
+                    .labels(vec![format!("{}", low), format!("{}", high)]),
+            );
+        frame.render_widget(chart, graph);
+    }
+}
+
+/// A row per `ForecastSummary` day with the high, low and precipitation
+fn forecast_table(forecast: &WeatherForecast, opts: &DisplayOpts) -> Table<'static> {
+    let summary = forecast.get_forecast_summary_of(opts.forecast_field);
+    let fo: FixedOffset = forecast.city.timezone.into();
+    let today = opts.now().with_timezone(&fo).date_naive();
+    let rows: Vec<Row> = summary
+        .days
+        .iter()
+        .map(|day| {
+            Row::new(vec![
+                day.localized_label(today, &opts.locale),
+                opts.temperatures(day.high, 0).0,
+                opts.temperatures(day.low, 0).0,
+                opts.precipitation(day.rain + day.snow),
+            ])
+        })
+        .collect();
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(8),
+        Constraint::Min(8),
+    ];
+    Table::new(rows, widths)
+        .header(
+            Row::new(vec!["Day", "High", "Low", "Precip"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title("Forecast"))
+}
+
+fn temperature_value(temp: Temperature, units: Units) -> f64 {
+    match units {
+        Units::Imperial => temp.fahrenheit(),
+        Units::Metric => temp.celcius(),
+    }
+}
+
+fn units_suffix(units: Units) -> &'static str {
+    match units {
+        Units::Imperial => "°F",
+        Units::Metric => "°C",
+    }
+}
+
+/// Take over the terminal showing the conditions at `location`, refreshed
+/// every `interval` (at least `MIN_WATCH_SECONDS`) or when r is pressed,
+/// until q (or Esc) is pressed
+pub async fn run(
+    api: &WeatherApi,
+    location: &WeatherLocation,
+    opts: &DisplayOpts,
+    interval: Duration,
+) -> Result<(), Error> {
+    let interval = interval.max(Duration::from_secs(MIN_WATCH_SECONDS));
+    let mut terminal = ratatui::init();
+    let result = run_loop(&mut terminal, api, location, opts, interval).await;
+    ratatui::restore();
+    result
+}
+
+async fn run_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    api: &WeatherApi,
+    location: &WeatherLocation,
+    opts: &DisplayOpts,
+    interval: Duration,
+) -> Result<(), Error> {
+    let mut state = TuiState::fetch(api, location).await;
+    let mut refreshed = Instant::now();
+    loop {
+        terminal.draw(|frame| state.draw(frame, opts))?;
+        let timeout = interval
+            .checked_sub(refreshed.elapsed())
+            .unwrap_or_default();
+        let refresh = match tokio::task::spawn_blocking(move || next_event(timeout)).await?? {
+            Some(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('r') => true,
+                _ => false,
+            },
+            // e.g. a resize, only redraw
+            Some(_) => false,
+            None => true,
+        };
+        if refresh {
+            state = TuiState::fetch(api, location).await;
+            refreshed = Instant::now();
+        }
+    }
+}
+
+/// The next terminal event within `timeout`, `None` if there is none
+fn next_event(timeout: Duration) -> Result<Option<Event>, Error> {
+    if event::poll(timeout).map_err(|e| format_err!("Failed to read the terminal: {}", e))? {
+        Ok(Some(event::read()?))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{TimeZone, Utc};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use crate::{
+        display_opts::DisplayOpts, tui::TuiState, weather_data::WeatherData,
+        weather_forecast::WeatherForecast,
+    };
+
+    fn render(state: &TuiState) -> Result<String, Error> {
+        let mut terminal = Terminal::new(TestBackend::new(120, 40))?;
+        let opts =
+            DisplayOpts::default().with_now(Utc.with_ymd_and_hms(2020, 1, 19, 18, 0, 0).unwrap());
+        terminal.draw(|frame| state.draw(frame, &opts))?;
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect()
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    }
+
+    #[test]
+    fn test_draw() -> Result<(), Error> {
+        let state = TuiState {
+            data: Some(WeatherData::from_json(include_str!(
+                "../tests/weather.json"
+            ))?),
+            forecast: Some(WeatherForecast::from_json(include_str!(
+                "../tests/forecast.json"
+            ))?),
+            errors: Vec::new(),
+        };
+        let screen = render(&state)?;
+        assert!(screen.contains("Current conditions"), "{}", screen);
+        assert!(screen.contains("Astoria, US"), "{}", screen);
+        assert!(screen.contains("Temperature: 41.05°F"), "{}", screen);
+        assert!(screen.contains("Day"), "{}", screen);
+        assert!(screen.contains("Today"), "{}", screen);
+        assert!(screen.contains("Tomorrow"), "{}", screen);
+        // the temperature graph's axis titles and bound labels
+        assert!(screen.contains("│41│°F "), "{}", screen);
+        assert!(screen.contains("│17│"), "{}", screen);
+        assert!(screen.contains(" hours│"), "{}", screen);
+        assert!(screen.contains(" 117│"), "{}", screen);
+        assert!(screen.contains("q: quit"), "{}", screen);
+
+        let state = TuiState {
+            forecast: None,
+            errors: vec!["forecast: timed out".into()],
+            ..state
+        };
+        let screen = render(&state)?;
+        assert!(screen.contains("No forecast available"), "{}", screen);
+        assert!(
+            screen.contains("Failed to get the forecast: timed out"),
+            "{}",
+            screen
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{format_err, Error};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::{precipitation::PrecipitationUnit, speed::SpeedUnit};
+
+/// System of units used to display temperatures, precipitation and (unless
+/// a wind unit is given) wind speeds
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    #[default]
+    Imperial,
+    Metric,
+}
+
+impl Units {
+    pub fn all() -> [Self; 2] {
+        [Self::Imperial, Self::Metric]
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Imperial => "imperial",
+            Self::Metric => "metric",
+        }
+    }
+
+    /// Default unit for wind speeds
+    pub fn wind_unit(self) -> SpeedUnit {
+        match self {
+            Self::Imperial => SpeedUnit::Mph,
+            Self::Metric => SpeedUnit::Kmh,
+        }
+    }
+
+    /// Default unit for rain and snow
+    pub fn precipitation_unit(self) -> PrecipitationUnit {
+        match self {
+            Self::Imperial => PrecipitationUnit::Inches,
+            Self::Metric => PrecipitationUnit::Millimeters,
+        }
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for Units {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|units| units.to_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| format_err!("{} is not a valid unit system (imperial, metric)", s))
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt;
+
+use crate::{
+    display_opts::DisplayOpts, temperature::Temperature, units::Units, weather_data::WeatherData,
+};
+
+/// A new state has to be seen on this many consecutive polls by default
+/// before it is reported, so that conditions flapping between two values
+/// don't fire on every poll
+pub const DEFAULT_DEBOUNCE: usize = 2;
+
+/// Shortest interval between polls of `--watch` (and refreshes of `--tui`)
+/// in seconds, anything shorter would hammer the api
+pub const MIN_WATCH_SECONDS: u64 = 10;
+
+/// Exit code of `--watch` with `--exit-on-change` when a change was detected
+pub const CONDITIONS_CHANGED_EXIT_CODE: i32 = 3;
+
+/// What a `ChangeDetector` tracks of an observation
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchState {
+    /// Main condition, e.g. `Clear` or `Rain`
+    pub condition: String,
+    /// Whether the temperature is at or above the threshold, `None` without
+    /// one
+    pub above_threshold: Option<bool>,
+}
+
+/// The tracked state changed from `previous` to `current`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeEvent {
+    pub previous: WatchState,
+    pub current: WatchState,
+}
+
+impl ChangeEvent {
+    /// Whether the main condition changed (e.g. it started raining)
+    pub fn condition_changed(&self) -> bool {
+        self.previous.condition != self.current.condition
+    }
+
+    /// Whether the temperature crossed the threshold
+    pub fn threshold_crossed(&self) -> bool {
+        self.previous.above_threshold != self.current.above_threshold
+    }
+
+    /// e.g. `Clear -> Rain, temperature rose above the threshold`
+    pub fn description(&self) -> String {
+        let mut parts = Vec::new();
+        if self.condition_changed() {
+            parts.push(format!(
+                "{} -> {}",
+                self.previous.condition, self.current.condition
+            ));
+        }
+        match self
+            .current
+            .above_threshold
+            .filter(|_| self.threshold_crossed())
+        {
+            Some(true) => parts.push("temperature rose above the threshold".into()),
+            Some(false) => parts.push("temperature fell below the threshold".into()),
+            None => {}
+        }
+        parts.join(", ")
+    }
+
+    /// Environment variables of the `--on-change` command, with the
+    /// temperature in the primary units of `opts`
+    pub fn hook_env(&self, data: &WeatherData, opts: &DisplayOpts) -> Vec<(&'static str, String)> {
+        let temp = match opts.units {
+            Units::Imperial => data.main.temp.fahrenheit(),
+            Units::Metric => data.main.temp.celcius(),
+        };
+        let mut events = Vec::new();
+        if self.condition_changed() {
+            events.push("condition");
+        }
+        if self.threshold_crossed() {
+            events.push("threshold");
+        }
+        vec![
+            ("WEATHER_EVENT", events.join(",")),
+            ("WEATHER_LOCATION", data.name.clone()),
+            ("WEATHER_CONDITION", self.current.condition.clone()),
+            (
+                "WEATHER_PREVIOUS_CONDITION",
+                self.previous.condition.clone(),
+            ),
+            (
+                "WEATHER_DESCRIPTION",
+                data.weather
+                    .first()
+                    .map_or_else(String::new, |cond| cond.description.clone()),
+            ),
+            ("WEATHER_TEMPERATURE", format!("{:.1}", temp)),
+            ("WEATHER_HUMIDITY", data.main.humidity.to_string()),
+            (
+                "WEATHER_WIND_SPEED",
+                format!("{:.1}", data.wind.speed.value(opts.wind_unit)),
+            ),
+        ]
+    }
+}
+
+/// Error ending `--watch` with `--exit-on-change`, `main` exits with
+/// `CONDITIONS_CHANGED_EXIT_CODE` on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionsChanged(pub ChangeEvent);
+
+impl fmt::Display for ConditionsChanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "conditions changed: {}", self.0.description())
+    }
+}
+
+impl std::error::Error for ConditionsChanged {}
+
+/// Reports when the main condition changes or the temperature crosses a
+/// threshold between polls of `--watch`.  The first observation is the
+/// baseline, a change is reported once it has been seen `debounce` times in
+/// a row.
+#[derive(Debug, Clone)]
+pub struct ChangeDetector {
+    threshold: Option<Temperature>,
+    debounce: usize,
+    reported: Option<WatchState>,
+    pending: Option<(WatchState, usize)>,
+}
+
+impl Default for ChangeDetector {
+    fn default() -> Self {
+        Self {
+            threshold: None,
+            debounce: DEFAULT_DEBOUNCE,
+            reported: None,
+            pending: None,
+        }
+    }
+}
+
+impl ChangeDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also report the temperature crossing `threshold`
+    pub fn with_threshold(self, threshold: Temperature) -> Self {
+        Self {
+            threshold: Some(threshold),
+            ..self
+        }
+    }
+
+    /// Report a change once it has been seen `debounce` (at least 1) times
+    /// in a row
+    pub fn with_debounce(self, debounce: usize) -> Self {
+        Self {
+            debounce: debounce.max(1),
+            ..self
+        }
+    }
+
+    fn get_state(&self, data: &WeatherData) -> WatchState {
+        WatchState {
+            condition: data
+                .weather
+                .first()
+                .map_or_else(String::new, |cond| cond.main.clone()),
+            above_threshold: self.threshold.map(|threshold| data.main.temp >= threshold),
+        }
+    }
+
+    /// Track the conditions of a poll, returning the change if one is
+    /// reported
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::watch::ChangeDetector;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let mut data = WeatherData::from_json(&buf)?;
+    /// let mut detector = ChangeDetector::new().with_debounce(1);
+    /// assert!(detector.observe(&data).is_none());
+    ///
+    /// data.weather[0].main = "Rain".into();
+    /// let event = detector.observe(&data).unwrap();
+    /// assert_eq!(event.description(), "Clear -> Rain");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn observe(&mut self, data: &WeatherData) -> Option<ChangeEvent> {
+        let state = self.get_state(data);
+        let previous = match &self.reported {
+            Some(reported) if *reported != state => reported.clone(),
+            Some(_) => {
+                self.pending = None;
+                return None;
+            }
+            None => {
+                self.reported = Some(state);
+                return None;
+            }
+        };
+        let seen = match self.pending.take() {
+            Some((pending, seen)) if pending == state => seen + 1,
+            _ => 1,
+        };
+        if seen < self.debounce {
+            self.pending = Some((state, seen));
+            return None;
+        }
+        self.reported = Some(state.clone());
+        Some(ChangeEvent {
+            previous,
+            current: state,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        temperature::Temperature,
+        units::Units,
+        watch::{ChangeDetector, WatchState},
+        weather_data::WeatherData,
+    };
+
+    fn with_condition(data: &WeatherData, condition: &str) -> WeatherData {
+        let mut data = data.clone();
+        data.weather[0].main = condition.into();
+        data
+    }
+
+    #[test]
+    fn test_change_detector() -> Result<(), Error> {
+        let clear = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let rain = with_condition(&clear, "Rain");
+        let mut detector = ChangeDetector::new();
+
+        // The baseline and unchanged polls never fire
+        assert!(detector.observe(&clear).is_none());
+        assert!(detector.observe(&clear).is_none());
+
+        // Flapping doesn't fire either
+        for _ in 0..3 {
+            assert!(detector.observe(&rain).is_none());
+            assert!(detector.observe(&clear).is_none());
+        }
+
+        assert!(detector.observe(&rain).is_none());
+        let event = detector.observe(&rain).unwrap();
+        assert!(event.condition_changed());
+        assert!(!event.threshold_crossed());
+        assert_eq!(event.previous.condition, "Clear");
+        assert_eq!(event.current.condition, "Rain");
+        assert!(detector.observe(&rain).is_none());
+        assert!(detector.observe(&rain).is_none());
+
+        // Back to clear is another change
+        assert!(detector.observe(&clear).is_none());
+        assert_eq!(
+            detector.observe(&clear).map(|e| e.description()),
+            Some("Rain -> Clear".into())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_threshold() -> Result<(), Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let mut warmer = data.clone();
+        warmer.main.temp = Temperature::from_fahrenheit(45.0)?;
+        let mut detector = ChangeDetector::new()
+            .with_threshold(Temperature::from_fahrenheit(43.0)?)
+            .with_debounce(1);
+
+        assert!(detector.observe(&data).is_none());
+        let event = detector.observe(&warmer).unwrap();
+        assert!(event.threshold_crossed());
+        assert!(!event.condition_changed());
+        assert_eq!(event.description(), "temperature rose above the threshold");
+        assert_eq!(
+            event.current,
+            WatchState {
+                condition: "Clear".into(),
+                above_threshold: Some(true),
+            }
+        );
+
+        let env = event.hook_env(&warmer, &DisplayOpts::default().with_units(Units::Metric));
+        let get = |key| {
+            env.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(get("WEATHER_EVENT"), Some("threshold"));
+        assert_eq!(get("WEATHER_LOCATION"), Some("Astoria"));
+        assert_eq!(get("WEATHER_CONDITION"), Some("Clear"));
+        assert_eq!(get("WEATHER_TEMPERATURE"), Some("7.2"));
+
+        // A temperature change staying above the threshold isn't reported
+        warmer.main.temp = Temperature::from_fahrenheit(50.0)?;
+        assert!(detector.observe(&warmer).is_none());
+        let event = detector.observe(&data).unwrap();
+        assert_eq!(event.description(), "temperature fell below the threshold");
+        Ok(())
+    }
+}
@@ -1,13 +1,33 @@
 use anyhow::{format_err, Error};
-use reqwest::{Client, Url};
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, Stream, StreamExt};
+use log::{debug, warn};
+use retry::delay::{jitter, Exponential};
 use std::{
     fmt,
     hash::{Hash, Hasher},
+    sync::Arc,
+    time,
 };
+use url::Url;
 
+#[cfg(feature = "native-client")]
+use crate::http_client::ReqwestClient;
 use crate::{
-    latitude::Latitude, longitude::Longitude, weather_data::WeatherData,
-    weather_forecast::WeatherForecast,
+    disk_cache::DiskCache,
+    geocoder::no_location_found,
+    http_client::{HttpClient, HttpResponse},
+    latitude::Latitude,
+    longitude::Longitude,
+    memory_cache::MemoryCache,
+    resolved_location::ResolvedLocation,
+    round_trip::unknown_keys,
+    weather_box::{BoundingBox, WeatherBox},
+    weather_daily_forecast::{WeatherDailyForecast, MAX_DAILY_FORECAST_DAYS},
+    weather_data::WeatherData,
+    weather_find::WeatherFind,
+    weather_forecast::{WeatherForecast, FORECAST_SLOTS_PER_DAY, MAX_FORECAST_SLOTS},
+    weather_onecall::{OneCallSection, WeatherOneCall},
 };
 
 #[derive(Clone, Debug)]
@@ -66,22 +86,186 @@ impl WeatherLocation {
     }
 }
 
-/// `WeatherApi` contains a `reqwest` Client and all the metadata required to
-/// query the openweathermap.org api.
-#[derive(Default, Clone)]
+/// How far back the timemachine api can be queried
+pub const HISTORY_DAYS: i64 = 5;
+
+/// Number of times a rate limited or failed request is retried by default
+pub const DEFAULT_RETRIES: usize = 3;
+
+/// Requests `get_weather_data_many` makes at once by default
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Longest `Retry-After` we are willing to wait out before giving up
+pub const MAX_RETRY_AFTER: time::Duration = time::Duration::from_secs(60);
+
+const RETRY_BASE_MILLIS: u64 = 250;
+
+/// Path of the geocoding api, used instead of the `api_path`
+pub const GEOCODING_PATH: &str = "geo/1.0/";
+
+/// Query parameter selecting the units of the responses, never sent: the
+/// default (standard) units report temperatures in kelvin, which is what
+/// `Temperature` deserializes, and conversions happen when displaying
+const UNITS_PARAMETER: &str = "units";
+
+/// Number of 3 hour forecast slots covering the next `days` days (8 per
+/// day), between 1 and `MAX_FORECAST_SLOTS`.  The window starts now, so the
+/// slots also cover the first `days` calendar days.
+/// ```
+/// use weather_util_rust::weather_api::forecast_cnt;
+/// assert_eq!(forecast_cnt(2), 16);
+/// assert_eq!(forecast_cnt(10), 40);
+/// ```
+pub fn forecast_cnt(days: usize) -> usize {
+    days.saturating_mul(FORECAST_SLOTS_PER_DAY)
+        .clamp(1, MAX_FORECAST_SLOTS)
+}
+
+/// Error returned when the api responds with `429 Too Many Requests`,
+/// carrying whatever rate limit information the response headers provided.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimited {
+    /// Parsed from the `Retry-After` header (seconds or an HTTP date)
+    pub retry_after: Option<time::Duration>,
+    /// Parsed from the `X-RateLimit-Limit` header
+    pub limit: Option<String>,
+}
+
+impl RateLimited {
+    fn from_response(res: &HttpResponse, now: DateTime<Utc>) -> Self {
+        let get_header = |key: &str| res.header(key).map(str::trim);
+        let retry_after = get_header("retry-after").and_then(|value| {
+            value
+                .parse()
+                .ok()
+                .map(time::Duration::from_secs)
+                .or_else(|| {
+                    let dt = DateTime::parse_from_rfc2822(value).ok()?;
+                    let seconds = (dt.with_timezone(&Utc) - now).num_seconds().max(0);
+                    Some(time::Duration::from_secs(seconds as u64))
+                })
+        });
+        let limit = get_header("x-ratelimit-limit").map(ToString::to_string);
+        Self { retry_after, limit }
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rate limited")?;
+        if let Some(limit) = &self.limit {
+            write!(f, " (limit {})", limit)?;
+        }
+        if let Some(retry_after) = self.retry_after {
+            write!(f, "; retry after {}s", retry_after.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Error returned when the api responds with a 5xx status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServerError(pub u16);
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "server error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Error returned when the api responds with `404 Not Found`, carrying the
+/// message of the response, e.g. "city not found"
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotFound(pub String);
+
+impl NotFound {
+    fn from_response(res: &HttpResponse) -> Self {
+        let message = serde_json::from_str::<serde_json::Value>(&res.body)
+            .ok()
+            .and_then(|body| body["message"].as_str().map(ToString::to_string))
+            .unwrap_or_else(|| "not found".into());
+        Self(message)
+    }
+}
+
+impl fmt::Display for NotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// A `NotFound` error for a city name or zipcode reported as the geocoder
+/// reports a query without matches, other errors are returned unchanged
+fn location_not_found(location: &WeatherLocation, error: Error) -> Error {
+    if !error.is::<NotFound>() {
+        return error;
+    }
+    match location {
+        WeatherLocation::CityName(name) => no_location_found(name),
+        WeatherLocation::ZipCode {
+            zipcode,
+            country_code: Some(country_code),
+        } => no_location_found(&format!("{},{}", zipcode, country_code)),
+        WeatherLocation::ZipCode { zipcode, .. } => no_location_found(&zipcode.to_string()),
+        WeatherLocation::LatLon { .. } => error,
+    }
+}
+
+/// `User-Agent` sent with every request, e.g. `weather_util_rust/0.5.4`
+pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// Mask all but the first and last few characters of `api_key`, e.g.
+/// `9533...5b11`, so that it can be logged
+pub fn mask_api_key(api_key: &str) -> String {
+    let chars: Vec<_> = api_key.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let start: String = chars[..4].iter().collect();
+    let end: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}...{}", start, end)
+}
+
+/// `WeatherApi` contains a `HttpClient` (by default using `reqwest`) and all
+/// the metadata required to query the openweathermap.org api.
+#[derive(Clone)]
 pub struct WeatherApi {
-    client: Client,
-    api_key: String,
+    client: Arc<dyn HttpClient>,
+    /// Keys are tried in order, moving on to the next when one is rate
+    /// limited
+    api_keys: Vec<String>,
     api_endpoint: String,
     api_path: String,
+    retries: usize,
+    disk_cache: Option<DiskCache>,
+    /// Responses shared within the process, see `with_cache`
+    cache: Option<MemoryCache>,
+    offline: bool,
+    round_trip_check: bool,
+    strict: bool,
+    /// Requests made at once when fetching several locations
+    concurrency: usize,
+    /// Days the 3 hour forecast has to cover, all of them if unset
+    forecast_days: Option<usize>,
+    /// Request the current conditions as XML
+    #[cfg(feature = "xml")]
+    xml: bool,
 }
 
 impl fmt::Debug for WeatherApi {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keys: Vec<_> = self.api_keys.iter().map(|k| mask_api_key(k)).collect();
         write!(
             f,
             "WeatherApi(key={},endpoint={})",
-            self.api_key, self.api_endpoint
+            keys.join(","),
+            self.api_endpoint
         )
     }
 }
@@ -92,21 +276,74 @@ impl Hash for WeatherApi {
     }
 }
 
+#[cfg(feature = "native-client")]
+impl Default for WeatherApi {
+    fn default() -> Self {
+        Self {
+            client: Arc::new(ReqwestClient::default()),
+            api_keys: Vec::new(),
+            api_endpoint: String::new(),
+            api_path: String::new(),
+            retries: 0,
+            disk_cache: None,
+            cache: None,
+            offline: false,
+            round_trip_check: false,
+            strict: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            forecast_days: None,
+            #[cfg(feature = "xml")]
+            xml: false,
+        }
+    }
+}
+
 impl WeatherApi {
     /// Create `WeatherApi` instance specifying `api_key`, `api_endpoint` and
-    /// `api_path`
+    /// `api_path`.  Requests use https unless `api_endpoint` includes a
+    /// scheme.
+    #[cfg(feature = "native-client")]
     pub fn new(api_key: &str, api_endpoint: &str, api_path: &str) -> Self {
+        Self::from_http_client(ReqwestClient::default(), api_key, api_endpoint, api_path)
+    }
+
+    /// Same as `new`, making requests with `client`
+    pub fn from_http_client<C: HttpClient + 'static>(
+        client: C,
+        api_key: &str,
+        api_endpoint: &str,
+        api_path: &str,
+    ) -> Self {
         Self {
-            client: Client::new(),
-            api_key: api_key.into(),
+            client: Arc::new(client),
+            api_keys: vec![api_key.into()],
             api_endpoint: api_endpoint.into(),
             api_path: api_path.into(),
+            retries: DEFAULT_RETRIES,
+            disk_cache: None,
+            cache: None,
+            offline: false,
+            round_trip_check: false,
+            strict: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            forecast_days: None,
+            #[cfg(feature = "xml")]
+            xml: false,
         }
     }
 
     pub fn with_key(self, api_key: &str) -> Self {
         Self {
-            api_key: api_key.into(),
+            api_keys: vec![api_key.into()],
+            ..self
+        }
+    }
+
+    /// Use several api keys, if a request with one key is rate limited it is
+    /// repeated with the next
+    pub fn with_keys(self, api_keys: &[&str]) -> Self {
+        Self {
+            api_keys: api_keys.iter().map(|k| (*k).to_string()).collect(),
             ..self
         }
     }
@@ -125,10 +362,123 @@ impl WeatherApi {
         }
     }
 
+    /// Make requests with `client` instead
+    pub fn with_http_client<C: HttpClient + 'static>(self, client: C) -> Self {
+        Self {
+            client: Arc::new(client),
+            ..self
+        }
+    }
+
+    /// Number of times to retry a rate limited (429) or failed (5xx) request
+    pub fn with_retries(self, retries: usize) -> Self {
+        Self { retries, ..self }
+    }
+
+    /// Store every response in `disk_cache`
+    pub fn with_disk_cache(self, disk_cache: DiskCache) -> Self {
+        Self {
+            disk_cache: Some(disk_cache),
+            ..self
+        }
+    }
+
+    /// Serve repeated requests from `cache` while they're fresh, clones of
+    /// the cache can be shared between `WeatherApi`s
+    pub fn with_cache(self, cache: MemoryCache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Never make a request, only serve responses from the disk cache
+    pub fn with_offline(self, offline: bool) -> Self {
+        Self { offline, ..self }
+    }
+
+    /// Report any fields of the responses that are lost by deserialization
+    /// (see `round_trip::unknown_keys`) as warnings, written to stderr by
+    /// the command line tool
+    pub fn with_round_trip_check(self, round_trip_check: bool) -> Self {
+        Self {
+            round_trip_check,
+            ..self
+        }
+    }
+
+    /// Fail on responses with fields that are lost by deserialization
+    /// (see `round_trip::unknown_keys`) rather than ignoring them
+    pub fn with_strict(self, strict: bool) -> Self {
+        Self { strict, ..self }
+    }
+
+    /// Make at most `concurrency` (at least 1) requests at once in
+    /// `get_weather_data_many`
+    pub fn with_concurrency(self, concurrency: usize) -> Self {
+        Self {
+            concurrency: concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// Only request as many 3 hour forecast slots (`cnt`) as are needed to
+    /// cover `days` days, see `forecast_cnt`
+    pub fn with_forecast_days(self, days: usize) -> Self {
+        Self {
+            forecast_days: Some(days),
+            ..self
+        }
+    }
+
+    /// Request the current conditions with `mode=xml`, for when a proxy
+    /// mangles the JSON responses.  The other endpoints are unaffected.
+    #[cfg(feature = "xml")]
+    pub fn with_xml(self, xml: bool) -> Self {
+        Self { xml, ..self }
+    }
+
+    /// Options of the current conditions request, which adds the `mode`
+    fn get_weather_data_options(
+        &self,
+        location: &WeatherLocation,
+    ) -> Result<Vec<(&'static str, String)>, Error> {
+        #[allow(unused_mut)]
+        let mut options = self.get_options(location)?;
+        #[cfg(feature = "xml")]
+        if self.xml {
+            options.push(("mode", "xml".into()));
+        }
+        Ok(options)
+    }
+
     /// Get `WeatherData` from api
     pub async fn get_weather_data(&self, location: &WeatherLocation) -> Result<WeatherData, Error> {
-        let options = self.get_options(location)?;
-        self.run_api("weather", &options).await
+        let options = self.get_weather_data_options(location)?;
+        #[cfg(feature = "xml")]
+        if self.xml {
+            return self
+                .fetch_and_parse("weather", &options, WeatherData::from_xml)
+                .await
+                .map_err(|e| location_not_found(location, e));
+        }
+        self.run_api("weather", &options)
+            .await
+            .map_err(|e| location_not_found(location, e))
+    }
+
+    /// Get `WeatherData` for each of `locations` from api, in the same
+    /// order.  At most `concurrency` requests are in flight at a time, so
+    /// that many locations don't trip the rate limit.
+    pub async fn get_weather_data_many(
+        &self,
+        locations: &[WeatherLocation],
+    ) -> Vec<Result<WeatherData, Error>> {
+        stream::iter(locations)
+            .map(|location| self.get_weather_data(location))
+            .buffered(self.concurrency)
+            .collect()
+            .await
     }
 
     /// Get `WeatherForecast` from api
@@ -136,8 +486,212 @@ impl WeatherApi {
         &self,
         location: &WeatherLocation,
     ) -> Result<WeatherForecast, Error> {
-        let options = self.get_options(location)?;
-        self.run_api("forecast", &options).await
+        let options = self.get_forecast_options(location)?;
+        self.run_api("forecast", &options)
+            .await
+            .map_err(|e| location_not_found(location, e))
+    }
+
+    /// Get `WeatherDailyForecast` for the next `days` days from api, at
+    /// most `MAX_DAILY_FORECAST_DAYS`
+    pub async fn get_weather_daily_forecast(
+        &self,
+        location: &WeatherLocation,
+        days: usize,
+    ) -> Result<WeatherDailyForecast, Error> {
+        let options = self.get_daily_forecast_options(location, days)?;
+        self.run_api("forecast/daily", &options)
+            .await
+            .map_err(|e| location_not_found(location, e))
+    }
+
+    /// Get the current conditions of every station within `bbox` from api
+    pub async fn get_weather_box(&self, bbox: &BoundingBox) -> Result<WeatherBox, Error> {
+        self.run_api("box/city", &[("bbox", bbox.to_string())])
+            .await
+    }
+
+    /// Search for cities named `query`, e.g. `London` or `London,GB`,
+    /// with their current conditions
+    pub async fn get_weather_find(&self, query: &str) -> Result<WeatherFind, Error> {
+        self.run_api("find", &[("q", query.to_string())]).await
+    }
+
+    /// Get the best match for `query`, e.g. `London,GB`, from the direct
+    /// geocoding api.  It lives under `GEOCODING_PATH` rather than
+    /// `api_path`.
+    pub async fn get_geocoding(&self, query: &str) -> Result<Vec<ResolvedLocation>, Error> {
+        let options = [("q", query.to_string()), ("limit", "1".to_string())];
+        self.clone()
+            .with_path(GEOCODING_PATH)
+            .run_api("direct", &options)
+            .await
+    }
+
+    /// Get `WeatherOneCall` from api, leaving out the `exclude` sections.
+    /// The One Call api requires a latitude and longitude.
+    pub async fn get_weather_onecall(
+        &self,
+        location: &WeatherLocation,
+        exclude: &[OneCallSection],
+    ) -> Result<WeatherOneCall, Error> {
+        let options = self.get_onecall_options(location, exclude)?;
+        self.run_api("onecall", &options).await
+    }
+
+    /// Get historical conditions at `dt` from the One Call timemachine api,
+    /// `dt` must be within the last `HISTORY_DAYS` days.
+    pub async fn get_weather_history(
+        &self,
+        location: &WeatherLocation,
+        dt: DateTime<Utc>,
+    ) -> Result<WeatherOneCall, Error> {
+        Self::validate_history_date(dt, Utc::now())?;
+        let mut options = self.get_onecall_options(location, &[])?;
+        options.push(("dt", dt.timestamp().to_string()));
+        self.run_api("onecall/timemachine", &options).await
+    }
+
+    /// Poll the current conditions at `location` every `interval`, starting
+    /// right away.  Polls go through the cache (when set) like any other
+    /// request and the delay of the `HttpClient` is used between them, so it
+    /// works the same natively and in the browser.  The stream never ends by
+    /// itself, dropping it stops the polling.
+    pub fn watch(
+        &self,
+        location: &WeatherLocation,
+        interval: time::Duration,
+    ) -> impl Stream<Item = Result<WeatherData, Error>> {
+        let state = (self.clone(), location.clone(), true);
+        stream::unfold(state, move |(api, location, first)| async move {
+            if !first {
+                api.client.delay(interval).await;
+            }
+            let data = api.get_weather_data(&location).await;
+            Some((data, (api, location, false)))
+        })
+    }
+
+    /// Url `get_weather_data` would request, with the api key masked
+    pub fn get_weather_data_url(&self, location: &WeatherLocation) -> Result<Url, Error> {
+        self.get_masked_url("weather", &self.get_weather_data_options(location)?)
+    }
+
+    /// Url `get_weather_forecast` would request, with the api key masked
+    pub fn get_weather_forecast_url(&self, location: &WeatherLocation) -> Result<Url, Error> {
+        self.get_masked_url("forecast", &self.get_forecast_options(location)?)
+    }
+
+    /// Url `get_weather_daily_forecast` would request, with the api key masked
+    pub fn get_weather_daily_forecast_url(
+        &self,
+        location: &WeatherLocation,
+        days: usize,
+    ) -> Result<Url, Error> {
+        self.get_masked_url(
+            "forecast/daily",
+            &self.get_daily_forecast_options(location, days)?,
+        )
+    }
+
+    /// Url `get_weather_box` would request, with the api key masked
+    pub fn get_weather_box_url(&self, bbox: &BoundingBox) -> Result<Url, Error> {
+        self.get_masked_url("box/city", &[("bbox", bbox.to_string())])
+    }
+
+    /// Url `get_weather_find` would request, with the api key masked
+    pub fn get_weather_find_url(&self, query: &str) -> Result<Url, Error> {
+        self.get_masked_url("find", &[("q", query.to_string())])
+    }
+
+    /// Url `get_weather_onecall` would request, with the api key masked
+    pub fn get_weather_onecall_url(
+        &self,
+        location: &WeatherLocation,
+        exclude: &[OneCallSection],
+    ) -> Result<Url, Error> {
+        self.get_masked_url("onecall", &self.get_onecall_options(location, exclude)?)
+    }
+
+    /// Url `get_weather_history` would request, with the api key masked
+    pub fn get_weather_history_url(
+        &self,
+        location: &WeatherLocation,
+        dt: DateTime<Utc>,
+    ) -> Result<Url, Error> {
+        Self::validate_history_date(dt, Utc::now())?;
+        let mut options = self.get_onecall_options(location, &[])?;
+        options.push(("dt", dt.timestamp().to_string()));
+        self.get_masked_url("onecall/timemachine", &options)
+    }
+
+    fn get_masked_url(
+        &self,
+        command: &str,
+        options: &[(&'static str, String)],
+    ) -> Result<Url, Error> {
+        let api_key = self.api_keys.first().map_or("", String::as_str);
+        let mut options = options.to_vec();
+        options.push(("APPID", mask_api_key(api_key)));
+        self.get_url(command, &options)
+    }
+
+    fn validate_history_date(dt: DateTime<Utc>, now: DateTime<Utc>) -> Result<(), Error> {
+        if dt > now {
+            Err(format_err!("{} is in the future", dt))
+        } else if dt < now - Duration::days(HISTORY_DAYS) {
+            Err(format_err!(
+                "{} is more than {} days in the past",
+                dt,
+                HISTORY_DAYS
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn get_onecall_options(
+        &self,
+        location: &WeatherLocation,
+        exclude: &[OneCallSection],
+    ) -> Result<Vec<(&'static str, String)>, Error> {
+        let (latitude, longitude) = match location {
+            WeatherLocation::LatLon {
+                latitude,
+                longitude,
+            } => (latitude, longitude),
+            _ => return Err(format_err!("One Call requires a latitude and longitude")),
+        };
+        let mut options = vec![
+            ("lat", latitude.to_string()),
+            ("lon", longitude.to_string()),
+        ];
+        if !exclude.is_empty() {
+            let exclude: Vec<_> = exclude.iter().map(ToString::to_string).collect();
+            options.push(("exclude", exclude.join(",")));
+        }
+        Ok(options)
+    }
+
+    fn get_forecast_options(
+        &self,
+        location: &WeatherLocation,
+    ) -> Result<Vec<(&'static str, String)>, Error> {
+        let mut options = self.get_options(location)?;
+        if let Some(days) = self.forecast_days {
+            options.push(("cnt", forecast_cnt(days).to_string()));
+        }
+        Ok(options)
+    }
+
+    fn get_daily_forecast_options(
+        &self,
+        location: &WeatherLocation,
+        days: usize,
+    ) -> Result<Vec<(&'static str, String)>, Error> {
+        let mut options = self.get_options(location)?;
+        options.push(("cnt", days.clamp(1, MAX_DAILY_FORECAST_DAYS).to_string()));
+        Ok(options)
     }
 
     fn get_options(
@@ -150,15 +704,11 @@ impl WeatherApi {
                 country_code,
             } => {
                 let country_code = country_code.clone().unwrap_or_else(|| "us".to_string());
-                vec![
-                    ("zip", zipcode.to_string()),
-                    ("country_code", country_code),
-                    ("APPID", self.api_key.to_string()),
-                ]
+                vec![("zip", zipcode.to_string()), ("country_code", country_code)]
             }
             WeatherLocation::CityName(city_name) => {
                 let city_name = city_name.clone();
-                vec![("q", city_name), ("APPID", self.api_key.to_string())]
+                vec![("q", city_name)]
             }
             WeatherLocation::LatLon {
                 latitude,
@@ -166,22 +716,211 @@ impl WeatherApi {
             } => vec![
                 ("lat", latitude.to_string()),
                 ("lon", longitude.to_string()),
-                ("APPID", self.api_key.to_string()),
             ],
         };
         Ok(options)
     }
 
-    async fn run_api<T: serde::de::DeserializeOwned>(
+    /// Url for `command`, the path segments and query parameters are
+    /// percent-encoded by `Url` rather than pasted into the string.
+    fn get_url(&self, command: &str, options: &[(&'static str, String)]) -> Result<Url, Error> {
+        let mut url = if self.api_endpoint.contains("://") {
+            Url::parse(&self.api_endpoint)?
+        } else {
+            Url::parse(&format!("https://{}", self.api_endpoint))?
+        };
+        url.path_segments_mut()
+            .map_err(|_| format_err!("Invalid api endpoint {}", self.api_endpoint))?
+            .pop_if_empty()
+            .extend(self.api_path.split('/').filter(|s| !s.is_empty()))
+            .extend(command.split('/'));
+        // Responses are always parsed as standard units (kelvin), a `units`
+        // given with the endpoint would silently change what they contain
+        let endpoint_pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| key != UNITS_PARAMETER)
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        url.set_query(None);
+        url.query_pairs_mut()
+            .extend_pairs(endpoint_pairs)
+            .extend_pairs(options);
+        Ok(url)
+    }
+
+    /// Delay before retrying after `error`, a rate limited response waits for
+    /// `Retry-After` when given, otherwise fall back to the `backoff`.
+    /// Returns `None` if the request shouldn't be retried.
+    fn retry_delay(
+        error: &Error,
+        backoff: &mut impl Iterator<Item = time::Duration>,
+    ) -> Option<time::Duration> {
+        if let Some(rate_limited) = error.downcast_ref::<RateLimited>() {
+            match rate_limited.retry_after {
+                Some(retry_after) if retry_after <= MAX_RETRY_AFTER => Some(retry_after),
+                Some(_) => None,
+                None => backoff.next(),
+            }
+        } else if error.is::<ServerError>() {
+            backoff.next()
+        } else {
+            None
+        }
+    }
+
+    async fn get_text(&self, url: &Url) -> Result<String, Error> {
+        let res = self.client.get(url).await?;
+        if res.status == 429 {
+            return Err(RateLimited::from_response(&res, Utc::now()).into());
+        } else if res.status == 404 {
+            return Err(NotFound::from_response(&res).into());
+        } else if (500..600).contains(&res.status) {
+            return Err(ServerError(res.status).into());
+        }
+        Ok(res.body)
+    }
+
+    /// Request `url`, retrying rate limited and failed requests.  If
+    /// `rotate` is set a rate limited request is returned straight away so
+    /// that the next api key can be tried instead.
+    async fn get_text_with_retries(&self, url: &Url, rotate: bool) -> Result<String, Error> {
+        let mut backoff = Exponential::from_millis_with_factor(RETRY_BASE_MILLIS, 2.0).map(jitter);
+        let mut attempts = 0;
+        loop {
+            match self.get_text(url).await {
+                Ok(text) => return Ok(text),
+                Err(e) if rotate && e.is::<RateLimited>() => return Err(e),
+                Err(e) => {
+                    attempts += 1;
+                    match Self::retry_delay(&e, &mut backoff) {
+                        Some(delay) if attempts <= self.retries => self.client.delay(delay).await,
+                        _ => return Err(e),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_api<T: serde::de::DeserializeOwned + serde::Serialize>(
         &self,
         command: &str,
         options: &[(&'static str, String)],
     ) -> Result<T, Error> {
-        let base_url = format!("https://{}/{}{}", self.api_endpoint, self.api_path, command);
-        let url = Url::parse_with_params(&base_url, options)?;
-        let res = self.client.get(url).send().await?;
-        let text = res.text().await?;
-        serde_json::from_str(&text).map_err(|e| {
+        self.fetch_and_parse(command, options, |text| {
+            self.check_round_trip::<T>(command, text);
+            if self.strict {
+                Self::check_strict::<T>(command, text)?;
+            }
+            Self::parse_text(text)
+        })
+        .await
+    }
+
+    /// Fetch `command` (or read it from the cache when offline) and parse
+    /// the response body with `parse`, only responses that parse are cached
+    async fn fetch_and_parse<T>(
+        &self,
+        command: &str,
+        options: &[(&'static str, String)],
+        parse: impl Fn(&str) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.offline {
+            let text = self
+                .disk_cache
+                .as_ref()
+                .and_then(|cache| cache.get(command, options))
+                .ok_or_else(|| format_err!("no cached data for this location"))?;
+            return parse(&text);
+        }
+        let text = match &self.cache {
+            Some(cache) => {
+                cache
+                    .get_or_fetch(command, options, self.fetch_text(command, options))
+                    .await?
+            }
+            None => self.fetch_text(command, options).await?,
+        };
+        let data = parse(&text).inspect_err(|_| {
+            if let Some(cache) = &self.cache {
+                cache.remove(command, options);
+            }
+        })?;
+        if let Some(cache) = &self.disk_cache {
+            if let Err(e) = cache.put(command, options, &text) {
+                debug!("failed to cache {} response: {}", command, e);
+            }
+        }
+        Ok(data)
+    }
+
+    /// Request `command`, trying each api key in turn while they're rate
+    /// limited
+    async fn fetch_text(
+        &self,
+        command: &str,
+        options: &[(&'static str, String)],
+    ) -> Result<String, Error> {
+        let mut keys = self.api_keys.iter().peekable();
+        loop {
+            let api_key = keys.next().ok_or_else(|| format_err!("No api key"))?;
+            let rotate = keys.peek().is_some();
+            let mut options = options.to_vec();
+            options.push(("APPID", api_key.to_string()));
+            let url = self.get_url(command, &options)?;
+            debug!("{} using api key {}", command, mask_api_key(api_key));
+            match self.get_text_with_retries(&url, rotate).await {
+                Ok(text) => return Ok(text),
+                Err(e) if rotate && e.is::<RateLimited>() => {
+                    warn!("api key {} {}", mask_api_key(api_key), e);
+                }
+                Err(e) if self.api_keys.len() > 1 && e.is::<RateLimited>() => {
+                    return Err(format_err!(
+                        "All {} api keys are rate limited, last error: {}",
+                        self.api_keys.len(),
+                        e
+                    ))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn check_strict<T: serde::de::DeserializeOwned + serde::Serialize>(
+        command: &str,
+        text: &str,
+    ) -> Result<(), Error> {
+        match unknown_keys::<T>(text) {
+            Ok(keys) if !keys.is_empty() => Err(format_err!(
+                "strict mode: {} response has unknown keys: {}",
+                command,
+                keys.join(", ")
+            )),
+            // Responses which don't parse are reported by `parse_text`
+            _ => Ok(()),
+        }
+    }
+
+    fn check_round_trip<T: serde::de::DeserializeOwned + serde::Serialize>(
+        &self,
+        command: &str,
+        text: &str,
+    ) {
+        if !self.round_trip_check {
+            return;
+        }
+        match unknown_keys::<T>(text) {
+            Ok(keys) if keys.is_empty() => {}
+            Ok(keys) => warn!(
+                "round trip check: {} response has unknown keys: {}",
+                command,
+                keys.join(", ")
+            ),
+            Err(e) => warn!("round trip check: {} response failed: {}", command, e),
+        }
+    }
+
+    fn parse_text<T: serde::de::DeserializeOwned>(text: &str) -> Result<T, Error> {
+        serde_json::from_str(text).map_err(|e| {
             println!("{}", text);
             e.into()
         })
@@ -191,10 +930,42 @@ impl WeatherApi {
 #[cfg(test)]
 mod tests {
     use anyhow::Error;
-    use futures::future::join;
+    use chrono::{Duration, TimeZone, Utc};
+    use futures::{
+        future::{join, join_all},
+        StreamExt,
+    };
+    use std::{
+        convert::TryFrom,
+        env::temp_dir,
+        fs,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time,
+    };
+    use url::Url;
 
-    use crate::weather_api::{WeatherApi, WeatherLocation};
+    #[cfg(feature = "native-client")]
+    use crate::mock_server::{http_response, serve};
+    use crate::{
+        disk_cache::DiskCache,
+        display_opts::DisplayOpts,
+        http_client::{HttpClient, HttpFuture, HttpResponse},
+        latitude::Latitude,
+        logger::captured_logs,
+        longitude::Longitude,
+        memory_cache::MemoryCache,
+        units::Units,
+        weather_api::{
+            forecast_cnt, mask_api_key, NotFound, RateLimited, WeatherApi, WeatherLocation,
+        },
+        weather_data::WeatherData,
+        weather_onecall::OneCallSection,
+    };
 
+    #[cfg(feature = "native-client")]
     #[tokio::test]
     async fn test_process_opts() -> Result<(), Error> {
         let api_key = "95337ed3a8a87acae620d673fae85b11";
@@ -207,13 +978,628 @@ mod tests {
         let (data, forecast) =
             join(api.get_weather_data(&loc), api.get_weather_forecast(&loc)).await;
         let (data, forecast) = (data?, forecast?);
-        assert!(data.name == "Astoria", format!("{:?}", data));
+        assert!(data.name == "Astoria", "{:?}", data);
         let timezone: i32 = forecast.city.timezone.into();
         println!("{}", timezone);
+        assert!(timezone == -18000 || timezone == -14400, "{:?}", forecast);
+        Ok(())
+    }
+
+    #[test]
+    fn test_onecall_exclude() -> Result<(), Error> {
+        let api = fake_api("api_key", "api.openweathermap.org", "data/2.5/");
+        let loc =
+            WeatherLocation::from_lat_lon(Latitude::try_from(40.76)?, Longitude::try_from(-73.93)?);
+        let exclude = [OneCallSection::Minutely, OneCallSection::Alerts];
+        let options = api.get_onecall_options(&loc, &exclude)?;
+        let url = api.get_url("onecall", &options)?;
+        assert_eq!(url.path(), "/data/2.5/onecall");
+        let (_, value) = url.query_pairs().find(|(k, _)| k == "exclude").unwrap();
+        assert_eq!(value, "minutely,alerts");
+
+        let options = api.get_onecall_options(&loc, &[])?;
+        assert!(options.iter().all(|(k, _)| *k != "exclude"));
+
+        let loc = WeatherLocation::from_zipcode(11106);
+        assert!(api.get_onecall_options(&loc, &exclude).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_forecast_cnt() -> Result<(), Error> {
+        for (days, cnt) in &[
+            (0, 1),
+            (1, 8),
+            (2, 16),
+            (3, 24),
+            (5, 40),
+            (6, 40),
+            (100, 40),
+        ] {
+            assert_eq!(forecast_cnt(*days), *cnt, "{} days", days);
+        }
+        assert_eq!(forecast_cnt(usize::MAX), 40);
+
+        let api = fake_api("api_key", "api.openweathermap.org", "data/2.5/");
+        let loc = WeatherLocation::from_zipcode(11106);
+        let url = api.get_weather_forecast_url(&loc)?;
+        assert!(!url.as_str().contains("cnt="), "{}", url);
+        let url = api.with_forecast_days(2).get_weather_forecast_url(&loc)?;
+        assert!(url.as_str().contains("&cnt=16&"), "{}", url);
+        Ok(())
+    }
+
+    #[test]
+    fn test_url_encoding() -> Result<(), Error> {
+        let api = fake_api("api_key", "api.openweathermap.org", "data/2.5/");
+        let loc = WeatherLocation::from_city_name("São Paulo");
+        let url = api.get_url("weather", &api.get_options(&loc)?)?;
+        assert_eq!(
+            url.as_str(),
+            "https://api.openweathermap.org/data/2.5/weather?q=S%C3%A3o+Paulo"
+        );
+
+        let loc = WeatherLocation::from_city_name("Washington, D.C.&units=metric");
+        let url = api.get_url("weather", &api.get_options(&loc)?)?;
+        assert_eq!(url.path(), "/data/2.5/weather");
+        assert_eq!(url.query(), Some("q=Washington%2C+D.C.%26units%3Dmetric"));
+        let pairs: Vec<_> = url.query_pairs().collect();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].1, "Washington, D.C.&units=metric");
+
+        // Endpoints with a scheme are used as is
+        let api = fake_api("api_key", "http://127.0.0.1:8080", "data/2.5/");
+        let url = api.get_url("forecast", &[("zip", "11106".into())])?;
+        assert_eq!(
+            url.as_str(),
+            "http://127.0.0.1:8080/data/2.5/forecast?zip=11106"
+        );
+        let url = api.get_url("onecall/timemachine", &[])?;
+        assert_eq!(url.path(), "/data/2.5/onecall/timemachine");
+        let dt = Utc::now() - Duration::days(1);
+        let loc =
+            WeatherLocation::from_lat_lon(Latitude::try_from(40.76)?, Longitude::try_from(-73.93)?);
+        let url = api.get_weather_history_url(&loc, dt)?;
+        assert_eq!(
+            url.as_str(),
+            format!(
+                "http://127.0.0.1:8080/data/2.5/onecall/timemachine?lat=40.76&lon=-73.93&dt={}&APPID=*******",
+                dt.timestamp()
+            )
+        );
+
+        let url = api.get_weather_box_url(&"12,32,15,37,10".parse()?)?;
+        assert_eq!(url.path(), "/data/2.5/box/city");
+        assert!(url.as_str().contains("bbox=12%2C32%2C15%2C37%2C10&APPID="));
+
+        let url = api.get_weather_find_url("London,GB")?;
+        assert_eq!(url.path(), "/data/2.5/find");
+        assert!(url.as_str().contains("?q=London%2CGB&APPID="), "{}", url);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_history_date() -> Result<(), Error> {
+        let now = Utc.with_ymd_and_hms(2020, 1, 19, 18, 0, 0).unwrap();
+        assert!(WeatherApi::validate_history_date(now - Duration::days(2), now).is_ok());
+        assert!(WeatherApi::validate_history_date(now, now).is_ok());
+        assert!(WeatherApi::validate_history_date(now + Duration::hours(1), now).is_err());
+        assert!(WeatherApi::validate_history_date(now - Duration::days(6), now).is_err());
+        Ok(())
+    }
+
+    /// Replies with `responses` in turn, recording every url requested and
+    /// delay waited for
+    #[derive(Default)]
+    struct FakeClient {
+        responses: Mutex<Vec<HttpResponse>>,
+        urls: Arc<Mutex<Vec<String>>>,
+        delays: Arc<Mutex<Vec<time::Duration>>>,
+    }
+
+    impl HttpClient for FakeClient {
+        fn get<'a>(&'a self, url: &'a Url) -> HttpFuture<'a, Result<HttpResponse, Error>> {
+            self.urls.lock().unwrap().push(url.to_string());
+            let res = self.responses.lock().unwrap().remove(0);
+            Box::pin(async move { Ok(res) })
+        }
+
+        fn delay(&self, duration: time::Duration) -> HttpFuture<'_, ()> {
+            self.delays.lock().unwrap().push(duration);
+            Box::pin(async {})
+        }
+    }
+
+    /// A `WeatherApi` that can build urls without the native client, it
+    /// mustn't make any request
+    fn fake_api(api_key: &str, api_endpoint: &str, api_path: &str) -> WeatherApi {
+        WeatherApi::from_http_client(FakeClient::default(), api_key, api_endpoint, api_path)
+    }
+
+    #[tokio::test]
+    async fn test_http_client() -> Result<(), Error> {
+        let client = FakeClient {
+            responses: Mutex::new(vec![
+                HttpResponse::new(503, "Service Unavailable"),
+                HttpResponse::new(200, include_str!("../tests/weather.json")),
+            ]),
+            ..FakeClient::default()
+        };
+        let (urls, delays) = (client.urls.clone(), client.delays.clone());
+        let api = WeatherApi::from_http_client(
+            client,
+            "0123456789abcdef",
+            "api.openweathermap.org",
+            "data/2.5/",
+        );
+        let data = api
+            .get_weather_data(&WeatherLocation::from_zipcode(11106))
+            .await?;
+        assert_eq!(data.name, "Astoria");
+
+        let urls = urls.lock().unwrap();
+        assert_eq!(urls.len(), 2);
+        assert!(urls[0].starts_with("https://api.openweathermap.org/data/2.5/weather?zip=11106"));
+        assert_eq!(urls[0], urls[1]);
+        assert_eq!(delays.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_location_not_found() -> Result<(), Error> {
+        let not_found = r#"{"cod":"404","message":"city not found"}"#;
+        let client = FakeClient {
+            responses: Mutex::new(vec![
+                HttpResponse::new(404, not_found),
+                HttpResponse::new(404, not_found),
+                HttpResponse::new(404, not_found),
+            ]),
+            ..FakeClient::default()
+        };
+        let urls = client.urls.clone();
+        let api =
+            WeatherApi::from_http_client(client, "api_key", "api.openweathermap.org", "data/2.5/");
+        let error = api
+            .get_weather_data(&WeatherLocation::from_city_name("Nowhere"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No location found for 'Nowhere'; try adding a country code, e.g. 'Nowhere,US' or \
+             'Nowhere,GB'"
+        );
+        let error = api
+            .get_weather_forecast(&WeatherLocation::from_zipcode_country_code(99999, "GB"))
+            .await
+            .unwrap_err();
         assert!(
-            timezone == -18000 || timezone == -14400,
-            format!("{:?}", forecast)
+            error
+                .to_string()
+                .starts_with("No location found for '99999,GB'; check the spelling"),
+            "{}",
+            error
+        );
+
+        // Coordinates always exist, the api's message is kept
+        let loc =
+            WeatherLocation::from_lat_lon(Latitude::try_from(40.76)?, Longitude::try_from(-73.93)?);
+        let error = api.get_weather_data(&loc).await.unwrap_err();
+        assert!(error.is::<NotFound>());
+        assert_eq!(error.to_string(), "city not found");
+        // Not found isn't retried
+        assert_eq!(urls.lock().unwrap().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_watch() -> Result<(), Error> {
+        let client = FakeClient {
+            responses: Mutex::new(vec![
+                HttpResponse::new(200, include_str!("../tests/weather.json")),
+                HttpResponse::new(200, include_str!("../tests/weather.json")),
+            ]),
+            ..FakeClient::default()
+        };
+        let (urls, delays) = (client.urls.clone(), client.delays.clone());
+        let api = WeatherApi::from_http_client(
+            client,
+            "0123456789abcdef",
+            "api.openweathermap.org",
+            "data/2.5/",
+        )
+        .with_cache(MemoryCache::new());
+        let interval = time::Duration::from_millis(10);
+        let polls: Vec<_> = api
+            .watch(&WeatherLocation::from_zipcode(11106), interval)
+            .take(3)
+            .collect()
+            .await;
+        assert_eq!(polls.len(), 3);
+        for data in polls {
+            assert_eq!(data?.name, "Astoria");
+        }
+        assert_eq!(*delays.lock().unwrap(), vec![interval; 2]);
+        // served from the cache after the first poll
+        assert_eq!(urls.lock().unwrap().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limited_headers() {
+        let now = Utc.with_ymd_and_hms(2020, 1, 19, 18, 0, 0).unwrap();
+        let res = HttpResponse::new(429, "")
+            .with_header("Retry-After", "60")
+            .with_header("X-RateLimit-Limit", "60");
+        let rate_limited = RateLimited::from_response(&res, now);
+        assert_eq!(
+            rate_limited.retry_after,
+            Some(time::Duration::from_secs(60))
+        );
+        assert_eq!(
+            rate_limited.to_string(),
+            "rate limited (limit 60); retry after 60s"
         );
+
+        let res =
+            HttpResponse::new(429, "").with_header("Retry-After", "Sun, 19 Jan 2020 18:02:00 GMT");
+        let rate_limited = RateLimited::from_response(&res, now);
+        assert_eq!(rate_limited.to_string(), "rate limited; retry after 120s");
+
+        let rate_limited = RateLimited::from_response(&HttpResponse::new(429, ""), now);
+        assert_eq!(rate_limited.to_string(), "rate limited");
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_user_agent() -> Result<(), Error> {
+        let body = include_str!("../tests/weather.json");
+        let (endpoint, handle) = serve(vec![http_response("200 OK", &[], body)]).await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/");
+        let loc = WeatherLocation::from_zipcode(11106);
+        api.get_weather_data(&loc).await?;
+
+        let requests = handle.await?;
+        let user_agent = format!(
+            "user-agent: weather_util_rust/{}",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert!(
+            requests[0]
+                .lines()
+                .any(|line| line.eq_ignore_ascii_case(&user_agent)),
+            "{}",
+            requests[0]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_retry_after() -> Result<(), Error> {
+        let body = include_str!("../tests/weather.json");
+        let (endpoint, handle) = serve(vec![
+            http_response("429 Too Many Requests", &[("Retry-After", "1")], "{}"),
+            http_response("200 OK", &[], body),
+        ])
+        .await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/");
+        let loc = WeatherLocation::from_zipcode(11106);
+
+        let start = time::Instant::now();
+        let data = api.get_weather_data(&loc).await?;
+        assert!(start.elapsed() >= time::Duration::from_secs(1));
+        assert_eq!(data.name, "Astoria");
+        let requests = handle.await?;
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /data/2.5/weather?zip=11106"));
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_rate_limited_error() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![http_response(
+            "429 Too Many Requests",
+            &[("Retry-After", "3600")],
+            "{}",
+        )])
+        .await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/");
+        let loc = WeatherLocation::from_zipcode(11106);
+
+        let err = api.get_weather_data(&loc).await.unwrap_err();
+        assert_eq!(err.to_string(), "rate limited; retry after 3600s");
+        assert_eq!(handle.await?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mask_api_key() {
+        assert_eq!(
+            mask_api_key("95337ed3a8a87acae620d673fae85b11"),
+            "9533...5b11"
+        );
+        assert_eq!(mask_api_key("short"), "*****");
+        let api = fake_api("95337ed3a8a87acae620d673fae85b11", "localhost", "data/2.5/");
+        assert_eq!(
+            format!("{:?}", api),
+            "WeatherApi(key=9533...5b11,endpoint=localhost)"
+        );
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_rotate_api_keys() -> Result<(), Error> {
+        let body = include_str!("../tests/weather.json");
+        let (endpoint, handle) = serve(vec![
+            http_response("429 Too Many Requests", &[], "{}"),
+            http_response("200 OK", &[], body),
+        ])
+        .await?;
+        let api = WeatherApi::new("rotated_key", &endpoint, "data/2.5/")
+            .with_keys(&["rotated_key", "second_key"]);
+        let loc = WeatherLocation::from_zipcode(11106);
+
+        captured_logs();
+        let data = api.get_weather_data(&loc).await?;
+        assert_eq!(data.name, "Astoria");
+        let requests = handle.await?;
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].contains("APPID=rotated_key"), "{}", requests[0]);
+        assert!(requests[1].contains("APPID=second_key"), "{}", requests[1]);
+        // the rotation is logged with the key masked
+        let logs = captured_logs();
+        assert!(
+            logs.contains(&"api key rota..._key rate limited".to_string()),
+            "{:?}",
+            logs
+        );
+        assert!(logs.contains(&"weather using api key seco..._key".to_string()));
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_api_keys_exhausted() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![
+            http_response("429 Too Many Requests", &[], "{}"),
+            http_response("429 Too Many Requests", &[("Retry-After", "3600")], "{}"),
+        ])
+        .await?;
+        let api = WeatherApi::new("first_key", &endpoint, "data/2.5/")
+            .with_keys(&["first_key", "second_key"]);
+        let loc = WeatherLocation::from_zipcode(11106);
+
+        let err = api.get_weather_data(&loc).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "All 2 api keys are rate limited, last error: rate limited; retry after 3600s"
+        );
+        assert_eq!(handle.await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_weather_find() -> Result<(), Error> {
+        let client = FakeClient {
+            responses: Mutex::new(vec![HttpResponse::new(
+                200,
+                include_str!("../tests/find.json"),
+            )]),
+            ..FakeClient::default()
+        };
+        let urls = client.urls.clone();
+        let api = WeatherApi::from_http_client(
+            client,
+            "0123456789abcdef",
+            "api.openweathermap.org",
+            "data/2.5/",
+        );
+        let data = api.get_weather_find("London").await?;
+        assert_eq!(data.list.len(), 3);
+        assert!(urls.lock().unwrap()[0].contains("/find?q=London&"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_strict() -> Result<(), Error> {
+        // Without the fields the crate drops, then with an unknown one
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let clean = serde_json::to_value(&data)?;
+        let mut extra = clean.clone();
+        extra["main"]["air_quality"] = 42.into();
+        let client = FakeClient {
+            responses: Mutex::new(vec![
+                HttpResponse::new(200, &clean.to_string()),
+                HttpResponse::new(200, &extra.to_string()),
+                HttpResponse::new(200, &extra.to_string()),
+            ]),
+            ..FakeClient::default()
+        };
+        let api = WeatherApi::from_http_client(
+            client,
+            "0123456789abcdef",
+            "api.openweathermap.org",
+            "data/2.5/",
+        );
+        let loc = WeatherLocation::from_zipcode(11106);
+
+        let strict = api.clone().with_strict(true);
+        assert_eq!(strict.get_weather_data(&loc).await?.name, "Astoria");
+        let e = strict.get_weather_data(&loc).await.unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "strict mode: weather response has unknown keys: main.air_quality"
+        );
+        assert_eq!(api.get_weather_data(&loc).await?.name, "Astoria");
+        Ok(())
+    }
+
+    /// Answers every request after a while, named after the requested
+    /// zipcode, keeping track of the most requests in flight at once
+    #[derive(Default)]
+    struct SlowClient {
+        in_flight: AtomicUsize,
+        max_in_flight: Arc<AtomicUsize>,
+    }
+
+    impl HttpClient for SlowClient {
+        fn get<'a>(&'a self, url: &'a Url) -> HttpFuture<'a, Result<HttpResponse, Error>> {
+            Box::pin(async move {
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                tokio::time::delay_for(time::Duration::from_millis(20)).await;
+                self.in_flight.fetch_sub(1, Ordering::SeqCst);
+                let zipcode = url
+                    .query_pairs()
+                    .find(|(key, _)| key == "zip")
+                    .map(|(_, value)| value.into_owned())
+                    .unwrap_or_default();
+                let body = include_str!("../tests/weather.json").replace(
+                    "\"name\": \"Astoria\"",
+                    &format!("\"name\": \"{}\"", zipcode),
+                );
+                Ok(HttpResponse::new(200, &body))
+            })
+        }
+
+        fn delay(&self, _: time::Duration) -> HttpFuture<'_, ()> {
+            Box::pin(async {})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrency() -> Result<(), Error> {
+        let client = SlowClient::default();
+        let max_in_flight = client.max_in_flight.clone();
+        let api = WeatherApi::from_http_client(
+            client,
+            "0123456789abcdef",
+            "api.openweathermap.org",
+            "data/2.5/",
+        )
+        .with_concurrency(3);
+        let locations: Vec<_> = (11101..11111).map(WeatherLocation::from_zipcode).collect();
+        let names = api
+            .get_weather_data_many(&locations)
+            .await
+            .into_iter()
+            .map(|data| data.map(|data| data.name))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let expected: Vec<_> = (11101..11111).map(|zip| zip.to_string()).collect();
+        assert_eq!(names, expected);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+
+        max_in_flight.store(0, Ordering::SeqCst);
+        let api = api.with_concurrency(0);
+        assert_eq!(api.get_weather_data_many(&locations[..4]).await.len(), 4);
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "xml")]
+    #[tokio::test]
+    async fn test_xml() -> Result<(), Error> {
+        let client = FakeClient {
+            responses: Mutex::new(vec![HttpResponse::new(
+                200,
+                include_str!("../tests/weather.xml"),
+            )]),
+            ..FakeClient::default()
+        };
+        let urls = client.urls.clone();
+        let api = WeatherApi::from_http_client(
+            client,
+            "0123456789abcdef",
+            "api.openweathermap.org",
+            "data/2.5/",
+        )
+        .with_xml(true);
+        let loc = WeatherLocation::from_zipcode(11106);
+        let data = api.get_weather_data(&loc).await?;
+        assert_eq!(data.name, "Astoria");
+        assert!(urls.lock().unwrap()[0].contains("&mode=xml&"));
+        let url = api.get_weather_data_url(&loc)?;
+        assert!(url.as_str().contains("&mode=xml&"), "{}", url);
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_offline() -> Result<(), Error> {
+        let directory = temp_dir().join(format!("weather_util_cache_{}", std::process::id()));
+        let cache = DiskCache::new(&directory);
+
+        let body = include_str!("../tests/weather.json");
+        let (endpoint, handle) = serve(vec![http_response("200 OK", &[], body)]).await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/").with_disk_cache(cache.clone());
+        let loc = WeatherLocation::from_zipcode(11106);
+        api.get_weather_data(&loc).await?;
+        assert_eq!(handle.await?.len(), 1);
+
+        // Nothing is listening here, any request would fail
+        let api = WeatherApi::new("other_key", "http://127.0.0.1:1", "data/2.5/")
+            .with_disk_cache(cache)
+            .with_offline(true);
+        let data = api.get_weather_data(&loc).await?;
+        assert_eq!(data.name, "Astoria");
+
+        let loc = WeatherLocation::from_zipcode(10001);
+        let err = api.get_weather_data(&loc).await.unwrap_err();
+        assert_eq!(err.to_string(), "no cached data for this location");
+        let err = api.get_weather_forecast(&loc).await.unwrap_err();
+        assert_eq!(err.to_string(), "no cached data for this location");
+
+        fs::remove_dir_all(&directory)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_metric_endpoint() -> Result<(), Error> {
+        let body = include_str!("../tests/weather.json");
+        let (endpoint, handle) = serve(vec![http_response("200 OK", &[], body)]).await?;
+        let endpoint = format!("{}/?units=metric&lang=en", endpoint);
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/").with_retries(0);
+        let loc = WeatherLocation::from_zipcode(11106);
+        let data = api.get_weather_data(&loc).await?;
+
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default().with_units(Units::Metric))?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tTemperature: 5.03°C (41.05°F)"), "{}", buf);
+
+        let requests = handle.await?;
+        assert!(
+            requests[0].starts_with("GET /data/2.5/weather?lang=en&zip=11106&country_code=us&"),
+            "{}",
+            requests[0]
+        );
+        assert!(!requests[0].contains("units"), "{}", requests[0]);
+        Ok(())
+    }
+
+    #[cfg(feature = "native-client")]
+    #[tokio::test]
+    async fn test_memory_cache() -> Result<(), Error> {
+        let body = include_str!("../tests/weather.json");
+        let (endpoint, handle) = serve(vec![http_response("200 OK", &[], body)]).await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/")
+            .with_retries(0)
+            .with_cache(MemoryCache::new());
+        let loc = WeatherLocation::from_zipcode(11106);
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let api = api.clone();
+                let loc = loc.clone();
+                tokio::spawn(async move { api.get_weather_data(&loc).await })
+            })
+            .collect();
+        for data in join_all(tasks).await {
+            assert_eq!(data??.name, "Astoria");
+        }
+        assert_eq!(handle.await?.len(), 1);
         Ok(())
     }
 }
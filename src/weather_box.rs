@@ -0,0 +1,255 @@
+use anyhow::{format_err, Error};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    io::Write,
+    str::FromStr,
+};
+
+use crate::{
+    display_opts::DisplayOpts,
+    humidity::Humidity,
+    latitude::Latitude,
+    longitude::Longitude,
+    pressure::Pressure,
+    temperature::Temperature,
+    timestamp,
+    weather_data::{Rain, Snow, WeatherCond, Wind},
+};
+
+/// Rectangle of stations requested from the `box/city` endpoint, written
+/// as "lon_left,lat_bottom,lon_right,lat_top,zoom"
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct BoundingBox {
+    pub lon_left: Longitude,
+    pub lat_bottom: Latitude,
+    pub lon_right: Longitude,
+    pub lat_top: Latitude,
+    /// Map zoom level, higher levels return more (smaller) stations
+    pub zoom: u8,
+}
+
+impl fmt::Display for BoundingBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{},{},{},{},{}",
+            self.lon_left, self.lat_bottom, self.lon_right, self.lat_top, self.zoom
+        )
+    }
+}
+
+impl FromStr for BoundingBox {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = s.split(',').map(str::trim).collect();
+        if fields.len() != 5 {
+            return Err(format_err!(
+                "{} is not a valid bounding box, expected lon_left,lat_bottom,lon_right,lat_top,zoom",
+                s
+            ));
+        }
+        let bbox = Self {
+            lon_left: fields[0].parse::<f64>()?.try_into()?,
+            lat_bottom: fields[1].parse::<f64>()?.try_into()?,
+            lon_right: fields[2].parse::<f64>()?.try_into()?,
+            lat_top: fields[3].parse::<f64>()?.try_into()?,
+            zoom: fields[4].parse()?,
+        };
+        if f64::from(bbox.lon_left) >= f64::from(bbox.lon_right)
+            || f64::from(bbox.lat_bottom) >= f64::from(bbox.lat_top)
+        {
+            return Err(format_err!(
+                "{} is not a valid bounding box, the left and bottom edges must come first",
+                s
+            ));
+        }
+        Ok(bbox)
+    }
+}
+
+impl TryFrom<String> for BoundingBox {
+    type Error = Error;
+    fn try_from(item: String) -> Result<Self, Self::Error> {
+        item.parse()
+    }
+}
+
+impl From<BoundingBox> for String {
+    fn from(item: BoundingBox) -> Self {
+        item.to_string()
+    }
+}
+
+/// The `box/city` endpoint capitalizes the coordinates
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StationCoord {
+    #[serde(rename = "Lon")]
+    pub lon: Longitude,
+    #[serde(rename = "Lat")]
+    pub lat: Latitude,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StationMain {
+    pub temp: Temperature,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feels_like: Option<Temperature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_min: Option<Temperature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_max: Option<Temperature>,
+    pub pressure: Pressure,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sea_level: Option<Pressure>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grnd_level: Option<Pressure>,
+    pub humidity: Humidity,
+}
+
+/// Current conditions at a single station of the box
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct StationEntry {
+    pub id: u64,
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    pub name: String,
+    pub coord: StationCoord,
+    pub main: StationMain,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind: Option<Wind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rain: Option<Rain>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow: Option<Snow>,
+    #[serde(default)]
+    pub weather: Vec<WeatherCond>,
+}
+
+/// Representation of the "weather in a rectangle" (`box/city`) response
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeatherBox {
+    #[serde(default)]
+    pub list: Vec<StationEntry>,
+}
+
+impl WeatherBox {
+    /// Stations sorted by name
+    pub fn get_stations(&self) -> Vec<&StationEntry> {
+        let mut stations: Vec<_> = self.list.iter().collect();
+        stations.sort_by(|a, b| a.name.cmp(&b.name));
+        stations
+    }
+
+    /// Write out a line with the temperature, humidity and conditions of each
+    /// station, sorted by name
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_box::WeatherBox;
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/box_city.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data: WeatherBox = serde_json::from_str(&buf)?;
+    ///
+    /// let mut buf = Vec::new();
+    /// data.get_conditions(&mut buf, &DisplayOpts::default())?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert!(buf.starts_with("\nStations:\n\tGharyan "), "{}", buf);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_conditions<T: Write>(&self, buf: &mut T, opts: &DisplayOpts) -> Result<(), Error> {
+        writeln!(buf, "\nStations:")?;
+        if self.list.is_empty() {
+            writeln!(buf, "\tNo stations")?;
+        }
+        self.get_stations().into_iter().try_for_each(|station| {
+            let temp = opts.temperatures(station.main.temp, 1).0;
+            writeln!(
+                buf,
+                "\t{:20} {}{} Humidity {:3}% {}",
+                station.name,
+                opts.condition_icon(station.weather.first().map_or(0, |cond| cond.id)),
+                opts.paint_temperature(station.main.temp, &format!("{:>8}", temp)),
+                i64::from(station.main.humidity),
+                station
+                    .weather
+                    .first()
+                    .map_or("", |cond| cond.description.as_str()),
+            )
+            .map_err(Error::from)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::convert::TryFrom;
+
+    use crate::{
+        display_opts::DisplayOpts,
+        temperature::Temperature,
+        weather_box::{BoundingBox, WeatherBox},
+    };
+
+    #[test]
+    fn test_bounding_box() -> Result<(), Error> {
+        let bbox: BoundingBox = "12,32,15,37,10".parse()?;
+        assert_eq!(bbox.zoom, 10);
+        assert_eq!(f64::from(bbox.lat_top), 37.0);
+        assert_eq!(bbox.to_string(), "12,32,15,37,10");
+        let bbox: BoundingBox = " -74.1, 40.6, -73.7, 40.9, 12 ".parse()?;
+        assert_eq!(f64::from(bbox.lon_left), -74.1);
+
+        assert!("12,32,15,37".parse::<BoundingBox>().is_err());
+        assert!("12,32,15,97,10".parse::<BoundingBox>().is_err());
+        assert!("15,32,12,37,10".parse::<BoundingBox>().is_err());
+        assert!("12,32,15,37,zoom".parse::<BoundingBox>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_weather_box() -> Result<(), Error> {
+        let data: WeatherBox = serde_json::from_str(include_str!("../tests/box_city.json"))?;
+        assert_eq!(data.list.len(), 3);
+        let names: Vec<_> = data
+            .get_stations()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Gharyan", "Yafran", "Zuwarah"]);
+
+        let yafran = &data.list[0];
+        assert_eq!(f64::from(yafran.coord.lat), 32.06329);
+        assert_eq!(yafran.main.temp, Temperature::try_from(282.83)?);
+        let rain = yafran
+            .rain
+            .as_ref()
+            .and_then(|rain| rain.three_hour)
+            .unwrap();
+        assert_eq!(rain.millimeters(), 0.255);
+
+        let mut buf = Vec::new();
+        data.get_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        let lines: Vec<_> = buf.lines().skip(2).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("\tGharyan "), "{}", buf);
+        assert!(lines[1].contains("light rain"), "{}", buf);
+        assert!(lines[2].ends_with("broken clouds"), "{}", buf);
+
+        let mut buf = Vec::new();
+        WeatherBox { list: Vec::new() }.get_conditions(&mut buf, &DisplayOpts::default())?;
+        assert_eq!(String::from_utf8(buf)?, "\nStations:\n\tNo stations\n");
+        Ok(())
+    }
+}
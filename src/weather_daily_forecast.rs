@@ -0,0 +1,237 @@
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, io::Write};
+
+use crate::{
+    direction::Direction,
+    display_opts::DisplayOpts,
+    forecast_summary::{DaySummary, ForecastSummary, TemperatureField},
+    humidity::Humidity,
+    precipitation::Precipitation,
+    pressure::Pressure,
+    speed::Speed,
+    temperature::Temperature,
+    timestamp,
+    timezone::TimeZone,
+    weather_data::{WeatherCond, Wind},
+    weather_forecast::{CityEntry, ForecastEntry, ForecastMain, Rain, Snow, WeatherForecast},
+    weather_onecall::{DailyFeelsLike, DailyTemperature},
+};
+
+/// Most days the daily forecast endpoint returns
+pub const MAX_DAILY_FORECAST_DAYS: usize = 16;
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DailyForecastEntry {
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub sunrise: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub sunset: DateTime<Utc>,
+    pub temp: DailyTemperature,
+    pub feels_like: DailyFeelsLike,
+    pub pressure: Pressure,
+    pub humidity: Humidity,
+    #[serde(default)]
+    pub weather: Vec<WeatherCond>,
+    pub speed: Speed,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deg: Option<Direction>,
+    /// Probability of precipitation (0.0 to 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pop: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rain: Option<Precipitation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow: Option<Precipitation>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DailyCityEntry {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country: Option<String>,
+    pub timezone: TimeZone,
+}
+
+/// Representation of the daily forecast (`forecast/daily`) response, up to
+/// `MAX_DAILY_FORECAST_DAYS` days with one entry per day
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeatherDailyForecast {
+    pub list: Vec<DailyForecastEntry>,
+    pub city: DailyCityEntry,
+}
+
+impl WeatherDailyForecast {
+    /// Summary of each day of the forecast
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// # use chrono::NaiveDate;
+    /// use weather_util_rust::weather_daily_forecast::WeatherDailyForecast;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/forecast_daily.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data: WeatherDailyForecast = serde_json::from_str(&buf)?;
+    ///
+    /// let summary = data.get_forecast_summary();
+    /// assert_eq!(summary.days.len(), 16);
+    /// assert_eq!(summary.days[0].date, NaiveDate::from_ymd_opt(2020, 1, 19).unwrap());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_forecast_summary(&self) -> ForecastSummary {
+        self.get_forecast_summary_of(TemperatureField::Temperature)
+    }
+
+    /// Same as `get_forecast_summary`, with the highs, lows and averages
+    /// taken from `field`
+    pub fn get_forecast_summary_of(&self, field: TemperatureField) -> ForecastSummary {
+        let fo: FixedOffset = self.city.timezone.into();
+        let days = self
+            .list
+            .iter()
+            .map(|entry| DaySummary {
+                date: entry.dt.with_timezone(&fo).date_naive(),
+                high: match field {
+                    TemperatureField::Temperature => entry.temp.max,
+                    TemperatureField::FeelsLike => entry.feels_like.low_high().1,
+                },
+                low: match field {
+                    TemperatureField::Temperature => entry.temp.min,
+                    TemperatureField::FeelsLike => entry.feels_like.low_high().0,
+                },
+                average: match field {
+                    TemperatureField::Temperature => Temperature::average(&[
+                        entry.temp.morn,
+                        entry.temp.day,
+                        entry.temp.eve,
+                        entry.temp.night,
+                    ]),
+                    TemperatureField::FeelsLike => entry.feels_like.average(),
+                },
+                rain: entry.rain.unwrap_or_default(),
+                snow: entry.snow.unwrap_or_default(),
+                pop: entry.pop,
+                wind_speed: Some(entry.speed),
+                observations: None,
+                condition: entry.weather.first().map(|cond| cond.id),
+            })
+            .collect();
+        ForecastSummary { days }
+    }
+
+    /// Convert the days into a `WeatherForecast` with an entry per day, e.g.
+    /// so that `summary::get_summary` can mention the precipitation expected
+    /// soon
+    pub fn get_weather_forecast(&self) -> WeatherForecast {
+        let list = self
+            .list
+            .iter()
+            .map(|entry| ForecastEntry {
+                dt: entry.dt,
+                main: ForecastMain {
+                    temp: entry.temp.day,
+                    feels_like: entry.feels_like.day,
+                    temp_min: entry.temp.min,
+                    temp_max: entry.temp.max,
+                    pressure: entry.pressure,
+                    sea_level: entry.pressure,
+                    grnd_level: entry.pressure,
+                    humidity: entry.humidity,
+                },
+                weather: entry.weather.clone(),
+                wind: Some(Wind {
+                    speed: entry.speed,
+                    deg: entry.deg,
+                }),
+                rain: entry.rain.map(|rain| Rain {
+                    three_hour: Some(rain),
+                }),
+                snow: entry.snow.map(|snow| Snow {
+                    three_hour: Some(snow),
+                }),
+                pop: entry.pop,
+                extra: HashMap::new(),
+            })
+            .collect();
+        let (sunrise, sunset) = self
+            .list
+            .first()
+            .map_or_else(Default::default, |day| (day.sunrise, day.sunset));
+        WeatherForecast {
+            list,
+            city: CityEntry {
+                timezone: self.city.timezone,
+                sunrise,
+                sunset,
+            },
+        }
+    }
+
+    /// Write out daily high and low temperatures
+    pub fn get_forecast<T: Write>(&self, buf: &mut T, opts: &DisplayOpts) -> Result<(), Error> {
+        if opts.forecast_days == Some(0) {
+            return Ok(());
+        }
+        opts.write_forecast_banner(buf)?;
+        let fo: FixedOffset = self.city.timezone.into();
+        let today = opts.now().with_timezone(&fo).date_naive();
+        self.get_forecast_summary_of(opts.forecast_field)
+            .write_days(buf, opts, today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::NaiveDate;
+
+    use crate::{
+        display_opts::DisplayOpts, precipitation::Precipitation, units::Units,
+        weather_daily_forecast::WeatherDailyForecast,
+    };
+
+    #[test]
+    fn test_daily_forecast() -> Result<(), Error> {
+        let forecast: WeatherDailyForecast =
+            serde_json::from_str(include_str!("../tests/forecast_daily.json"))?;
+        assert_eq!(forecast.list.len(), 16);
+        assert_eq!(forecast.city.name, "Astoria");
+
+        let summary = forecast.get_forecast_summary();
+        let last = summary.days.last().unwrap();
+        assert_eq!(last.date, NaiveDate::from_ymd_opt(2020, 2, 3).unwrap());
+        let entry = &forecast.list[15];
+        assert_eq!((last.high, last.low), (entry.temp.max, entry.temp.min));
+        let average = last.average.unwrap();
+        assert!(average > last.low && average < last.high);
+        assert!(summary.days[2].rain > Precipitation::default());
+        assert!(summary.days[3].snow > Precipitation::default());
+
+        let entries = forecast.get_weather_forecast();
+        assert_eq!(entries.list.len(), 16);
+        assert_eq!(entries.city.timezone, forecast.city.timezone);
+        assert_eq!(
+            entries.list[2].rain.as_ref().unwrap().three_hour,
+            forecast.list[2].rain
+        );
+
+        let mut buf = Vec::new();
+        let opts = DisplayOpts::default()
+            .with_units(Units::Metric)
+            .with_now(forecast.list[0].dt);
+        forecast.get_forecast(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.starts_with("\nForecast:\n\tToday    High: "), "{}", buf);
+        assert!(buf.contains("\n\tTomorrow High: "), "{}", buf);
+        assert!(buf.contains("\n\tMon      High: "), "{}", buf);
+        assert_eq!(buf.matches("High: ").count(), 16);
+        assert!(buf.contains("\nBest day: "), "{}", buf);
+        Ok(())
+    }
+}
@@ -1,14 +1,68 @@
-use anyhow::Error;
-use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use anyhow::{format_err, Error};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Timelike, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, io::Write};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+    ops::Range,
+};
 
 use crate::{
-    direction::Direction, distance::Distance, humidity::Humidity, latitude::Latitude,
-    longitude::Longitude, precipitation::Precipitation, pressure::Pressure, speed::Speed,
-    temperature::Temperature, timestamp, timezone::TimeZone,
+    conditions::Severity,
+    direction::Direction,
+    display_opts::DisplayOpts,
+    distance::Distance,
+    elevation::Elevation,
+    flight_category::{Ceiling, FlightCategory},
+    humidity::Humidity,
+    latitude::Latitude,
+    longitude::Longitude,
+    precipitation::Precipitation,
+    pressure::Pressure,
+    resolved_location::ResolvedLocation,
+    speed::Speed,
+    temperature::Temperature,
+    timestamp,
+    timezone::TimeZone,
+    weather_forecast::WINDY_PERCENTILE,
 };
 
+/// Local hours treated as daytime when the sunrise or sunset is unknown
+const DEFAULT_DAYTIME_HOURS: std::ops::Range<u32> = 6..18;
+
+/// Length in minutes of the golden hour after sunrise and before sunset
+pub const GOLDEN_HOUR_MINUTES: i64 = 60;
+
+/// Sun times are only counted down to for conditions at most this many
+/// hours old, older ones are for another day
+pub const SUN_COUNTDOWN_HOURS: i64 = 24;
+
+/// Span of local time, e.g. a golden hour
+pub type LocalTimeRange = Range<DateTime<FixedOffset>>;
+
+/// Smallest difference in degrees Celcius between the actual and "feels
+/// like" temperature for the latter to be printed
+pub const FEELS_LIKE_THRESHOLD: f64 = 2.0;
+
+/// Deserialize a response body, naming `what` was being parsed and adding
+/// the `message` of an api error response (e.g. an invalid key) if there is
+/// one
+pub(crate) fn parse_json<T: serde::de::DeserializeOwned>(
+    text: &str,
+    what: &str,
+) -> Result<T, Error> {
+    serde_json::from_str(text).map_err(|e| {
+        let message = serde_json::from_str::<Value>(text)
+            .ok()
+            .and_then(|value| value.get("message")?.as_str().map(String::from));
+        match message {
+            Some(message) => format_err!("Failed to parse {}: {} ({})", what, e, message),
+            None => format_err!("Failed to parse {}: {}", what, e),
+        }
+    })
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Coord {
     pub lon: Longitude,
@@ -17,11 +71,17 @@ pub struct Coord {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WeatherCond {
+    /// Condition code, see https://openweathermap.org/weather-conditions
+    #[serde(default)]
+    pub id: u32,
     pub main: String,
     pub description: String,
 }
 
+/// `temp_min` and `temp_max` are left out of some responses for a single
+/// point observation, in which case they default to `temp`
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "RawWeatherMain")]
 pub struct WeatherMain {
     pub temp: Temperature,
     pub feels_like: Temperature,
@@ -31,20 +91,67 @@ pub struct WeatherMain {
     pub humidity: Humidity,
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+#[derive(Deserialize)]
+struct RawWeatherMain {
+    temp: Temperature,
+    feels_like: Temperature,
+    temp_min: Option<Temperature>,
+    temp_max: Option<Temperature>,
+    pressure: Pressure,
+    humidity: Humidity,
+}
+
+impl From<RawWeatherMain> for WeatherMain {
+    fn from(raw: RawWeatherMain) -> Self {
+        Self {
+            temp: raw.temp,
+            feels_like: raw.feels_like,
+            temp_min: raw.temp_min.unwrap_or(raw.temp),
+            temp_max: raw.temp_max.unwrap_or(raw.temp),
+            pressure: raw.pressure,
+            humidity: raw.humidity,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
 pub struct Wind {
+    /// Occasionally omitted when there is no wind
+    #[serde(default)]
     pub speed: Speed,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub deg: Option<Direction>,
 }
 
+impl Wind {
+    /// "calm" without any wind, otherwise the direction and speed
+    pub fn description(&self, opts: &DisplayOpts) -> String {
+        if self.speed.mps() == 0.0 {
+            "calm".into()
+        } else {
+            let deg = self.deg.unwrap_or_else(|| 0.0.into());
+            match opts.compass_points {
+                Some(points) => format!(
+                    "{} degrees ({}) at {}",
+                    deg,
+                    deg.compass_point(points),
+                    opts.wind_speed(self.speed)
+                ),
+                None => format!("{} degrees at {}", deg, opts.wind_speed(self.speed)),
+            }
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Sys {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub country: Option<String>,
-    #[serde(with = "timestamp")]
+    /// Missing during polar day and night, in which case this is the Unix
+    /// epoch
+    #[serde(with = "timestamp", default)]
     pub sunrise: DateTime<Utc>,
-    #[serde(with = "timestamp")]
+    #[serde(with = "timestamp", default)]
     pub sunset: DateTime<Utc>,
 }
 
@@ -52,12 +159,16 @@ pub struct Sys {
 pub struct Rain {
     #[serde(alias = "3h", skip_serializing_if = "Option::is_none")]
     pub three_hour: Option<Precipitation>,
+    #[serde(alias = "1h", skip_serializing_if = "Option::is_none")]
+    pub one_hour: Option<Precipitation>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Snow {
     #[serde(alias = "3h", skip_serializing_if = "Option::is_none")]
     pub three_hour: Option<Precipitation>,
+    #[serde(alias = "1h", skip_serializing_if = "Option::is_none")]
+    pub one_hour: Option<Precipitation>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -78,13 +189,267 @@ pub struct WeatherData {
     pub sys: Sys,
     pub timezone: TimeZone,
     pub name: String,
+    /// Elevation of the location, only in some responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<Elevation>,
+    /// Any fields not otherwise recognized, e.g. `clouds` or `id`
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 impl WeatherData {
+    /// Parse the body of a current weather response fetched by other means
+    /// than `WeatherApi`
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_json(&buf)?;
+    /// assert_eq!(data.name, "Astoria");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_json(text: &str) -> Result<Self, Error> {
+        parse_json(text, "current conditions")
+    }
+
+    /// Whether `dt` falls between sunrise and sunset.  Only the local time
+    /// of day is compared, so a sunrise or sunset reported for a different
+    /// day still works, as does a sunset before the sunrise (which happens
+    /// when the day straddles midnight UTC).  If either is missing the local
+    /// hour is compared against `DEFAULT_DAYTIME_HOURS`.
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data: WeatherData = serde_json::from_str(&buf)?;
+    /// assert!(data.is_daytime());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_daytime(&self) -> bool {
+        let fo: FixedOffset = self.timezone.into();
+        let now = self.dt.with_timezone(&fo).time();
+        let epoch = DateTime::<Utc>::default();
+        if self.sys.sunrise == epoch || self.sys.sunset == epoch {
+            return DEFAULT_DAYTIME_HOURS.contains(&now.hour());
+        }
+        let sunrise = self.sys.sunrise.with_timezone(&fo).time();
+        let sunset = self.sys.sunset.with_timezone(&fo).time();
+        if sunrise <= sunset {
+            sunrise <= now && now < sunset
+        } else {
+            now >= sunrise || now < sunset
+        }
+    }
+
+    /// Most severe of the current conditions, `Severity::Clear` if there
+    /// are none
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::conditions::Severity;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_json(&buf)?;
+    /// assert_eq!(data.severity(), Severity::Clear);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn severity(&self) -> Severity {
+        self.weather
+            .iter()
+            .map(|cond| Severity::from_code(cond.id))
+            .max()
+            .unwrap_or(Severity::Clear)
+    }
+
+    /// Percentage of the sky covered by clouds, from the `clouds` field
+    pub fn cloud_cover(&self) -> Option<u8> {
+        self.extra
+            .get("clouds")?
+            .get("all")?
+            .as_u64()
+            .map(|all| all.min(100) as u8)
+    }
+
+    /// Flight category from the visibility and the ceiling estimated by
+    /// `Ceiling::estimate`
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::flight_category::FlightCategory;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_json(&buf)?;
+    /// assert_eq!(data.flight_category(), FlightCategory::Vfr);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn flight_category(&self) -> FlightCategory {
+        let ceiling = self
+            .cloud_cover()
+            .and_then(|cover| Ceiling::estimate(self.main.temp, self.main.humidity, cover));
+        FlightCategory::from_conditions(ceiling, self.visibility)
+    }
+
+    /// " (feels like 28°F)" when the "feels like" temperature differs from
+    /// the actual temperature by more than `FEELS_LIKE_THRESHOLD`, otherwise
+    /// empty
+    fn feels_like(&self, opts: &DisplayOpts) -> String {
+        let difference = self.main.feels_like.celcius() - self.main.temp.celcius();
+        if difference.abs() > FEELS_LIKE_THRESHOLD {
+            format!(
+                " (feels like {})",
+                opts.temperatures(self.main.feels_like, 0).0
+            )
+        } else {
+            "".to_string()
+        }
+    }
+
+    /// Local times of the golden hour after sunrise and the one before
+    /// sunset, `None` during polar day and night (when either is missing)
+    pub fn golden_hours(&self) -> Option<(LocalTimeRange, LocalTimeRange)> {
+        let epoch = DateTime::<Utc>::default();
+        if self.sys.sunrise == epoch || self.sys.sunset == epoch {
+            return None;
+        }
+        let fo: FixedOffset = self.timezone.into();
+        let sunrise = self.sys.sunrise.with_timezone(&fo);
+        let sunset = self.sys.sunset.with_timezone(&fo);
+        let golden_hour = Duration::minutes(GOLDEN_HOUR_MINUTES);
+        Some((sunrise..sunrise + golden_hour, sunset - golden_hour..sunset))
+    }
+
+    /// Time from `now` until the next sunrise or sunset, e.g.
+    /// `Sunset in 2h 15m`.  After sunset this is the next day's sunrise,
+    /// taken to be a day after this one.  `None` during polar day and night
+    /// or if the conditions are more than `SUN_COUNTDOWN_HOURS` old.
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use chrono::Duration;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_json(&buf)?;
+    /// let now = data.sys.sunset - Duration::minutes(135);
+    /// assert_eq!(data.sun_countdown(now), Some("Sunset in 2h 15m".into()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sun_countdown(&self, now: DateTime<Utc>) -> Option<String> {
+        let epoch = DateTime::<Utc>::default();
+        if self.sys.sunrise == epoch
+            || self.sys.sunset == epoch
+            || now - self.dt > Duration::hours(SUN_COUNTDOWN_HOURS)
+        {
+            return None;
+        }
+        let events = [
+            ("Sunrise", self.sys.sunrise),
+            ("Sunset", self.sys.sunset),
+            ("Sunrise", self.sys.sunrise + Duration::days(1)),
+        ];
+        match events
+            .iter()
+            .filter(|(_, time)| *time > now)
+            .min_by_key(|(_, time)| *time)
+        {
+            Some((label, time)) => Some(format!("{} in {}", label, format_duration(*time - now))),
+            None => Some(format!(
+                "Sunset was {} ago",
+                format_duration(now - self.sys.sunset)
+            )),
+        }
+    }
+
+    /// Write out only the sunrise and/or sunset, each followed by its golden
+    /// hour with `golden_hour`
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_json(&buf)?;
+    ///
+    /// let mut buf = Vec::new();
+    /// data.get_sun_times(&mut buf, false, true, true)?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert_eq!(
+    ///     buf,
+    ///     "Sunset: 2020-01-19 16:56:14 -05:00\n\tGolden hour: 15:56 to 16:56\n"
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_sun_times<T: Write>(
+        &self,
+        buf: &mut T,
+        sunrise: bool,
+        sunset: bool,
+        golden_hour: bool,
+    ) -> Result<(), Error> {
+        let epoch = DateTime::<Utc>::default();
+        let fo: FixedOffset = self.timezone.into();
+        let golden_hour_length = Duration::minutes(GOLDEN_HOUR_MINUTES);
+        let rise = self.sys.sunrise.with_timezone(&fo);
+        let set = self.sys.sunset.with_timezone(&fo);
+        let times = [
+            ("Sunrise", sunrise, rise, rise..rise + golden_hour_length),
+            ("Sunset", sunset, set, set - golden_hour_length..set),
+        ];
+        // Either time can be missing on its own
+        for (label, _, time, window) in times.iter().filter(|t| t.1) {
+            if *time == epoch {
+                writeln!(buf, "{}: none today", label)?;
+                continue;
+            }
+            writeln!(buf, "{}: {}", label, time)?;
+            if golden_hour {
+                writeln!(
+                    buf,
+                    "\tGolden hour: {} to {}",
+                    window.start.format("%H:%M"),
+                    window.end.format("%H:%M")
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// Write out formatted information about current conditions for a mutable
     /// buffer.
     /// ```
     /// use weather_util_rust::weather_data::WeatherData;
+    /// use weather_util_rust::display_opts::DisplayOpts;
     /// # use anyhow::Error;
     /// # use std::io::{stdout, Write, Read};
     /// # use std::fs::File;
@@ -95,61 +460,707 @@ impl WeatherData {
     /// let data: WeatherData = serde_json::from_str(&buf)?;
     ///
     /// let mut buf = Vec::new();
-    /// data.get_current_conditions(&mut buf)?;
+    /// data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
     ///
     /// let buf = String::from_utf8(buf)?;
-    /// assert!(buf.starts_with("Current conditions Astoria US 40.76"));
-    /// assert!(buf.contains("Temperature: 41.05 F (5.03 C)"));
+    /// assert!(buf.starts_with("Current conditions Astoria, US (40.76, -73.93)\n"));
+    /// assert!(buf.contains("Temperature: 41.05°F (5.03°C) (feels like 28°F)\n"));
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_current_conditions<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
+    pub fn get_current_conditions<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        self.write_conditions(buf, opts, "Current conditions", "Last Updated", true)
+    }
+
+    /// Write out conditions taken from a forecast slot (see
+    /// `WeatherForecast::get_conditions_at`) like the current conditions,
+    /// but labelled as a forecast
+    pub fn get_forecast_conditions<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        self.write_conditions(buf, opts, "Forecast conditions", "Forecast for", false)
+    }
+
+    fn write_conditions<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+        heading: &str,
+        time_label: &str,
+        countdown: bool,
+    ) -> Result<(), Error> {
         let fo: FixedOffset = self.timezone.into();
         let dt = self.dt.with_timezone(&fo);
         let sunrise = self.sys.sunrise.with_timezone(&fo);
         let sunset = self.sys.sunset.with_timezone(&fo);
+        let deltas = opts
+            .baseline
+            .as_ref()
+            .and_then(|baseline| baseline.deltas(self));
+        if opts.hide_location {
+            writeln!(buf, "{}", heading)?;
+        } else {
+            writeln!(
+                buf,
+                "{} {}{}",
+                heading,
+                ResolvedLocation::from(self).describe(opts.coord_precision()),
+                self.elevation
+                    .map_or_else(String::new, |e| format!(" {}", opts.elevation(e)))
+            )?;
+        }
         writeln!(
             buf,
-            "Current conditions {} {}\n{}\n{}\n{}\n{}\n{}\n{}\n{}{}{}",
-            if let Some(country) = &self.sys.country {
-                format!("{} {}", self.name, country)
-            } else {
-                "".to_string()
-            },
-            format!("{}N {}E", self.coord.lat, self.coord.lon),
-            format!("Last Updated {}", dt,),
-            format!(
-                "\tTemperature: {:0.2} F ({:0.2} C)",
-                self.main.temp.fahrenheit(),
-                self.main.temp.celcius(),
+            "{}\n{}{}{}\n{}\n{}\n{}\n{}\n{}{}{}{}",
+            format_args!("{} {}", time_label, opts.local_time(dt)),
+            format_args!(
+                "\tTemperature: {}",
+                opts.paint_temperature(self.main.temp, &{
+                    let (primary, secondary) = opts.temperatures(self.main.temp, 2);
+                    format!("{} ({})", primary, secondary)
+                }),
             ),
-            format!("\tRelative Humidity: {}%", self.main.humidity),
-            format!(
-                "\tWind: {} degrees at {:0.2} mph",
-                self.wind.deg.unwrap_or_else(|| 0.0.into()),
-                (self.wind.speed.mph())
+            deltas.map(|d| d.temperature(opts)).unwrap_or_default(),
+            self.feels_like(opts),
+            format_args!(
+                "\tRelative Humidity: {}%{}",
+                self.main.humidity,
+                deltas.map(|d| d.humidity()).unwrap_or_default()
+            ),
+            format_args!(
+                "\tWind: {}{}{}",
+                self.wind.description(opts),
+                deltas.map(|d| d.wind_speed(opts)).unwrap_or_default(),
+                match opts.wind_percentile {
+                    Some(percentile) if percentile >= WINDY_PERCENTILE => {
+                        " (windier than usual this week)"
+                    }
+                    _ => "",
+                }
             ),
-            format!("\tConditions: {}", self.weather[0].description),
-            format!("\tSunrise: {}", sunrise),
-            format!("\tSunset: {}", sunset),
+            format_args!(
+                "\tConditions: {}{}",
+                opts.condition_icon(self.weather[0].id),
+                self.weather[0].description
+            ),
+            format_args!("\tSunrise: {}", opts.local_time(sunrise)),
+            format_args!("\tSunset: {}", opts.local_time(sunset)),
+            self.sun_countdown(opts.now())
+                .filter(|_| countdown)
+                .map_or_else(String::new, |countdown| format!("\n\t{}", countdown)),
             if let Some(rain) = &self.rain {
                 format!(
-                    "\n\tRain: {} in",
-                    rain.three_hour.map_or(0.0, Precipitation::inches)
+                    "\n\tRain: {}",
+                    opts.precipitation(rain.three_hour.or(rain.one_hour).unwrap_or_default())
                 )
             } else {
                 "".to_string()
             },
             if let Some(snow) = &self.snow {
                 format!(
-                    "\n\tSnow: {} in",
-                    snow.three_hour.map_or(0.0, Precipitation::inches)
+                    "\n\tSnow: {}",
+                    opts.precipitation(snow.three_hour.or(snow.one_hour).unwrap_or_default())
                 )
             } else {
                 "".to_string()
             },
-        )
-        .map(|_| ())
-        .map_err(Into::into)
+        )?;
+        if let Some(elevation) = opts.elevation {
+            writeln!(
+                buf,
+                "\tPressure: {} at {} m ({} at sea level)",
+                opts.pressure(self.main.pressure.at_elevation(elevation)),
+                elevation,
+                opts.pressure(self.main.pressure)
+            )?;
+        }
+        if opts.aviation {
+            writeln!(buf, "\tFlight category: {}", self.flight_category())?;
+        }
+        if opts.verbose {
+            writeln!(buf, "\tSource: {}", self.base)?;
+            if !self.extra.is_empty() {
+                let mut keys: Vec<_> = self.extra.keys().map(String::as_str).collect();
+                keys.sort_unstable();
+                writeln!(buf, "\tOther fields: {}", keys.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `2h 15m`, `1h` or `45m`, rounded down to the minute
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{DateTime, Duration, TimeZone as _, Utc};
+    use std::convert::TryFrom;
+
+    use crate::{
+        conditions::{Condition, Severity},
+        direction::CompassPoints,
+        display_opts::{DisplayOpts, IconStyle},
+        elevation::Elevation,
+        flight_category::FlightCategory,
+        humidity::Humidity,
+        latitude::Latitude,
+        longitude::Longitude,
+        precipitation::{Precipitation, PrecipitationUnit},
+        speed::Speed,
+        temperature::Temperature,
+        timezone::TimeZone,
+        units::Units,
+        weather_data::{Snow, WeatherData},
+    };
+
+    #[test]
+    fn test_negative_timezone() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("Last Updated 2020-01-19 13:23:42 -05:00"),
+            "{}",
+            buf
+        );
+        assert!(
+            buf.contains("Sunrise: 2020-01-19 07:15:59 -05:00"),
+            "{}",
+            buf
+        );
+        assert!(
+            buf.contains("Sunset: 2020-01-19 16:56:14 -05:00"),
+            "{}",
+            buf
+        );
+
+        // Newfoundland is UTC-03:30
+        data.timezone = TimeZone::try_from(-12600)?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("Sunrise: 2020-01-19 08:45:59 -03:30"),
+            "{}",
+            buf
+        );
+        assert!(
+            buf.contains("Sunset: 2020-01-19 18:26:14 -03:30"),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_utc() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let opts = DisplayOpts::default().with_show_utc(true);
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        for line in &[
+            "Last Updated 2020-01-19 13:23:42 -05:00 (2020-01-19 18:23:42 UTC)\n",
+            "Sunrise: 2020-01-19 07:15:59 -05:00 (2020-01-19 12:15:59 UTC)\n",
+            "Sunset: 2020-01-19 16:56:14 -05:00 (2020-01-19 21:56:14 UTC)\n",
+        ] {
+            assert!(buf.contains(line), "{}", buf);
+        }
+
+        // Another timezone shows the same UTC times
+        data.timezone = TimeZone::try_from(-12600)?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("Sunset: 2020-01-19 18:26:14 -03:30 (2020-01-19 21:56:14 UTC)\n"),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aviation() -> Result<(), Error> {
+        let mut data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        assert_eq!(data.cloud_cover(), Some(1));
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        assert!(!String::from_utf8(buf)?.contains("Flight category"));
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default().with_aviation(true))?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tFlight category: VFR\n"), "{}", buf);
+
+        // Overcast with a low cloud base
+        data.extra
+            .insert("clouds".into(), serde_json::json!({"all": 90}));
+        assert_eq!(data.flight_category(), FlightCategory::Vfr);
+        data.main.humidity = Humidity::try_from(90)?;
+        assert_eq!(data.flight_category(), FlightCategory::Ifr);
+
+        data.extra.remove("clouds");
+        assert_eq!(data.cloud_cover(), None);
+        assert_eq!(data.flight_category(), FlightCategory::Unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verbose_source() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(!buf.contains("Source:"), "{}", buf);
+
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default().with_verbose(true))?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.ends_with("\tSource: stations\n\tOther fields: clouds, cod, id\n"),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json() -> Result<(), Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        assert_eq!(data.name, "Astoria");
+        assert_eq!(data.main.humidity, Humidity::try_from(56)?);
+
+        let e = WeatherData::from_json(r#"{"coord": {"lon": -73.93"#).unwrap_err();
+        let e = e.to_string();
+        assert!(
+            e.starts_with("Failed to parse current conditions: "),
+            "{}",
+            e
+        );
+        assert!(e.contains("line 1 column"), "{}", e);
+
+        let e = WeatherData::from_json(r#"{"cod": 401, "message": "Invalid API key."}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(e.contains("missing field"), "{}", e);
+        assert!(e.ends_with("(Invalid API key.)"), "{}", e);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sun_countdown() -> Result<(), Error> {
+        let mut data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+
+        // Before sunset
+        assert_eq!(data.sun_countdown(data.dt), Some("Sunset in 3h 32m".into()));
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default().with_now(data.dt))?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("\tSunset: 2020-01-19 16:56:14 -05:00\n\tSunset in 3h 32m\n"),
+            "{}",
+            buf
+        );
+        let mut buf = Vec::new();
+        data.get_forecast_conditions(&mut buf, &DisplayOpts::default().with_now(data.dt))?;
+        assert!(!String::from_utf8(buf)?.contains("Sunset in"));
+
+        // After sunset it's the next day's sunrise
+        let now = data.sys.sunset + Duration::hours(1);
+        assert_eq!(data.sun_countdown(now), Some("Sunrise in 13h 19m".into()));
+
+        // At night before sunrise
+        let now = data.sys.sunrise - Duration::minutes(45);
+        assert_eq!(data.sun_countdown(now), Some("Sunrise in 45m".into()));
+        let now = data.sys.sunrise - Duration::hours(2);
+        assert_eq!(data.sun_countdown(now), Some("Sunrise in 2h".into()));
+
+        // Past the next sunrise too
+        let now = data.sys.sunrise + Duration::days(1) + Duration::hours(2);
+        data.dt = now;
+        assert_eq!(
+            data.sun_countdown(now),
+            Some("Sunset was 16h 19m ago".into())
+        );
+
+        // Conditions from another day
+        data.dt = data.sys.sunset;
+        assert_eq!(data.sun_countdown(data.dt + Duration::hours(25)), None);
+        data.sys.sunset = DateTime::<Utc>::default();
+        assert_eq!(data.sun_countdown(data.dt), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_golden_hours() -> Result<(), Error> {
+        let mut data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let (morning, evening) = data.golden_hours().unwrap();
+        assert_eq!(morning.start.to_rfc3339(), "2020-01-19T07:15:59-05:00");
+        assert_eq!(morning.end.to_rfc3339(), "2020-01-19T08:15:59-05:00");
+        assert_eq!(evening.start.to_rfc3339(), "2020-01-19T15:56:14-05:00");
+        assert_eq!(evening.end.to_rfc3339(), "2020-01-19T16:56:14-05:00");
+
+        let mut buf = Vec::new();
+        data.get_sun_times(&mut buf, true, false, false)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "Sunrise: 2020-01-19 07:15:59 -05:00\n"
+        );
+        let mut buf = Vec::new();
+        data.get_sun_times(&mut buf, true, true, true)?;
+        let buf = String::from_utf8(buf)?;
+        assert_eq!(buf.lines().count(), 4);
+        assert!(buf.contains("\tGolden hour: 07:15 to 08:15\n"), "{}", buf);
+
+        // Only the sunrise is missing, the sunset is still written
+        data.sys.sunrise = Utc.timestamp_opt(0, 0).unwrap();
+        assert!(data.golden_hours().is_none());
+        let mut buf = Vec::new();
+        data.get_sun_times(&mut buf, true, true, true)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "Sunrise: none today\nSunset: 2020-01-19 16:56:14 -05:00\n\tGolden hour: 15:56 to 16:56\n"
+        );
+
+        // Polar night
+        data.sys.sunset = Utc.timestamp_opt(0, 0).unwrap();
+        let mut buf = Vec::new();
+        data.get_sun_times(&mut buf, true, true, true)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "Sunrise: none today\nSunset: none today\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_calm_wind() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tWind: 310 degrees at 16.1 mph\n"), "{}", buf);
+        let mut buf = Vec::new();
+        let opts = DisplayOpts::default().with_compass_points(CompassPoints::ThirtyTwo);
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("\tWind: 310 degrees (NW) at 16.1 mph\n"),
+            "{}",
+            buf
+        );
+
+        data.wind = serde_json::from_str(r#"{"deg": 0}"#)?;
+        assert_eq!(data.wind.speed, Speed::default());
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tWind: calm\n"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_coord_precision() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        data.coord.lat = Latitude::try_from(40.712_776)?;
+        data.coord.lon = Longitude::try_from(-74.005_974)?;
+        let header = |opts: &DisplayOpts| -> Result<String, Error> {
+            let mut buf = Vec::new();
+            data.get_current_conditions(&mut buf, opts)?;
+            let buf = String::from_utf8(buf)?;
+            Ok(buf.lines().next().unwrap_or_default().to_string())
+        };
+        assert_eq!(
+            header(&DisplayOpts::default())?,
+            "Current conditions Astoria, US (40.7128, -74.006)"
+        );
+        assert_eq!(
+            header(&DisplayOpts::default().with_coord_precision(2))?,
+            "Current conditions Astoria, US (40.71, -74.01)"
+        );
+        assert_eq!(f64::from(data.coord.lat), 40.712_776);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hide_location() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(
+            &mut buf,
+            &DisplayOpts::default()
+                .with_hide_location(true)
+                .with_verbose(true),
+        )?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.starts_with("Current conditions\nLast Updated"),
+            "{}",
+            buf
+        );
+        assert!(!buf.contains("Astoria"), "{}", buf);
+        assert!(!buf.contains("40.76"), "{}", buf);
+        assert!(!buf.contains("-73.93"), "{}", buf);
+        assert!(buf.contains("Temperature: 41.05°F (5.03°C)"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_degree_symbol() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Temperature: 41.05°F (5.03°C)"), "{}", buf);
+
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default().with_ascii(true))?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Temperature: 41.05 F (5.03 C)"), "{}", buf);
+        assert!(!buf.contains('°'), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_condition_icon() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let description = &data.weather[0].description;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains(&format!("\tConditions: {}\n", description)));
+
+        let opts = DisplayOpts::default().with_icons(IconStyle::Emoji);
+        let icon = Condition::find(data.weather[0].id).unwrap().icon;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains(&format!("\tConditions: {} {}\n", icon, description)),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_severity() -> Result<(), Error> {
+        let mut data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        assert_eq!(data.severity(), Severity::Clear);
+        let mut storm = data.weather[0].clone();
+        storm.id = 211;
+        let mut fog = data.weather[0].clone();
+        fog.id = 741;
+        data.weather.push(fog);
+        assert_eq!(data.severity(), Severity::Caution);
+        data.weather.push(storm);
+        assert_eq!(data.severity(), Severity::Severe);
+        data.weather.clear();
+        assert_eq!(data.severity(), Severity::Clear);
+        Ok(())
+    }
+
+    #[test]
+    fn test_elevation_header() -> Result<(), Error> {
+        let buf = include_str!("../tests/weather.json").replace(
+            "\"name\": \"Astoria\"",
+            "\"name\": \"Astoria\", \"elevation\": 1655",
+        );
+        let data = WeatherData::from_json(&buf)?;
+        assert_eq!(data.elevation.map(Elevation::meters), Some(1655.0));
+        assert!(!data.extra.contains_key("elevation"));
+
+        let header = |data: &WeatherData, opts: &DisplayOpts| -> Result<String, Error> {
+            let mut buf = Vec::new();
+            data.get_current_conditions(&mut buf, opts)?;
+            Ok(String::from_utf8(buf)?.lines().next().unwrap().to_string())
+        };
+        assert_eq!(
+            header(&data, &DisplayOpts::default())?,
+            "Current conditions Astoria, US (40.76, -73.93) elev 5430 ft / 1655 m"
+        );
+        assert_eq!(
+            header(&data, &DisplayOpts::default().with_units(Units::Metric))?,
+            "Current conditions Astoria, US (40.76, -73.93) elev 1655 m / 5430 ft"
+        );
+
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        assert!(data.elevation.is_none());
+        assert_eq!(
+            header(&data, &DisplayOpts::default())?,
+            "Current conditions Astoria, US (40.76, -73.93)"
+        );
+        assert!(serde_json::to_value(&data)?.get("elevation").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_precipitation_unit() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        data.snow = Some(Snow {
+            three_hour: Some(Precipitation::from_millimeters(12.7)?),
+            one_hour: None,
+        });
+        let write = |opts: &DisplayOpts| -> Result<String, Error> {
+            let mut buf = Vec::new();
+            data.get_current_conditions(&mut buf, opts)?;
+            Ok(String::from_utf8(buf)?)
+        };
+        let opts = DisplayOpts::default();
+        assert!(write(&opts)?.contains("\n\tSnow: 0.50 in"));
+        let opts = opts.with_precipitation_unit(PrecipitationUnit::Centimeters);
+        assert!(write(&opts)?.contains("\n\tSnow: 1.27 cm"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_feels_like() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let opts = DisplayOpts::default();
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("\tTemperature: 41.05°F (5.03°C) (feels like 28°F)\n"),
+            "{}",
+            buf
+        );
+
+        // Within the threshold the "feels like" temperature is left out
+        data.main.feels_like = Temperature::from_celcius(data.main.temp.celcius() - 1.5)?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tTemperature: 41.05°F (5.03°C)\n"), "{}", buf);
+        assert!(!buf.contains("feels like"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_daytime() -> Result<(), Error> {
+        let mut data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        // 13:23 local, sunrise 07:15 and sunset 16:56
+        assert!(data.is_daytime());
+
+        // 22:00 local
+        data.dt = Utc.with_ymd_and_hms(2020, 1, 20, 3, 0, 0).unwrap();
+        assert!(!data.is_daytime());
+        // 06:00 local, before sunrise
+        data.dt = Utc.with_ymd_and_hms(2020, 1, 19, 11, 0, 0).unwrap();
+        assert!(!data.is_daytime());
+
+        // Sunset falls on the next UTC day, so before sunrise by time of day
+        data.timezone = TimeZone::try_from(0)?;
+        data.sys.sunrise = Utc.with_ymd_and_hms(2020, 1, 19, 12, 0, 0).unwrap();
+        data.sys.sunset = Utc.with_ymd_and_hms(2020, 1, 20, 2, 0, 0).unwrap();
+        data.dt = Utc.with_ymd_and_hms(2020, 1, 19, 23, 0, 0).unwrap();
+        assert!(data.is_daytime());
+        data.dt = Utc.with_ymd_and_hms(2020, 1, 19, 6, 0, 0).unwrap();
+        assert!(!data.is_daytime());
+
+        // Polar night, no sunrise or sunset
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/weather.json"))?;
+        json["sys"].as_object_mut().unwrap().remove("sunrise");
+        json["sys"].as_object_mut().unwrap().remove("sunset");
+        let data: WeatherData = serde_json::from_value(json)?;
+        assert!(data.is_daytime());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_temp_min_max() -> Result<(), Error> {
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let main = json["main"].as_object_mut().unwrap();
+        main.remove("temp_min");
+        main.remove("temp_max");
+        let data: WeatherData = serde_json::from_value(json)?;
+        assert_eq!(data.main.temp_min, data.main.temp);
+        assert_eq!(data.main.temp_max, data.main.temp);
+
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Temperature: 41.05°F (5.03°C)"), "{}", buf);
+
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        assert_eq!(data.main.temp_min, Temperature::try_from(276.48)?);
+        assert_eq!(data.main.temp_max, Temperature::try_from(279.82)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_elevation_pressure() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        assert!(!String::from_utf8(buf)?.contains("Pressure"));
+
+        let opts = DisplayOpts::default().with_elevation(Elevation::try_from(1500.0)?);
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.ends_with("\tPressure: 841 hPa at 1500 m (1008 hPa at sea level)\n"),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_fields() -> Result<(), Error> {
+        let mut json: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/weather.json"))?;
+        json["air_quality"] = serde_json::json!({"aqi": 2});
+        let data: WeatherData = serde_json::from_value(json)?;
+        assert_eq!(data.extra["air_quality"]["aqi"], 2);
+        assert_eq!(data.extra["cod"], 200);
+        assert!(!data.extra.contains_key("name"));
+
+        let value = serde_json::to_value(&data)?;
+        assert_eq!(value["air_quality"]["aqi"], 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_precision() -> Result<(), Error> {
+        let data: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let opts = DisplayOpts::default().with_elevation(Elevation::try_from(0.0)?);
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Temperature: 41.05°F (5.03°C)"), "{}", buf);
+        assert!(buf.contains("degrees at 16.1 mph\n"), "{}", buf);
+        assert!(buf.contains("Pressure: 1008 hPa at 0 m"), "{}", buf);
+
+        let opts = opts
+            .with_precision(0)
+            .with_wind_precision(2)
+            .with_pressure_precision(1);
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Temperature: 41°F (5°C)"), "{}", buf);
+        assert!(buf.contains("degrees at 16.11 mph\n"), "{}", buf);
+        assert!(buf.contains("Pressure: 1008.0 hPa at 0 m"), "{}", buf);
+        Ok(())
     }
 }
@@ -0,0 +1,142 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::{
+    display_opts::{round_coordinate, DisplayOpts},
+    timestamp,
+    weather_data::{Coord, Rain, Snow, Sys, WeatherCond, WeatherMain, Wind},
+};
+
+/// A city matching the search, with its current conditions
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct FindEntry {
+    pub id: u64,
+    pub name: String,
+    pub coord: Coord,
+    pub main: WeatherMain,
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind: Option<Wind>,
+    pub sys: Sys,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rain: Option<Rain>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow: Option<Snow>,
+    #[serde(default)]
+    pub weather: Vec<WeatherCond>,
+}
+
+/// Representation of the city search (`find`) response
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeatherFind {
+    #[serde(default)]
+    pub count: usize,
+    #[serde(default)]
+    pub list: Vec<FindEntry>,
+}
+
+impl WeatherFind {
+    /// Write out a line with the country, coordinates, temperature and
+    /// conditions of each candidate, in the order the api ranked them
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_find::WeatherFind;
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/find.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data: WeatherFind = serde_json::from_str(&buf)?;
+    ///
+    /// let mut buf = Vec::new();
+    /// data.get_candidates(&mut buf, &DisplayOpts::default())?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert!(buf.starts_with("\nMatching cities:\n\tLondon, GB (51.5085, -0.1257) "), "{}", buf);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_candidates<T: Write>(&self, buf: &mut T, opts: &DisplayOpts) -> Result<(), Error> {
+        writeln!(buf, "\nMatching cities:")?;
+        if self.list.is_empty() {
+            writeln!(buf, "\tNo matching cities")?;
+        }
+        self.list.iter().try_for_each(|entry| {
+            let name = match &entry.sys.country {
+                Some(country) => format!("{}, {}", entry.name, country),
+                None => entry.name.clone(),
+            };
+            let temp = opts.temperatures(entry.main.temp, 1).0;
+            writeln!(
+                buf,
+                "\t{} ({}, {}) {} {}",
+                name,
+                round_coordinate(entry.coord.lat.into(), opts.coord_precision()),
+                round_coordinate(entry.coord.lon.into(), opts.coord_precision()),
+                opts.paint_temperature(entry.main.temp, &temp),
+                entry
+                    .weather
+                    .first()
+                    .map_or("", |cond| cond.description.as_str()),
+            )
+            .map_err(Error::from)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::convert::TryFrom;
+
+    use crate::{
+        display_opts::DisplayOpts, temperature::Temperature, units::Units,
+        weather_find::WeatherFind,
+    };
+
+    #[test]
+    fn test_weather_find() -> Result<(), Error> {
+        let data: WeatherFind = serde_json::from_str(include_str!("../tests/find.json"))?;
+        assert_eq!(data.count, 3);
+        assert_eq!(data.list.len(), 3);
+        let countries: Vec<_> = data
+            .list
+            .iter()
+            .map(|entry| entry.sys.country.as_deref().unwrap_or(""))
+            .collect();
+        assert_eq!(countries, vec!["GB", "CA", "US"]);
+
+        let ontario = &data.list[1];
+        assert_eq!(ontario.id, 6058560);
+        assert_eq!(f64::from(ontario.coord.lon), -81.233);
+        assert_eq!(ontario.main.temp, Temperature::try_from(268.15)?);
+        assert!(data.list[0].snow.is_none());
+        let snow = ontario.snow.as_ref().and_then(|s| s.one_hour).unwrap();
+        assert_eq!(snow.millimeters(), 0.25);
+
+        let mut buf = Vec::new();
+        data.get_candidates(&mut buf, &DisplayOpts::default().with_units(Units::Metric))?;
+        let buf = String::from_utf8(buf)?;
+        let lines: Vec<_> = buf.lines().skip(2).collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "\tLondon, CA (42.9834, -81.233) -5.0°C light snow"
+        );
+        assert!(lines[2].ends_with(" clear sky"), "{}", buf);
+
+        let mut buf = Vec::new();
+        let empty: WeatherFind = serde_json::from_str(r#"{"cod":"200","count":0,"list":[]}"#)?;
+        empty.get_candidates(&mut buf, &DisplayOpts::default())?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "\nMatching cities:\n\tNo matching cities\n"
+        );
+        Ok(())
+    }
+}
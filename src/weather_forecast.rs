@@ -1,13 +1,54 @@
-use anyhow::Error;
-use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use anyhow::{format_err, Error};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, io::Write};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Write,
+};
 
 use crate::{
-    humidity::Humidity, latitude::Latitude, longitude::Longitude, precipitation::Precipitation,
-    pressure::Pressure, temperature::Temperature, timestamp, timezone::TimeZone,
+    display_opts::DisplayOpts,
+    forecast_summary::{DaySummary, ForecastSummary, TemperatureField},
+    humidity::Humidity,
+    latitude::Latitude,
+    longitude::Longitude,
+    precipitation::Precipitation,
+    pressure::Pressure,
+    speed::Speed,
+    temperature::Temperature,
+    timestamp,
+    timezone::TimeZone,
+    weather_data::{
+        parse_json, Rain as CurrentRain, Snow as CurrentSnow, WeatherCond, WeatherData,
+        WeatherMain, Wind,
+    },
 };
 
+/// Number of days covered by the 3 hour forecast
+pub const FORECAST_DAYS: usize = 5;
+
+/// Number of 3 hour slots in a day
+pub const FORECAST_SLOTS_PER_DAY: usize = 8;
+
+/// Most slots the 3 hour forecast endpoint returns
+pub const MAX_FORECAST_SLOTS: usize = FORECAST_DAYS * FORECAST_SLOTS_PER_DAY;
+
+/// Value of `base` for conditions taken from a forecast slot
+pub const FORECAST_BASE: &str = "forecast";
+
+/// Times up to half a (3 hour) slot before the first or after the last slot
+/// are still matched to them by `get_conditions_at`
+pub const SLOT_MARGIN_MINUTES: i64 = 90;
+
+/// Written by `get_forecast` and `get_interpolated_hourly` in place of a
+/// forecast without any entries
+pub const NO_FORECAST: &str = "No forecast available";
+
+/// Current wind speeds at or above this percentile of the forecast's are
+/// labelled "windier than usual this week"
+pub const WINDY_PERCENTILE: f64 = 90.0;
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ForecastMain {
     pub temp: Temperature,
@@ -37,10 +78,20 @@ pub struct ForecastEntry {
     #[serde(with = "timestamp")]
     pub dt: DateTime<Utc>,
     pub main: ForecastMain,
+    #[serde(default)]
+    pub weather: Vec<WeatherCond>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind: Option<Wind>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rain: Option<Rain>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub snow: Option<Snow>,
+    /// Probability of precipitation (0.0 to 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pop: Option<f64>,
+    /// Any fields not otherwise recognized, e.g. `clouds` or `dt_txt`
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -52,6 +103,32 @@ pub struct CityEntry {
     pub sunset: DateTime<Utc>,
 }
 
+/// Temperature at a single hour, either one of the forecast entries or
+/// interpolated between two of them
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct HourlyTemperature {
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    pub temp: Temperature,
+    pub interpolated: bool,
+}
+
+/// A point of the combined timeline of the current conditions and the
+/// forecast, see `WeatherForecast::get_timeline`
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TimelineEntry {
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    pub temp: Temperature,
+    pub feels_like: Temperature,
+    pub pressure: Pressure,
+    pub humidity: Humidity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_speed: Option<Speed>,
+    /// The current observation rather than a forecast entry
+    pub observed: bool,
+}
+
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct WeatherForecast {
     pub list: Vec<ForecastEntry>,
@@ -59,6 +136,12 @@ pub struct WeatherForecast {
 }
 
 impl WeatherForecast {
+    /// Parse the body of a forecast response fetched by other means than
+    /// `WeatherApi`
+    pub fn from_json(text: &str) -> Result<Self, Error> {
+        parse_json(text, "forecast")
+    }
+
     /// Get Map of Date to High/Low temperatures
     /// ```
     /// # use anyhow::Error;
@@ -95,38 +178,330 @@ impl WeatherForecast {
     pub fn get_high_low(
         &self,
     ) -> BTreeMap<NaiveDate, (Temperature, Temperature, Precipitation, Precipitation)> {
+        self.get_high_low_of(TemperatureField::Temperature)
+    }
+
+    /// Same as `get_high_low`, with the highs and lows taken from `field`
+    pub fn get_high_low_of(
+        &self,
+        field: TemperatureField,
+    ) -> BTreeMap<NaiveDate, (Temperature, Temperature, Precipitation, Precipitation)> {
+        self.get_forecast_summary_of(field)
+            .days
+            .into_iter()
+            .map(|day| (day.date, (day.high, day.low, day.rain, day.snow)))
+            .collect()
+    }
+
+    /// Aggregate the forecast entries by (local) day
+    pub fn get_forecast_summary(&self) -> ForecastSummary {
+        self.get_forecast_summary_of(TemperatureField::Temperature)
+    }
+
+    /// Aggregate the forecast entries by (local) day, with the highs, lows
+    /// and averages taken from `field`
+    pub fn get_forecast_summary_of(&self, field: TemperatureField) -> ForecastSummary {
         let fo: FixedOffset = self.city.timezone.into();
-        self.list.iter().fold(BTreeMap::new(), |mut hmap, entry| {
-            let date = entry.dt.with_timezone(&fo).date().naive_local();
-            let high = entry.main.temp_max;
-            let low = entry.main.temp_min;
-            let rain = if let Some(rain) = &entry.rain {
-                rain.three_hour.unwrap_or_default()
-            } else {
-                Precipitation::default()
+        let days = self.list.iter().fold(BTreeMap::new(), |mut days, entry| {
+            let date = entry.dt.with_timezone(&fo).date_naive();
+            let (high, low, temp) = match field {
+                TemperatureField::Temperature => {
+                    (entry.main.temp_max, entry.main.temp_min, entry.main.temp)
+                }
+                TemperatureField::FeelsLike => (
+                    entry.main.feels_like,
+                    entry.main.feels_like,
+                    entry.main.feels_like,
+                ),
             };
-            let snow = if let Some(snow) = &entry.snow {
-                snow.three_hour.unwrap_or_default()
-            } else {
-                Precipitation::default()
+            let day = DaySummary {
+                date,
+                high,
+                low,
+                average: None,
+                rain: entry
+                    .rain
+                    .as_ref()
+                    .and_then(|rain| rain.three_hour)
+                    .unwrap_or_default(),
+                snow: entry
+                    .snow
+                    .as_ref()
+                    .and_then(|snow| snow.three_hour)
+                    .unwrap_or_default(),
+                pop: entry.pop,
+                wind_speed: entry.wind.as_ref().map(|wind| wind.speed),
+                observations: Some(1),
+                condition: entry.weather.first().map(|cond| cond.id),
             };
+            days.entry(date)
+                .and_modify(|(summary, temps): &mut (DaySummary, Vec<Temperature>)| {
+                    summary.merge(&day);
+                    temps.push(temp);
+                })
+                .or_insert_with(|| (day, vec![temp]));
+            days
+        });
+        let days = days
+            .into_values()
+            .map(|(day, temps)| DaySummary {
+                average: Temperature::average(&temps),
+                ..day
+            })
+            .collect();
+        ForecastSummary { days }
+    }
 
-            if let Some((h, l, r, s)) = hmap.get(&date) {
-                let high = if high > *h { high } else { *h };
-                let low = if low < *l { low } else { *l };
-                let rain = *r + rain;
-                let snow = *s + snow;
-
-                if (high, low) != (*h, *l) {
-                    hmap.insert(date, (high, low, rain, snow));
-                }
-            } else {
-                hmap.insert(date, (high, low, rain, snow));
+    /// Drop the entries after the first `days` (local) days
+    pub fn retain_days(&mut self, days: usize) {
+        let fo: FixedOffset = self.city.timezone.into();
+        let mut dates = Vec::new();
+        self.list.retain(|entry| {
+            let date = entry.dt.with_timezone(&fo).date_naive();
+            if !dates.contains(&date) {
+                dates.push(date);
             }
-            hmap
+            dates.len() <= days
+        });
+    }
+
+    /// Lowest and highest temperature over the whole forecast, `None` if the
+    /// forecast is empty
+    pub fn get_temperature_range(&self) -> Option<(Temperature, Temperature)> {
+        self.list.iter().fold(None, |range, entry| {
+            let (low, high) = (entry.main.temp_min, entry.main.temp_max);
+            Some(match range {
+                Some((l, h)) => (
+                    if low < l { low } else { l },
+                    if high > h { high } else { h },
+                ),
+                None => (low, high),
+            })
         })
     }
 
+    /// Percentile of `speed` among the wind speeds of the forecast slots,
+    /// i.e. the percentage of slots with less wind, `None` if no slot has a
+    /// wind speed
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::fs::read_to_string;
+    /// use weather_util_rust::speed::Speed;
+    /// use weather_util_rust::weather_forecast::WeatherForecast;
+    /// # fn main() -> Result<(), Error> {
+    /// # let forecast = WeatherForecast::from_json(&read_to_string("tests/forecast.json")?)?;
+    /// assert_eq!(forecast.wind_percentile(Speed::from_mps(2.0)?), Some(50.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn wind_percentile(&self, speed: Speed) -> Option<f64> {
+        let speeds: Vec<_> = self
+            .list
+            .iter()
+            .filter_map(|entry| entry.wind.as_ref().map(|wind| wind.speed))
+            .collect();
+        speed.percentile(&speeds)
+    }
+
+    /// The forecast slot nearest to `dt`, `None` outside the forecast
+    /// window (or for an empty forecast)
+    pub fn nearest_entry(&self, dt: DateTime<Utc>) -> Option<&ForecastEntry> {
+        let margin = Duration::minutes(SLOT_MARGIN_MINUTES);
+        let first = self.list.iter().map(|entry| entry.dt).min()?;
+        let last = self.list.iter().map(|entry| entry.dt).max()?;
+        if dt < first - margin || dt > last + margin {
+            return None;
+        }
+        self.list
+            .iter()
+            .min_by_key(|entry| (entry.dt - dt).num_seconds().abs())
+    }
+
+    /// Conditions of the forecast slot nearest to `dt`, in the shape of the
+    /// `current` conditions (which provide the location) so that they can
+    /// be written out with `WeatherData::get_forecast_conditions`
+    /// ```
+    /// # use anyhow::Error;
+    /// # use chrono::{TimeZone, Utc};
+    /// # use std::fs::read_to_string;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// use weather_util_rust::weather_forecast::WeatherForecast;
+    /// # fn main() -> Result<(), Error> {
+    /// # let current = WeatherData::from_json(&read_to_string("tests/weather.json")?)?;
+    /// # let forecast = WeatherForecast::from_json(&read_to_string("tests/forecast.json")?)?;
+    /// let dt = Utc.with_ymd_and_hms(2020, 1, 21, 13, 0, 0).unwrap();
+    /// let conditions = forecast.get_conditions_at(dt, &current)?;
+    /// assert_eq!(conditions.dt, Utc.with_ymd_and_hms(2020, 1, 21, 12, 0, 0).unwrap());
+    /// assert_eq!(conditions.name, "Astoria");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_conditions_at(
+        &self,
+        dt: DateTime<Utc>,
+        current: &WeatherData,
+    ) -> Result<WeatherData, Error> {
+        let entry = self.nearest_entry(dt).ok_or_else(|| {
+            let fo: FixedOffset = self.city.timezone.into();
+            let window = self
+                .list
+                .first()
+                .zip(self.list.last())
+                .map(|(first, last)| {
+                    format!(
+                        ", the forecast covers {} to {}",
+                        first.dt.with_timezone(&fo),
+                        last.dt.with_timezone(&fo)
+                    )
+                })
+                .unwrap_or_default();
+            format_err!(
+                "{} is outside the forecast window{}",
+                dt.with_timezone(&fo),
+                window
+            )
+        })?;
+        let mut conditions = current.clone();
+        conditions.dt = entry.dt;
+        conditions.main = WeatherMain {
+            temp: entry.main.temp,
+            feels_like: entry.main.feels_like,
+            temp_min: entry.main.temp_min,
+            temp_max: entry.main.temp_max,
+            pressure: entry.main.pressure,
+            humidity: entry.main.humidity,
+        };
+        conditions.weather = entry.weather.clone();
+        conditions.wind = entry.wind.clone().unwrap_or_default();
+        conditions.rain = entry.rain.as_ref().map(|rain| CurrentRain {
+            three_hour: rain.three_hour,
+            one_hour: None,
+        });
+        conditions.snow = entry.snow.as_ref().map(|snow| CurrentSnow {
+            three_hour: snow.three_hour,
+            one_hour: None,
+        });
+        conditions.visibility = None;
+        conditions.base = FORECAST_BASE.into();
+        conditions.extra = HashMap::new();
+        Ok(conditions)
+    }
+
+    /// A single chronological sequence starting with the `current`
+    /// observation followed by the forecast entries after it.  Forecast
+    /// entries at or before the time of the observation are dropped, as are
+    /// repeated timestamps, so every `dt` appears once.
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// use weather_util_rust::weather_forecast::WeatherForecast;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let current: WeatherData = serde_json::from_str(&buf)?;
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/forecast.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let forecast: WeatherForecast = serde_json::from_str(&buf)?;
+    ///
+    /// let timeline = forecast.get_timeline(&current);
+    /// assert_eq!(timeline.len(), forecast.list.len() + 1);
+    /// assert!(timeline[0].observed);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_timeline(&self, current: &WeatherData) -> Vec<TimelineEntry> {
+        let mut forecast: Vec<_> = self
+            .list
+            .iter()
+            .filter(|entry| entry.dt > current.dt)
+            .map(|entry| TimelineEntry {
+                dt: entry.dt,
+                temp: entry.main.temp,
+                feels_like: entry.main.feels_like,
+                pressure: entry.main.pressure,
+                humidity: entry.main.humidity,
+                wind_speed: entry.wind.as_ref().map(|wind| wind.speed),
+                observed: false,
+            })
+            .collect();
+        forecast.sort_by_key(|entry| entry.dt);
+        forecast.dedup_by_key(|entry| entry.dt);
+
+        let mut timeline = vec![TimelineEntry {
+            dt: current.dt,
+            temp: current.main.temp,
+            feels_like: current.main.feels_like,
+            pressure: current.main.pressure,
+            humidity: current.main.humidity,
+            wind_speed: Some(current.wind.speed),
+            observed: true,
+        }];
+        timeline.extend(forecast);
+        timeline
+    }
+
+    /// Hourly temperatures, linearly interpolated between the 3 hourly
+    /// forecast entries.  Only the temperature is interpolated, conditions
+    /// can't be.
+    pub fn get_hourly_temperatures(&self) -> Vec<HourlyTemperature> {
+        let mut hourly: Vec<_> = self
+            .list
+            .windows(2)
+            .flat_map(|pair| {
+                let (start, end) = (&pair[0], &pair[1]);
+                let hours = (end.dt - start.dt).num_hours().max(1);
+                (0..hours).map(move |hour| HourlyTemperature {
+                    dt: start.dt + Duration::hours(hour),
+                    temp: start
+                        .main
+                        .temp
+                        .interpolate(end.main.temp, hour as f64 / hours as f64),
+                    interpolated: hour > 0,
+                })
+            })
+            .collect();
+        if let Some(last) = self.list.last() {
+            hourly.push(HourlyTemperature {
+                dt: last.dt,
+                temp: last.main.temp,
+                interpolated: false,
+            });
+        }
+        hourly
+    }
+
+    /// Write out `get_hourly_temperatures`, marking interpolated values
+    pub fn get_interpolated_hourly<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        if self.list.is_empty() {
+            writeln!(buf, "\n{}", NO_FORECAST)?;
+            return Ok(());
+        }
+        let fo: FixedOffset = self.city.timezone.into();
+        writeln!(buf, "\nHourly Forecast (* interpolated):")?;
+        self.get_hourly_temperatures()
+            .into_iter()
+            .try_for_each(|hour| {
+                let (primary, secondary) = opts.temperatures(hour.temp, 1);
+                let temp = format!("{} / {}", primary, secondary);
+                writeln!(
+                    buf,
+                    "\t{} {}{}",
+                    hour.dt.with_timezone(&fo).format("%Y-%m-%d %H:%M"),
+                    opts.paint_temperature(hour.temp, &temp),
+                    if hour.interpolated { " *" } else { "" },
+                )
+                .map_err(Error::from)
+            })
+    }
+
     /// Get High and Low Temperatures for the Next Few Days
     /// ```
     /// # use anyhow::Error;
@@ -135,6 +510,7 @@ impl WeatherForecast {
     /// # use std::convert::TryFrom;
     /// # use chrono::NaiveDate;
     /// use weather_util_rust::weather_forecast::WeatherForecast;
+    /// use weather_util_rust::display_opts::DisplayOpts;
     /// # fn main() -> Result<(), Error> {
     /// # let mut buf = String::new();
     /// # let mut f = File::open("tests/forecast.json")?;
@@ -142,43 +518,460 @@ impl WeatherForecast {
     /// let data: WeatherForecast = serde_json::from_str(&buf)?;
     ///
     /// let mut buf = Vec::new();
-    /// data.get_forecast(&mut buf)?;
+    /// data.get_forecast(&mut buf, &DisplayOpts::default())?;
     ///
     /// let buf = String::from_utf8(buf)?;
-    /// assert!(buf.starts_with("\nForecast:"), buf);
-    /// assert!(buf.contains("2020-01-23 High: 37.7 F / 3.2 C"));
-    /// assert!(buf.contains("Low: 30.1 F / -1.1 C"));
+    /// assert!(buf.starts_with("\nForecast:\n\tWeek ahead: 19°F to 40°F\n"), buf);
+    /// assert!(buf.contains("Thu      High: 37.7°F / 3.2°C"));
+    /// assert!(buf.contains("Low: 30.1°F / -1.1°C"));
+    /// assert!(buf.contains("\nBest day: Friday\n"), "{}", buf);
+    /// assert!(buf.ends_with(" over the next 3 days\n"), "{}", buf);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn get_forecast<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
-        writeln!(buf, "\nForecast:")?;
-        self.get_high_low()
+    pub fn get_forecast<T: Write>(&self, buf: &mut T, opts: &DisplayOpts) -> Result<(), Error> {
+        if opts.forecast_days == Some(0) {
+            return Ok(());
+        }
+        if self.list.is_empty() {
+            writeln!(buf, "\n{}", NO_FORECAST)?;
+            return Ok(());
+        }
+        opts.write_forecast_banner(buf)?;
+        let summary = self.get_forecast_summary_of(opts.forecast_field);
+        if let Some((low, high)) = summary.temperature_range() {
+            writeln!(
+                buf,
+                "\tWeek ahead: {} to {}",
+                opts.temperatures(low, 0).0,
+                opts.temperatures(high, 0).0
+            )?;
+        }
+        let fo: FixedOffset = self.city.timezone.into();
+        let today = opts.now().with_timezone(&fo).date_naive();
+        summary.write_days(buf, opts, today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+    use std::{collections::BTreeMap, convert::TryFrom};
+
+    use crate::{
+        display_opts::DisplayOpts,
+        forecast_summary::TemperatureField,
+        precipitation::Precipitation,
+        speed::Speed,
+        temperature::Temperature,
+        timezone::TimeZone,
+        units::Units,
+        weather_data::WeatherData,
+        weather_forecast::{Rain, Snow, WeatherForecast, WINDY_PERCENTILE},
+    };
+
+    #[test]
+    fn test_get_high_low() -> Result<(), Error> {
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let fo: FixedOffset = forecast.city.timezone.into();
+        let date = NaiveDate::from_ymd_opt(2020, 1, 20).unwrap();
+        let slots: Vec<usize> = (0..forecast.list.len())
+            .filter(|i| forecast.list[*i].dt.with_timezone(&fo).date_naive() == date)
+            .collect();
+        let (high, low, rain, snow) = forecast.get_high_low()[&date];
+        assert_eq!((rain, snow), Default::default());
+        // Precipitation in slots setting neither the high nor the low must
+        // still be counted
+        let wet: Vec<usize> = slots
             .into_iter()
-            .map(|(d, (h, l, r, s))| {
-                writeln!(
-                    buf,
-                    "\t{} {:25} {:25} {:25}",
-                    d,
-                    format!("High: {:0.1} F / {:0.1} C", h.fahrenheit(), h.celcius(),),
-                    format!("Low: {:0.1} F / {:0.1} C", l.fahrenheit(), l.celcius(),),
-                    format!(
-                        "{}{}",
-                        if r.millimeters() > 0.0 {
-                            format!("Rain {:0.2} in", r.inches())
-                        } else {
-                            "".to_string()
-                        },
-                        if s.millimeters() > 0.0 {
-                            format!("Snow {:0.2} in", s.inches())
-                        } else {
-                            "".to_string()
-                        },
-                    )
-                )
-                .map(|_| ())
-                .map_err(Into::into)
+            .filter(|i| {
+                let main = &forecast.list[*i].main;
+                main.temp_max != high && main.temp_min != low
             })
-            .collect()
+            .take(2)
+            .collect();
+        assert_eq!(wet.len(), 2);
+        for (i, mm) in wet.iter().zip(&[1.5, 2.0]) {
+            forecast.list[*i].rain = Some(Rain {
+                three_hour: Some(Precipitation::from_millimeters(*mm)?),
+            });
+        }
+        forecast.list[wet[1]].snow = Some(Snow {
+            three_hour: Some(Precipitation::from_millimeters(0.5)?),
+        });
+        let total = (
+            high,
+            low,
+            Precipitation::from_millimeters(3.5)?,
+            Precipitation::from_millimeters(0.5)?,
+        );
+        assert_eq!(forecast.get_high_low()[&date], total);
+
+        // Simulate a much longer forecast by repeating the list
+        let entries = forecast.list.clone();
+        for days in 1..10 {
+            forecast
+                .list
+                .extend(entries.iter().cloned().map(|mut entry| {
+                    entry.dt += Duration::days(5 * days);
+                    entry
+                }));
+        }
+        let high_low = forecast.get_high_low();
+        assert_eq!(high_low.len(), 51);
+        assert_eq!(high_low[&date], total);
+        assert_eq!(high_low[&(date + Duration::days(45))], total);
+        Ok(())
+    }
+
+    #[test]
+    fn test_temperature_range() -> Result<(), Error> {
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let (low, high) = forecast.get_temperature_range().unwrap();
+        assert!((low.celcius() - -7.33).abs() < 1e-6);
+        assert!((high.celcius() - 4.3).abs() < 1e-6);
+
+        let mut buf = Vec::new();
+        let opts = DisplayOpts::default().with_units(Units::Metric);
+        forecast.get_forecast(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tWeek ahead: -7°C to 4°C\n"), "{}", buf);
+
+        forecast.retain_days(2);
+        let summary = forecast.get_forecast_summary();
+        assert_eq!(summary.days.len(), 2);
+        assert_eq!(
+            summary.days[1].date,
+            NaiveDate::from_ymd_opt(2020, 1, 20).unwrap()
+        );
+
+        forecast.list.clear();
+        assert!(forecast.get_temperature_range().is_none());
+        let mut buf = Vec::new();
+        forecast.get_forecast(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(!buf.contains("Week ahead"), "{}", buf);
+        assert_eq!(buf, "\nNo forecast available\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_hourly_temperatures() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let hourly = forecast.get_hourly_temperatures();
+        assert_eq!(hourly.len(), (forecast.list.len() - 1) * 3 + 1);
+
+        let (first, second) = (&forecast.list[0], &forecast.list[1]);
+        assert_eq!(hourly[0].dt, first.dt);
+        assert_eq!(hourly[0].temp, first.main.temp);
+        assert!(!hourly[0].interpolated);
+        assert_eq!(hourly[3].temp, second.main.temp);
+        assert!(!hourly[3].interpolated);
+
+        // A third and two thirds of the way between the entries
+        let step = (second.main.temp.kelvin() - first.main.temp.kelvin()) / 3.0;
+        assert!(hourly[1].interpolated && hourly[2].interpolated);
+        assert_eq!(hourly[1].dt, first.dt + Duration::hours(1));
+        assert!((hourly[1].temp.kelvin() - (first.main.temp.kelvin() + step)).abs() < 1e-9);
+        assert!((hourly[2].temp.kelvin() - (second.main.temp.kelvin() - step)).abs() < 1e-9);
+
+        let last = hourly.last().unwrap();
+        assert_eq!(last.dt, forecast.list.last().unwrap().dt);
+        assert!(!last.interpolated);
+
+        let mut buf = Vec::new();
+        forecast.get_interpolated_hourly(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        let lines: Vec<_> = buf.lines().skip(2).collect();
+        assert_eq!(lines.len(), hourly.len());
+        assert!(!lines[0].ends_with(" *"), "{}", buf);
+        assert!(lines[1].ends_with(" *"), "{}", buf);
+        assert!(lines[1].starts_with("\t2020-01-19 17:00 "), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feels_like_high_low() -> Result<(), Error> {
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        // Make the apparent temperature clearly differ from the temperature
+        for entry in &mut forecast.list {
+            entry.main.feels_like = Temperature::from_celcius(entry.main.temp.celcius() - 20.0)?;
+        }
+        let fo: FixedOffset = forecast.city.timezone.into();
+        let date: NaiveDate = "2020-01-21".parse()?;
+        let feels_like: Vec<_> = forecast
+            .list
+            .iter()
+            .filter(|entry| entry.dt.with_timezone(&fo).date_naive() == date)
+            .map(|entry| entry.main.feels_like)
+            .collect();
+        let lowest = feels_like
+            .iter()
+            .fold(feels_like[0], |a, &b| if b < a { b } else { a });
+        let highest = feels_like
+            .iter()
+            .fold(feels_like[0], |a, &b| if b > a { b } else { a });
+
+        let high_low = forecast.get_high_low_of(TemperatureField::FeelsLike);
+        let (high, low, _, _) = high_low[&date];
+        assert_eq!((high, low), (highest, lowest));
+        let (high, low, _, _) = forecast.get_high_low()[&date];
+        assert_eq!(high, Temperature::try_from(272.65)?);
+        assert!(low > lowest);
+
+        let summary = forecast.get_forecast_summary_of(TemperatureField::FeelsLike);
+        let day = summary.days.iter().find(|day| day.date == date).unwrap();
+        assert_eq!(day.average, Temperature::average(&feels_like));
+
+        let opts = DisplayOpts::default().with_forecast_field(TemperatureField::FeelsLike);
+        let mut buf = Vec::new();
+        forecast.get_forecast(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.starts_with("\nForecast (feels like):\n"), "{}", buf);
+        assert!(buf.contains(&opts.temperatures(highest, 1).0), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json() -> Result<(), Error> {
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        assert_eq!(forecast.list.len(), 40);
+
+        let e = WeatherForecast::from_json(include_str!("../tests/weather.json"))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            e.starts_with("Failed to parse forecast: missing field `list`"),
+            "{}",
+            e
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_observations() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        // The first slot is in the afternoon of the 19th, local time
+        let counts: Vec<_> = forecast
+            .get_forecast_summary()
+            .days
+            .iter()
+            .map(|day| day.observations)
+            .collect();
+        assert_eq!(
+            counts,
+            vec![Some(3), Some(8), Some(8), Some(8), Some(8), Some(5)]
+        );
+
+        let mut buf = Vec::new();
+        forecast.get_forecast(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(!buf.contains(" obs)"), "{}", buf);
+
+        let mut buf = Vec::new();
+        forecast.get_forecast(&mut buf, &DisplayOpts::default().with_verbose(true))?;
+        let buf = String::from_utf8(buf)?;
+        let lines: Vec<_> = buf.lines().filter(|l| l.contains("High: ")).collect();
+        assert!(lines[0].ends_with(" (3 obs)"), "{}", buf);
+        assert!(lines[1].ends_with(" (8 obs)"), "{}", buf);
+        assert!(lines[5].ends_with(" (5 obs)"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_forecast_days() -> Result<(), Error> {
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        let forecast_lines = |opts: &DisplayOpts| -> Result<String, Error> {
+            let mut buf = Vec::new();
+            forecast.get_forecast(&mut buf, opts)?;
+            Ok(String::from_utf8(buf)?)
+        };
+        let full = forecast_lines(&DisplayOpts::default())?;
+        let buf = forecast_lines(&DisplayOpts::default().with_forecast_days(2))?;
+        let days: Vec<_> = buf.lines().filter(|l| l.contains("High: ")).collect();
+        assert_eq!(days.len(), 2, "{}", buf);
+        assert!(full.contains(days[1]), "{}", full);
+        assert!(!buf.contains("Thu "), "{}", buf);
+        // All of the forecast given is still aggregated
+        assert!(buf.contains("\tWeek ahead: 19°F to 40°F\n"), "{}", buf);
+        assert!(buf.contains("\nBest day: Friday\n"), "{}", buf);
+
+        assert_eq!(
+            forecast_lines(&DisplayOpts::default().with_forecast_days(10))?,
+            full
+        );
+        assert_eq!(
+            forecast_lines(&DisplayOpts::default().with_forecast_days(0))?,
+            ""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_today_label() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        assert_eq!(forecast.city.timezone, TimeZone::try_from(-18000)?);
+        let labels = |now: &str| -> Result<Vec<String>, Error> {
+            let now = DateTime::parse_from_rfc3339(now)?.with_timezone(&Utc);
+            let mut buf = Vec::new();
+            forecast.get_forecast(&mut buf, &DisplayOpts::default().with_now(now))?;
+            Ok(String::from_utf8(buf)?
+                .lines()
+                .filter(|line| line.contains("High: "))
+                .take(3)
+                .map(|line| line.split_whitespace().next().unwrap().to_string())
+                .collect())
+        };
+        // Already the 20th in UTC but still the evening of the 19th in
+        // Astoria
+        assert_eq!(
+            labels("2020-01-20T03:30:00Z")?,
+            vec!["Today", "Tomorrow", "Tue"]
+        );
+        assert_eq!(
+            labels("2020-01-20T05:30:00Z")?,
+            vec!["Sun", "Today", "Tomorrow"]
+        );
+
+        // The last day of the default forecast is still within its window
+        let now = DateTime::parse_from_rfc3339("2020-01-19T18:00:00Z")?.with_timezone(&Utc);
+        let mut buf = Vec::new();
+        forecast.get_forecast(&mut buf, &DisplayOpts::default().with_now(now))?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\tFri "), "{}", buf);
+        assert!(!buf.contains("low confidence"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_conditions_at() -> Result<(), Error> {
+        let current = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).map(|dt| dt.with_timezone(&Utc));
+
+        let entry = forecast.nearest_entry(at("2020-01-21T13:29:00Z")?).unwrap();
+        assert_eq!(entry.dt, at("2020-01-21T12:00:00Z")?);
+        let entry = forecast.nearest_entry(at("2020-01-21T13:31:00Z")?).unwrap();
+        assert_eq!(entry.dt, at("2020-01-21T15:00:00Z")?);
+        // Within half a slot of either end
+        let entry = forecast.nearest_entry(at("2020-01-19T19:30:00Z")?).unwrap();
+        assert_eq!(entry.dt, at("2020-01-19T21:00:00Z")?);
+        assert!(forecast
+            .nearest_entry(at("2020-01-24T19:31:00Z")?)
+            .is_none());
+
+        let conditions = forecast.get_conditions_at(at("2020-01-21T13:29:00Z")?, &current)?;
+        assert_eq!(
+            conditions.main.temp,
+            entry_temp(&forecast, "2020-01-21T12:00:00Z")?
+        );
+        let mut buf = Vec::new();
+        conditions.get_forecast_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.starts_with(
+                "Forecast conditions Astoria, US (40.76, -73.93)\nForecast for 2020-01-21 \
+                 07:00:00 -05:00\n"
+            ),
+            "{}",
+            buf
+        );
+
+        let e = forecast
+            .get_conditions_at(at("2020-01-28T12:00:00Z")?, &current)
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "2020-01-28 07:00:00 -05:00 is outside the forecast window, the forecast covers \
+             2020-01-19 16:00:00 -05:00 to 2020-01-24 13:00:00 -05:00"
+        );
+        Ok(())
+    }
+
+    fn entry_temp(forecast: &WeatherForecast, dt: &str) -> Result<Temperature, Error> {
+        let dt = DateTime::parse_from_rfc3339(dt)?;
+        Ok(forecast
+            .list
+            .iter()
+            .find(|entry| entry.dt == dt)
+            .map(|entry| entry.main.temp)
+            .unwrap())
+    }
+
+    #[test]
+    fn test_timeline() -> Result<(), Error> {
+        let mut current: WeatherData = serde_json::from_str(include_str!("../tests/weather.json"))?;
+        let mut forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        // The observation coincides with the second forecast entry and the
+        // last entry is repeated
+        current.dt = forecast.list[1].dt;
+        let last = forecast.list.last().unwrap().clone();
+        forecast.list.push(last);
+
+        let timeline = forecast.get_timeline(&current);
+        assert_eq!(timeline.len(), forecast.list.len() - 2);
+        assert!(timeline[0].observed);
+        assert_eq!(timeline[0].dt, current.dt);
+        assert_eq!(timeline[0].temp, current.main.temp);
+        assert!(timeline[1..].iter().all(|entry| !entry.observed));
+        assert_eq!(timeline[1].dt, forecast.list[2].dt);
+        assert!(timeline.windows(2).all(|pair| pair[0].dt < pair[1].dt));
+        Ok(())
+    }
+
+    #[test]
+    fn test_wind_percentile() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        assert_eq!(forecast.wind_percentile(Speed::from_mps(0.0)?), Some(0.0));
+        assert_eq!(
+            forecast.wind_percentile(Speed::from_mps(100.0)?),
+            Some(100.0)
+        );
+        let percentile = forecast.wind_percentile(Speed::from_mps(7.2)?).unwrap();
+        assert!(percentile >= WINDY_PERCENTILE, "{}", percentile);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_fields() -> Result<(), Error> {
+        let forecast: WeatherForecast =
+            serde_json::from_str(include_str!("../tests/forecast.json"))?;
+        let entry = &forecast.list[0];
+        assert_eq!(entry.extra["dt_txt"], "2020-01-19 21:00:00");
+        assert!(entry.extra.contains_key("clouds"));
+        assert!(!entry.extra.contains_key("main"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_forecast() -> Result<(), Error> {
+        let forecast = WeatherForecast::from_json(
+            r#"{"cod":"200","cnt":0,"list":[],"city":{"timezone":-18000,"sunrise":1579436159,"sunset":1579470974}}"#,
+        )?;
+        assert!(forecast.list.is_empty());
+        assert!(forecast.get_high_low().is_empty());
+        assert!(forecast.get_hourly_temperatures().is_empty());
+        assert!(forecast.get_temperature_range().is_none());
+        assert!(forecast.get_forecast_summary().days.is_empty());
+        assert!(forecast.wind_percentile(Speed::from_mps(2.0)?).is_none());
+
+        let opts = DisplayOpts::default();
+        let mut buf = Vec::new();
+        forecast.get_forecast(&mut buf, &opts)?;
+        forecast.get_interpolated_hourly(&mut buf, &opts)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            "\nNo forecast available\n\nNo forecast available\n"
+        );
+        Ok(())
     }
 }
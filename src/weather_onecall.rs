@@ -0,0 +1,688 @@
+use anyhow::{format_err, Error};
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, convert::TryFrom, fmt, io::Write, str::FromStr};
+
+use crate::{
+    direction::Direction,
+    display_opts::DisplayOpts,
+    distance::Distance,
+    elevation::Elevation,
+    forecast_summary::{DaySummary, ForecastSummary, TemperatureField},
+    humidity::Humidity,
+    latitude::Latitude,
+    longitude::Longitude,
+    precipitation::Precipitation,
+    pressure::Pressure,
+    speed::Speed,
+    temperature::Temperature,
+    timestamp,
+    timezone::TimeZone,
+    weather_data::{Coord, Rain, Snow, Sys, WeatherCond, WeatherData, WeatherMain, Wind},
+    weather_forecast::{self, CityEntry, ForecastEntry, ForecastMain, WeatherForecast},
+};
+
+/// Sections of the One Call response, any of which can be excluded from the
+/// request
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum OneCallSection {
+    Current,
+    Minutely,
+    Hourly,
+    Daily,
+    Alerts,
+}
+
+impl OneCallSection {
+    pub fn all() -> [Self; 5] {
+        [
+            Self::Current,
+            Self::Minutely,
+            Self::Hourly,
+            Self::Daily,
+            Self::Alerts,
+        ]
+    }
+
+    /// Whether the section is the hourly or daily forecast, the only ones
+    /// requested with `--forecast-only`
+    pub fn is_forecast(self) -> bool {
+        matches!(self, Self::Hourly | Self::Daily)
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Current => "current",
+            Self::Minutely => "minutely",
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Alerts => "alerts",
+        }
+    }
+}
+
+impl fmt::Display for OneCallSection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for OneCallSection {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::all()
+            .iter()
+            .find(|section| section.to_str() == s.trim().to_lowercase())
+            .copied()
+            .ok_or_else(|| format_err!("{} is not a valid One Call section", s))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OneCallCurrent {
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub sunrise: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub sunset: DateTime<Utc>,
+    pub temp: Temperature,
+    pub feels_like: Temperature,
+    pub pressure: Pressure,
+    pub humidity: Humidity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<Distance>,
+    pub wind_speed: Speed,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_deg: Option<Direction>,
+    pub weather: Vec<WeatherCond>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rain: Option<Rain>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow: Option<Snow>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OneCallHourly {
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    pub temp: Temperature,
+    pub feels_like: Temperature,
+    pub pressure: Pressure,
+    pub humidity: Humidity,
+    pub wind_speed: Speed,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_deg: Option<Direction>,
+    pub weather: Vec<WeatherCond>,
+    /// Probability of precipitation (0.0 to 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pop: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rain: Option<Rain>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow: Option<Snow>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DailyTemperature {
+    pub day: Temperature,
+    pub min: Temperature,
+    pub max: Temperature,
+    pub night: Temperature,
+    pub eve: Temperature,
+    pub morn: Temperature,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DailyFeelsLike {
+    pub day: Temperature,
+    pub night: Temperature,
+    pub eve: Temperature,
+    pub morn: Temperature,
+}
+
+impl DailyFeelsLike {
+    fn all(&self) -> [Temperature; 4] {
+        [self.morn, self.day, self.eve, self.night]
+    }
+
+    /// Lowest and highest of the apparent temperatures over the day
+    pub fn low_high(&self) -> (Temperature, Temperature) {
+        let all = self.all();
+        all[1..]
+            .iter()
+            .fold((all[0], all[0]), |(low, high), &temp| {
+                (
+                    if temp < low { temp } else { low },
+                    if temp > high { temp } else { high },
+                )
+            })
+    }
+
+    pub fn average(&self) -> Option<Temperature> {
+        Temperature::average(&self.all())
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OneCallDaily {
+    #[serde(with = "timestamp")]
+    pub dt: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub sunrise: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub sunset: DateTime<Utc>,
+    pub temp: DailyTemperature,
+    pub feels_like: DailyFeelsLike,
+    pub pressure: Pressure,
+    pub humidity: Humidity,
+    pub wind_speed: Speed,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wind_deg: Option<Direction>,
+    pub weather: Vec<WeatherCond>,
+    /// Probability of precipitation (0.0 to 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pop: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rain: Option<Precipitation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snow: Option<Precipitation>,
+}
+
+/// Government weather alert for the location
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct OneCallAlert {
+    pub sender_name: String,
+    /// Name of the alert, e.g. "Winter Storm Warning"
+    pub event: String,
+    #[serde(with = "timestamp")]
+    pub start: DateTime<Utc>,
+    #[serde(with = "timestamp")]
+    pub end: DateTime<Utc>,
+    pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl OneCallAlert {
+    /// Whether `event` contains any of `keywords`, ignoring case.  An empty
+    /// list matches every alert.
+    pub fn matches(&self, keywords: &[String]) -> bool {
+        let event = self.event.to_lowercase();
+        keywords.is_empty()
+            || keywords
+                .iter()
+                .any(|keyword| event.contains(&keyword.trim().to_lowercase()))
+    }
+}
+
+/// Representation of the One Call response, any excluded section will be
+/// `None`
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct WeatherOneCall {
+    pub lat: Latitude,
+    pub lon: Longitude,
+    /// IANA timezone name, e.g. `America/New_York`
+    #[serde(default)]
+    pub timezone: String,
+    pub timezone_offset: TimeZone,
+    /// Elevation of the location, only in some responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<Elevation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<OneCallCurrent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hourly: Option<Vec<OneCallHourly>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub daily: Option<Vec<OneCallDaily>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alerts: Option<Vec<OneCallAlert>>,
+}
+
+impl WeatherOneCall {
+    /// Local time of `dt`, from the IANA `timezone` when it's recognized (so
+    /// that daylight saving time changes are accounted for), otherwise from
+    /// the fixed `timezone_offset`
+    pub fn local_time(&self, dt: DateTime<Utc>) -> DateTime<FixedOffset> {
+        #[cfg(feature = "tz")]
+        {
+            if let Ok(tz) = self.timezone.parse::<chrono_tz::Tz>() {
+                return dt.with_timezone(&tz).fixed_offset();
+            }
+        }
+        let fo: FixedOffset = self.timezone_offset.into();
+        dt.with_timezone(&fo)
+    }
+
+    /// Convert the `current` section into `WeatherData`, so that it can be
+    /// written out using `WeatherData::get_current_conditions`
+    pub fn get_weather_data(&self) -> Option<WeatherData> {
+        self.current.as_ref().map(|current| WeatherData {
+            coord: Coord {
+                lon: self.lon,
+                lat: self.lat,
+            },
+            weather: current.weather.clone(),
+            base: "onecall".into(),
+            main: WeatherMain {
+                temp: current.temp,
+                feels_like: current.feels_like,
+                temp_min: current.temp,
+                temp_max: current.temp,
+                pressure: current.pressure,
+                humidity: current.humidity,
+            },
+            visibility: current.visibility,
+            wind: Wind {
+                speed: current.wind_speed,
+                deg: current.wind_deg,
+            },
+            rain: current.rain.clone(),
+            snow: current.snow.clone(),
+            dt: current.dt,
+            sys: Sys {
+                country: None,
+                sunrise: current.sunrise,
+                sunset: current.sunset,
+            },
+            timezone: TimeZone::try_from(self.local_time(current.dt).offset().local_minus_utc())
+                .unwrap_or(self.timezone_offset),
+            name: "".into(),
+            elevation: self.elevation,
+            extra: HashMap::new(),
+        })
+    }
+
+    /// Convert the `hourly` section, or without it the `daily` one, into a
+    /// `WeatherForecast`, e.g. so that `summary::get_summary` can mention
+    /// the precipitation expected soon
+    pub fn get_weather_forecast(&self) -> Option<WeatherForecast> {
+        let list: Vec<ForecastEntry> = match (&self.hourly, &self.daily) {
+            (Some(hourly), _) => hourly
+                .iter()
+                .map(|entry| ForecastEntry {
+                    dt: entry.dt,
+                    main: ForecastMain {
+                        temp: entry.temp,
+                        feels_like: entry.feels_like,
+                        temp_min: entry.temp,
+                        temp_max: entry.temp,
+                        pressure: entry.pressure,
+                        sea_level: entry.pressure,
+                        grnd_level: entry.pressure,
+                        humidity: entry.humidity,
+                    },
+                    weather: entry.weather.clone(),
+                    wind: Some(Wind {
+                        speed: entry.wind_speed,
+                        deg: entry.wind_deg,
+                    }),
+                    rain: entry.rain.as_ref().map(|rain| weather_forecast::Rain {
+                        three_hour: rain.three_hour.or(rain.one_hour),
+                    }),
+                    snow: entry.snow.as_ref().map(|snow| weather_forecast::Snow {
+                        three_hour: snow.three_hour.or(snow.one_hour),
+                    }),
+                    pop: entry.pop,
+                    extra: HashMap::new(),
+                })
+                .collect(),
+            (None, Some(daily)) => daily
+                .iter()
+                .map(|entry| ForecastEntry {
+                    dt: entry.dt,
+                    main: ForecastMain {
+                        temp: entry.temp.day,
+                        feels_like: entry.feels_like.day,
+                        temp_min: entry.temp.min,
+                        temp_max: entry.temp.max,
+                        pressure: entry.pressure,
+                        sea_level: entry.pressure,
+                        grnd_level: entry.pressure,
+                        humidity: entry.humidity,
+                    },
+                    weather: entry.weather.clone(),
+                    wind: Some(Wind {
+                        speed: entry.wind_speed,
+                        deg: entry.wind_deg,
+                    }),
+                    rain: entry.rain.map(|rain| weather_forecast::Rain {
+                        three_hour: Some(rain),
+                    }),
+                    snow: entry.snow.map(|snow| weather_forecast::Snow {
+                        three_hour: Some(snow),
+                    }),
+                    pop: entry.pop,
+                    extra: HashMap::new(),
+                })
+                .collect(),
+            (None, None) => return None,
+        };
+        let (sunrise, sunset) = match (&self.current, self.daily.as_ref().and_then(|d| d.first())) {
+            (Some(current), _) => (current.sunrise, current.sunset),
+            (None, Some(day)) => (day.sunrise, day.sunset),
+            (None, None) => Default::default(),
+        };
+        let timezone = list
+            .first()
+            .and_then(|entry| {
+                TimeZone::try_from(self.local_time(entry.dt).offset().local_minus_utc()).ok()
+            })
+            .unwrap_or(self.timezone_offset);
+        Some(WeatherForecast {
+            list,
+            city: CityEntry {
+                timezone,
+                sunrise,
+                sunset,
+            },
+        })
+    }
+
+    /// Write out hourly temperatures and conditions
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_onecall::WeatherOneCall;
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/onecall.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data: WeatherOneCall = serde_json::from_str(&buf)?;
+    ///
+    /// let mut buf = Vec::new();
+    /// data.get_hourly(&mut buf, &DisplayOpts::default())?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert!(buf.starts_with("\nHourly Forecast:"), "{}", buf);
+    /// assert!(buf.contains("2020-01-19 13:00 36.2°F / 2.3°C"), "{}", buf);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_hourly<T: Write>(&self, buf: &mut T, opts: &DisplayOpts) -> Result<(), Error> {
+        let hourly = match &self.hourly {
+            Some(hourly) => hourly,
+            None => return Ok(()),
+        };
+        writeln!(buf, "\nHourly Forecast:")?;
+        hourly.iter().try_for_each(|entry| {
+            let (primary, secondary) = opts.temperatures(entry.temp, 1);
+            let temp = format!("{} / {}", primary, secondary);
+            writeln!(
+                buf,
+                "\t{} {} {}",
+                self.local_time(entry.dt).format("%Y-%m-%d %H:%M"),
+                opts.paint_temperature(entry.temp, &format!("{:25}", temp)),
+                entry
+                    .weather
+                    .first()
+                    .map_or("", |cond| cond.description.as_str()),
+            )
+            .map_err(Into::into)
+        })
+    }
+
+    /// Write out the alerts whose event matches one of `keywords` (see
+    /// `OneCallAlert::matches`)
+    pub fn get_alerts<T: Write>(&self, buf: &mut T, keywords: &[String]) -> Result<(), Error> {
+        writeln!(buf, "\nAlerts:")?;
+        let alerts: Vec<_> = self
+            .alerts
+            .iter()
+            .flatten()
+            .filter(|alert| alert.matches(keywords))
+            .collect();
+        if alerts.is_empty() {
+            writeln!(buf, "\tNo alerts")?;
+        }
+        alerts.into_iter().try_for_each(|alert| {
+            writeln!(
+                buf,
+                "\t{} from {} until {} ({})\n\t\t{}",
+                alert.event,
+                self.local_time(alert.start).format("%Y-%m-%d %H:%M"),
+                self.local_time(alert.end).format("%Y-%m-%d %H:%M"),
+                alert.sender_name,
+                alert.description.trim().replace('\n', "\n\t\t"),
+            )
+            .map_err(Error::from)
+        })
+    }
+
+    /// Write out daily high and low temperatures
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_onecall::WeatherOneCall;
+    /// use weather_util_rust::display_opts::DisplayOpts;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/onecall.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data: WeatherOneCall = serde_json::from_str(&buf)?;
+    ///
+    /// let mut buf = Vec::new();
+    /// data.get_daily_forecast(&mut buf, &DisplayOpts::default())?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert!(buf.starts_with("\nForecast:"), "{}", buf);
+    /// assert!(buf.contains("Sun      High: 33.5°F / 0.9°C"), "{}", buf);
+    /// assert!(buf.contains("Rain 0.10 in"), "{}", buf);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_daily_forecast<T: Write>(
+        &self,
+        buf: &mut T,
+        opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        if self.daily.is_none() || opts.forecast_days == Some(0) {
+            return Ok(());
+        }
+        opts.write_forecast_banner(buf)?;
+        let today = self.local_time(opts.now()).date_naive();
+        self.get_forecast_summary_of(opts.forecast_field)
+            .write_days(buf, opts, today)
+    }
+
+    /// Summary of the `daily` section, empty if it was excluded
+    pub fn get_forecast_summary(&self) -> ForecastSummary {
+        self.get_forecast_summary_of(TemperatureField::Temperature)
+    }
+
+    /// Same as `get_forecast_summary`, with the highs, lows and averages
+    /// taken from `field`
+    pub fn get_forecast_summary_of(&self, field: TemperatureField) -> ForecastSummary {
+        let days = self
+            .daily
+            .iter()
+            .flatten()
+            .map(|entry| DaySummary {
+                date: self.local_time(entry.dt).date_naive(),
+                high: match field {
+                    TemperatureField::Temperature => entry.temp.max,
+                    TemperatureField::FeelsLike => entry.feels_like.low_high().1,
+                },
+                low: match field {
+                    TemperatureField::Temperature => entry.temp.min,
+                    TemperatureField::FeelsLike => entry.feels_like.low_high().0,
+                },
+                average: match field {
+                    TemperatureField::Temperature => Temperature::average(&[
+                        entry.temp.morn,
+                        entry.temp.day,
+                        entry.temp.eve,
+                        entry.temp.night,
+                    ]),
+                    TemperatureField::FeelsLike => entry.feels_like.average(),
+                },
+                rain: entry.rain.unwrap_or_default(),
+                snow: entry.snow.unwrap_or_default(),
+                pop: entry.pop,
+                wind_speed: Some(entry.wind_speed),
+                observations: None,
+                condition: entry.weather.first().map(|cond| cond.id),
+            })
+            .collect();
+        ForecastSummary { days }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use chrono::{TimeZone, Utc};
+
+    use crate::{
+        display_opts::DisplayOpts,
+        elevation::Elevation,
+        summary::get_summary,
+        weather_onecall::{OneCallSection, WeatherOneCall},
+    };
+
+    #[test]
+    fn test_onecall_section() -> Result<(), Error> {
+        let section: OneCallSection = "Hourly".parse()?;
+        assert_eq!(section, OneCallSection::Hourly);
+        assert_eq!(section.to_string(), "hourly");
+        assert!("weekly".parse::<OneCallSection>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_elevation() -> Result<(), Error> {
+        let buf = include_str!("../tests/onecall.json").replace(
+            "\"timezone_offset\": -18000,",
+            "\"timezone_offset\": -18000,\n \"elevation\": 1655,",
+        );
+        let onecall: WeatherOneCall = serde_json::from_str(&buf)?;
+        let data = onecall.get_weather_data().unwrap();
+        assert_eq!(data.elevation.map(Elevation::meters), Some(1655.0));
+
+        let onecall: WeatherOneCall = serde_json::from_str(include_str!("../tests/onecall.json"))?;
+        assert!(onecall.get_weather_data().unwrap().elevation.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_history() -> Result<(), Error> {
+        let history: WeatherOneCall = serde_json::from_str(include_str!("../tests/history.json"))?;
+        assert!(history.daily.is_none());
+        assert_eq!(history.hourly.as_ref().map(Vec::len), Some(24));
+
+        let data = history.get_weather_data().unwrap();
+        let mut buf = Vec::new();
+        data.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("Last Updated 2020-01-17 08:00:00 -05:00"),
+            "{}",
+            buf
+        );
+        assert!(buf.contains("Temperature: 29.41°F (-1.44°C)"), "{}", buf);
+        assert!(buf.contains("Conditions: scattered clouds"), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_time() -> Result<(), Error> {
+        let mut onecall: WeatherOneCall =
+            serde_json::from_str(include_str!("../tests/onecall.json"))?;
+        assert_eq!(onecall.timezone, "America/New_York");
+        let hourly = onecall.hourly.as_mut().unwrap();
+        hourly.truncate(2);
+        // Clocks go forward from 2:00 EST to 3:00 EDT on 2020-03-08
+        hourly[0].dt = Utc.with_ymd_and_hms(2020, 3, 8, 6, 0, 0).unwrap();
+        hourly[1].dt = Utc.with_ymd_and_hms(2020, 3, 8, 7, 0, 0).unwrap();
+
+        let mut buf = Vec::new();
+        onecall.get_hourly(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\t2020-03-08 01:00 "), "{}", buf);
+        if cfg!(feature = "tz") {
+            assert!(buf.contains("\t2020-03-08 03:00 "), "{}", buf);
+        } else {
+            assert!(buf.contains("\t2020-03-08 02:00 "), "{}", buf);
+        }
+
+        // An unknown timezone falls back to the fixed offset
+        onecall.timezone = "Nowhere/Atlantis".into();
+        let mut buf = Vec::new();
+        onecall.get_hourly(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("\t2020-03-08 02:00 "), "{}", buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_alerts_filter() -> Result<(), Error> {
+        let mut onecall: WeatherOneCall =
+            serde_json::from_str(include_str!("../tests/onecall.json"))?;
+        let mut buf = Vec::new();
+        onecall.get_alerts(&mut buf, &[])?;
+        assert_eq!(String::from_utf8(buf)?, "\nAlerts:\n\tNo alerts\n");
+
+        onecall.alerts = Some(serde_json::from_str(
+            r#"[
+                {"sender_name": "NWS New York City", "event": "Winter Storm Warning",
+                 "start": 1579449600, "end": 1579536000,
+                 "description": "Heavy snow expected.\nTravel could be very difficult.",
+                 "tags": ["Snow/Ice"]},
+                {"sender_name": "NWS New York City", "event": "Wind Advisory",
+                 "start": 1579449600, "end": 1579478400,
+                 "description": "Gusts up to 50 mph."}
+            ]"#,
+        )?);
+
+        let mut buf = Vec::new();
+        onecall.get_alerts(&mut buf, &[])?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Winter Storm Warning"), "{}", buf);
+        assert!(buf.contains("Wind Advisory"), "{}", buf);
+
+        let mut buf = Vec::new();
+        onecall.get_alerts(&mut buf, &["WARNING".into(), "tornado".into()])?;
+        let buf = String::from_utf8(buf)?;
+        assert_eq!(
+            buf,
+            "\nAlerts:\n\tWinter Storm Warning from 2020-01-19 11:00 until 2020-01-20 11:00 (NWS \
+             New York City)\n\t\tHeavy snow expected.\n\t\tTravel could be very difficult.\n"
+        );
+
+        let mut buf = Vec::new();
+        onecall.get_alerts(&mut buf, &["flood".into()])?;
+        assert!(String::from_utf8(buf)?.ends_with("\tNo alerts\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_weather_forecast() -> Result<(), Error> {
+        let mut onecall: WeatherOneCall =
+            serde_json::from_str(include_str!("../tests/onecall.json"))?;
+        let data = onecall.get_weather_data().unwrap();
+        let forecast = onecall.get_weather_forecast().unwrap();
+        assert_eq!(forecast.list.len(), 48);
+        assert_eq!(forecast.city.timezone, onecall.timezone_offset);
+
+        // the likeliest precipitation within the next hours is mentioned
+        let summary = get_summary(&data, Some(&forecast), &DisplayOpts::default());
+        assert!(
+            summary.ends_with(" and a 91% chance of rain this evening."),
+            "{}",
+            summary
+        );
+
+        // without the hourly section a day is an entry
+        onecall.hourly = None;
+        let forecast = onecall.get_weather_forecast().unwrap();
+        assert_eq!(forecast.list.len(), onecall.daily.as_ref().unwrap().len());
+
+        onecall.daily = None;
+        assert!(onecall.get_weather_forecast().is_none());
+        Ok(())
+    }
+}
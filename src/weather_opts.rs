@@ -1,23 +1,59 @@
 use anyhow::{format_err, Error};
-use futures::future::join;
-use reqwest::Url;
+use chrono::{DateTime, Utc};
+use futures::future::{join, try_join};
+use log::LevelFilter;
 use serde::{Deserialize, Serialize};
-use std::io::stdout;
+use std::{
+    convert::TryFrom,
+    fs::{self, File},
+    io::{stderr, stdout, BufWriter, Write},
+    path::{Path, PathBuf},
+};
 use structopt::StructOpt;
+use url::Url;
 
+#[cfg(feature = "tui")]
+use crate::tui::{self, DEFAULT_TUI_REFRESH_SECONDS};
 use crate::{
+    advice::get_advice,
+    conditions::write_conditions,
     config::Config,
+    direction::CompassPoints,
+    disk_cache::DiskCache,
+    display_opts::DisplayOpts,
+    elevation::Elevation,
+    forecast_summary::{ForecastSummary, TemperatureField},
+    geocoder::{Geocoders, OpenWeatherMapGeocoder, PlusCodeGeocoder},
+    ip_location::{get_ip_location, DEFAULT_IP_LOCATION_URL},
+    json_output::JsonOutput,
+    last_run::LastRun,
     latitude::Latitude,
+    locale::Locale,
+    location_presets::LocationPresets,
     longitude::Longitude,
+    output_format::{CsvFormat, JsonFormat, OneLineFormat, OutputFormat, TextFormat},
+    precipitation::PrecipitationUnit,
+    prometheus::write_metrics,
+    self_check::{check_api_key, check_cache_dir, check_location, CheckReport},
+    speed::SpeedUnit,
+    summary::get_summary,
+    temperature::Temperature,
+    theme::Theme,
+    units::Units,
+    watch::{ChangeDetector, ConditionsChanged, DEFAULT_DEBOUNCE, MIN_WATCH_SECONDS},
     weather_api::{WeatherApi, WeatherLocation},
+    weather_box::BoundingBox,
+    weather_daily_forecast::WeatherDailyForecast,
     weather_data::WeatherData,
-    weather_forecast::WeatherForecast,
+    weather_forecast::{WeatherForecast, FORECAST_DAYS},
+    weather_onecall::OneCallSection,
+    week_export::WeekExport,
 };
 
 /// Utility to retreive and format weather data from openweathermap.org
 ///
 /// Please specify one of `zipcode(country_code)`, `city_name`, or `lat` and
-/// `lon`.
+/// `lon`, or pass a zipcode or city name as the `LOCATION` argument.
 #[derive(StructOpt, Default, Serialize, Deserialize)]
 pub struct WeatherOpts {
     /// Zipcode (optional)
@@ -29,20 +65,338 @@ pub struct WeatherOpts {
     /// City Name (optional)
     #[structopt(long)]
     city_name: Option<String>,
+    /// Zipcode or city name (optional), a location made up of digits only
+    /// is always taken as a zipcode (one the api doesn't know is not retried
+    /// as a city name, use --city-name for that), anything else as a city
+    /// name
+    #[serde(default)]
+    #[structopt(name = "LOCATION")]
+    location: Option<String>,
+    /// Named location from the PRESETS config option (optional)
+    #[structopt(long)]
+    preset: Option<String>,
+    /// ICAO airport code (optional), e.g. KJFK, looked up in the bundled
+    /// airports and the AIRPORTS config option
+    #[structopt(long)]
+    icao: Option<String>,
+    /// IATA airport code (optional), e.g. JFK, looked up like `icao`
+    #[structopt(long)]
+    iata: Option<String>,
     /// Latitude (must also specify Longitude)
     #[structopt(long)]
     lat: Option<Latitude>,
     /// Longitude (must also specify Latitude)
     #[structopt(long)]
     lon: Option<Longitude>,
+    /// Approximate the location from your public IP address, which is sent
+    /// to the IP_LOCATION_URL geolocation service.  Falls back to the given
+    /// or default location if that fails
+    #[serde(default)]
+    #[structopt(long)]
+    here: bool,
     /// Api key (optional but either this or API_KEY environment variable must
-    /// exist)
+    /// exist), takes precedence over both.  Other users can see it in the
+    /// process list, prefer API_KEY or the config file
     #[structopt(short = "k", long)]
     api_key: Option<String>,
     /// Print forecast
     #[serde(default)]
     #[structopt(short, long)]
     forecast: bool,
+    /// Print only the forecast, without fetching the current conditions.
+    /// With `onecall` the current, minutely and alerts sections are
+    /// excluded from the request.  Implies `forecast`
+    #[serde(default)]
+    #[structopt(long)]
+    forecast_only: bool,
+    /// Number of days to forecast, more than 5 uses the daily forecast api
+    /// (at most 16 days).  Implies `forecast`
+    #[structopt(long)]
+    days: Option<usize>,
+    /// Print at most this many days of the forecast (0 leaves it out).  Only
+    /// those days are requested, so the week ahead range, best day and trend
+    /// cover just them, unless the full forecast is needed (e.g. `json`)
+    #[structopt(long)]
+    forecast_days: Option<usize>,
+    /// Only print the forecast days where rain or snow is likely (at least
+    /// a 30% chance) or more than 0.5 mm is expected
+    #[serde(default)]
+    #[structopt(long)]
+    rain_days_only: bool,
+    /// Follow the local times (last updated, sunrise and sunset) with the
+    /// same time in UTC
+    #[serde(default)]
+    #[structopt(long)]
+    utc: bool,
+    /// Print the aviation flight category (VFR, MVFR, IFR or LIFR) from
+    /// the visibility and the ceiling estimated from the cloud cover and
+    /// dew point
+    #[serde(default)]
+    #[structopt(long)]
+    aviation: bool,
+    /// Heading printed before the forecast days instead of "Forecast"
+    #[structopt(long)]
+    forecast_heading: Option<String>,
+    /// Leave out the heading printed before the forecast days
+    #[serde(default)]
+    #[structopt(long)]
+    no_forecast_banner: bool,
+    /// Don't note forecast days past the default 5 day window as low
+    /// confidence
+    #[serde(default)]
+    #[structopt(long)]
+    no_confidence_note: bool,
+    /// Name the forecast weekdays in this locale, e.g. fr or fr_FR (de, en,
+    /// es, fr, it, nl and pt are supported), others get the English names
+    /// (followed by the date outside the forecast table)
+    #[structopt(long)]
+    locale: Option<Locale>,
+    /// Group forecast days by week, useful with a long `days`
+    #[serde(default)]
+    #[structopt(long)]
+    compact_forecast: bool,
+    /// Print hourly temperatures interpolated from the 3 hour forecast
+    #[serde(default)]
+    #[structopt(long)]
+    interpolate: bool,
+    /// Use the One Call api (requires `lat` and `lon`)
+    #[serde(default)]
+    #[structopt(long)]
+    onecall: bool,
+    /// Print hourly forecast (requires `onecall`)
+    #[serde(default)]
+    #[structopt(long)]
+    hourly: bool,
+    /// Comma separated list of One Call sections to exclude (current,
+    /// minutely, hourly, daily, alerts), by default any section not needed
+    /// for the output is excluded
+    #[structopt(long, use_delimiter = true)]
+    exclude: Option<Vec<OneCallSection>>,
+    /// Print weather alerts (requires `onecall`)
+    #[serde(default)]
+    #[structopt(long)]
+    alerts: bool,
+    /// Comma separated list of keywords, only alerts whose event contains
+    /// one of them (ignoring case) are printed, e.g. `warning,watch`.
+    /// Implies `alerts`
+    #[structopt(long, use_delimiter = true)]
+    alerts_filter: Option<Vec<String>>,
+    /// Color temperatures, bands can be set with the TEMPERATURE_COLORS
+    /// config option
+    #[serde(default)]
+    #[structopt(long)]
+    color: bool,
+    /// Units for display (imperial, metric), defaults to the WEATHER_UNITS
+    /// config option or imperial
+    #[structopt(long)]
+    units: Option<Units>,
+    /// Unit for wind speed (mph, kmh, ms, knots), defaults to mph for
+    /// imperial and kmh for metric units
+    #[structopt(long)]
+    wind_unit: Option<SpeedUnit>,
+    /// Forecast the daily highs and lows of the apparent (feels like)
+    /// temperature instead of the temperature
+    #[serde(default)]
+    #[structopt(long)]
+    feels_like: bool,
+    /// Label wind directions with the nearest point of an 8, 16 or 32
+    /// point compass
+    #[structopt(long)]
+    compass_points: Option<CompassPoints>,
+    /// Unit for rain and snow (mm, in, cm), the default is in for imperial
+    /// and mm for metric units
+    #[structopt(long)]
+    precip_unit: Option<PrecipitationUnit>,
+    /// Elevation in meters, the pressure is also printed adjusted from sea
+    /// level to this elevation
+    #[structopt(long)]
+    elevation: Option<Elevation>,
+    /// Decimal places for temperatures, by default 2 for the current
+    /// conditions and 1 for forecasts
+    #[structopt(long)]
+    precision: Option<usize>,
+    /// Decimal places for wind speeds (default 1)
+    #[structopt(long)]
+    wind_precision: Option<usize>,
+    /// Decimal places coordinates are printed with in headers and the JSON
+    /// output (default 4), requests always use the full precision
+    #[structopt(long)]
+    coord_precision: Option<usize>,
+    /// Decimal places for pressures in hPa (default 0)
+    #[structopt(long)]
+    pressure_precision: Option<usize>,
+    /// Write temperatures as " F"/" C" rather than "°F"/"°C"
+    #[serde(default)]
+    #[structopt(long)]
+    ascii: bool,
+    /// Output preset (minimal, detailed, ascii, nerd), other flags take
+    /// precedence over the preset
+    #[structopt(long)]
+    theme: Option<Theme>,
+    /// Print additional details, e.g. the source of the data and the api
+    /// key each request uses
+    #[serde(default)]
+    #[structopt(short, long)]
+    verbose: bool,
+    /// Don't print the location name or coordinates
+    #[serde(default)]
+    #[structopt(long)]
+    no_location_name: bool,
+    /// Only use previously cached responses, never make a request
+    #[serde(default)]
+    #[structopt(long)]
+    offline: bool,
+    /// Store the responses in the cache directory for later use with
+    /// `--offline`, always the case if CACHE_DIR is configured
+    #[serde(default)]
+    #[structopt(long)]
+    cache: bool,
+    /// Print output as JSON
+    #[serde(default)]
+    #[structopt(long)]
+    json: bool,
+    /// Write the JSON output to this file instead, the usual text is still
+    /// printed
+    #[structopt(long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    /// Render the current conditions and forecast saved by an earlier
+    /// `json` run instead of requesting them, in any of the output formats
+    #[structopt(long, parse(from_os_str))]
+    from_json: Option<PathBuf>,
+    /// Also write the daily summary of the forecast to this file, as JSON
+    /// meant for dashboards
+    #[structopt(long, parse(from_os_str))]
+    export: Option<PathBuf>,
+    /// Request the current conditions as XML rather than JSON, for proxies
+    /// that mangle JSON responses
+    #[cfg(feature = "xml")]
+    #[serde(default)]
+    #[structopt(long)]
+    xml: bool,
+    /// Print the current conditions as Prometheus metrics
+    #[serde(default)]
+    #[structopt(long)]
+    prometheus: bool,
+    /// Print the forecast conditions nearest to this time, e.g.
+    /// 2020-01-21T12:00:00Z (within the next 5 days)
+    #[structopt(long)]
+    at: Option<DateTime<Utc>>,
+    /// Print historical conditions at this time, e.g. 2020-01-17T13:00:00Z
+    /// (requires `lat` and `lon`, at most 5 days in the past)
+    #[structopt(long)]
+    date: Option<DateTime<Utc>>,
+    /// List the conditions at every station in the box
+    /// "lon_left,lat_bottom,lon_right,lat_top,zoom" instead of a single
+    /// location
+    #[structopt(long)]
+    bbox: Option<BoundingBox>,
+    /// List the cities matching this name, e.g. London or London,GB, with
+    /// their country, coordinates and current temperature instead of the
+    /// conditions at a single location
+    #[structopt(long)]
+    find: Option<String>,
+    /// Semicolon separated list of zipcodes or city names, e.g.
+    /// `11106;London,GB`, print the current conditions at each of them
+    /// instead of a single location
+    #[serde(default)]
+    #[structopt(long, use_delimiter = true, value_delimiter = ";")]
+    locations: Vec<String>,
+    /// Most requests made at once for `locations`
+    #[structopt(long)]
+    concurrency: Option<usize>,
+    /// Poll the current conditions every this many seconds (at least 10),
+    /// reporting when the main condition changes (e.g. it starts raining)
+    /// or the temperature crosses `threshold`
+    #[structopt(long)]
+    watch: Option<u64>,
+    /// Show the current conditions, forecast and a temperature graph in an
+    /// interactive terminal interface, refreshed every `watch` seconds (10
+    /// minutes by default, at least 10 seconds) or on r, q quits
+    #[cfg(feature = "tui")]
+    #[serde(default)]
+    #[structopt(long)]
+    tui: bool,
+    /// Temperature (in the primary units) to report crossing in `watch`
+    /// mode
+    #[structopt(long)]
+    threshold: Option<f64>,
+    /// Consecutive polls a change has to be seen on before it is reported
+    /// in `watch` mode, defaults to 2
+    #[structopt(long)]
+    debounce: Option<usize>,
+    /// Shell command run on every change reported in `watch` mode, with the
+    /// conditions in WEATHER_EVENT, WEATHER_LOCATION, WEATHER_CONDITION,
+    /// WEATHER_PREVIOUS_CONDITION, WEATHER_DESCRIPTION,
+    /// WEATHER_TEMPERATURE, WEATHER_HUMIDITY and WEATHER_WIND_SPEED
+    #[structopt(long)]
+    on_change: Option<String>,
+    /// Exit with status 3 on the first change reported in `watch` mode
+    #[serde(default)]
+    #[structopt(long)]
+    exit_on_change: bool,
+    /// Annotate the current conditions with the changes since the previous
+    /// run with `diff` for the same location
+    #[serde(default)]
+    #[structopt(long)]
+    diff: bool,
+    /// Print the current conditions and forecast as CSV, a row per forecast
+    /// slot
+    #[serde(default)]
+    #[structopt(long)]
+    csv: bool,
+    /// Print the current conditions on a single line, e.g. for status bars
+    #[serde(default)]
+    #[structopt(long)]
+    oneline: bool,
+    /// Print a one sentence summary of the conditions
+    #[serde(default)]
+    #[structopt(long)]
+    summary: bool,
+    /// Only print today's sunrise
+    #[serde(default)]
+    #[structopt(long)]
+    sunrise_only: bool,
+    /// Only print today's sunset
+    #[serde(default)]
+    #[structopt(long)]
+    sunset_only: bool,
+    /// Print the golden hour after sunrise and before sunset, on its own or
+    /// with `sunrise-only` / `sunset-only`
+    #[serde(default)]
+    #[structopt(long)]
+    golden_hour: bool,
+    /// Suggest what to wear
+    #[serde(default)]
+    #[structopt(long)]
+    advice: bool,
+    /// Print the request urls (with the api key masked) instead of making
+    /// the requests
+    #[serde(default)]
+    #[structopt(long)]
+    dry_run: bool,
+    /// Report any response fields that are dropped when parsing, to spot
+    /// additions to the api
+    #[serde(default)]
+    #[structopt(long)]
+    round_trip_check: bool,
+    /// Fail when a response has fields that are dropped when parsing,
+    /// instead of ignoring them
+    #[serde(default)]
+    #[structopt(long)]
+    strict: bool,
+    /// Validate the configuration (api key, cache directory and default
+    /// location), exiting with an error if any check fails
+    #[serde(default)]
+    #[structopt(long)]
+    check: bool,
+    /// List the supported condition codes with their names and icons
+    #[serde(default)]
+    #[structopt(long, hidden = true)]
+    list_conditions: bool,
+    /// List the presets in the PRESETS config option
+    #[serde(default)]
+    #[structopt(long)]
+    list_presets: bool,
 }
 
 macro_rules! set_default {
@@ -55,26 +409,82 @@ impl WeatherOpts {
     /// Parse options from stdin, requires `Config` instance.
     pub async fn parse_opts(config: &Config) -> Result<(), Error> {
         let mut opts = Self::from_args();
+        if opts.verbose {
+            log::set_max_level(LevelFilter::Debug);
+        }
+        if let Some(warning) = opts.numeric_city_name_warning() {
+            eprintln!("{}", warning);
+        }
+        if let Some(warning) = opts.api_key_warning() {
+            eprintln!("{}", warning);
+        }
+        opts.apply_preset(&config.presets.clone().unwrap_or_default())?;
+        opts.apply_airport(config)?;
+        opts.apply_location_arg();
         opts.apply_defaults(config);
         opts.run_opts(config).await?;
         Ok(())
     }
 
     fn get_api(&self, config: &Config) -> Result<WeatherApi, Error> {
-        let api_key = self
-            .api_key
-            .as_deref()
-            .ok_or_else(|| format_err!(Self::api_help_msg()))?;
+        let mut api_keys: Vec<&str> = self.api_key.iter().map(String::as_str).collect();
+        for api_key in config.api_keys.iter().flatten() {
+            if !api_keys.contains(&api_key.as_str()) {
+                api_keys.push(api_key);
+            }
+        }
+        if api_keys.is_empty() {
+            return Err(format_err!(Self::api_help_msg()));
+        }
         let api_endpoint = config
             .api_endpoint
             .as_deref()
             .unwrap_or("api.openweathermap.org");
         let api_path = config.api_path.as_deref().unwrap_or("data/2.5/");
-        Ok(WeatherApi::new(api_key, api_endpoint, api_path))
+        let mut api = WeatherApi::new(api_keys[0], api_endpoint, api_path)
+            .with_keys(&api_keys)
+            .with_offline(self.offline)
+            .with_round_trip_check(self.round_trip_check)
+            .with_strict(self.strict);
+        if let Some(concurrency) = self.concurrency {
+            api = api.with_concurrency(concurrency);
+        }
+        if let Some(days) = self.forecast_window_days() {
+            api = api.with_forecast_days(days);
+        }
+        #[cfg(feature = "xml")]
+        {
+            api = api.with_xml(self.xml);
+        }
+        if let Some(directory) = self.get_cache_dir(config) {
+            api = api.with_disk_cache(DiskCache::new(&directory));
+        }
+        Ok(api)
+    }
+
+    /// Directory responses are cached in (and read from when offline),
+    /// none unless one is configured or `cache` or `offline` is given
+    fn get_cache_dir(&self, config: &Config) -> Option<PathBuf> {
+        match &config.cache_dir {
+            Some(directory) => Some(directory.clone()),
+            None if self.cache || self.offline => DiskCache::default_directory(),
+            None => None,
+        }
     }
 
     /// Extract options from `WeatherOpts` and apply to `WeatherApi`
     pub fn get_location(&self) -> Result<WeatherLocation, Error> {
+        if let Some(loc) = self.location_from_opts() {
+            return Ok(loc);
+        }
+        Self::clap().print_help()?;
+        Err(format_err!(
+            "\n\nERROR: You must specify at least one option"
+        ))
+    }
+
+    /// Location given by the options (or config), if any
+    fn location_from_opts(&self) -> Option<WeatherLocation> {
         let loc = if let Some(zipcode) = self.zipcode {
             if let Some(country_code) = &self.country_code {
                 WeatherLocation::from_zipcode_country_code(zipcode, country_code)
@@ -83,68 +493,1882 @@ impl WeatherOpts {
             }
         } else if let Some(city_name) = &self.city_name {
             WeatherLocation::from_city_name(city_name)
-        } else if self.lat.is_some() && self.lon.is_some() {
-            let lat = self.lat.unwrap();
-            let lon = self.lon.unwrap();
+        } else if let (Some(lat), Some(lon)) = (self.lat, self.lon) {
             WeatherLocation::from_lat_lon(lat, lon)
         } else {
-            Self::clap().print_help()?;
-            return Err(format_err!(
-                "\n\nERROR: You must specify at least one option"
-            ));
+            return None;
         };
-        Ok(loc)
+        Some(loc)
+    }
+
+    /// Location from the IP address with `here`, otherwise (or if that
+    /// fails) the location from `get_location`.  A `city_name` accepted by
+    /// one of the `geocoders` is resolved to its coordinates first.
+    async fn resolve_location(
+        &self,
+        ip_location_url: &str,
+        geocoders: &Geocoders,
+    ) -> Result<WeatherLocation, Error> {
+        if self.here && self.offline {
+            eprintln!(
+                "Can't find the location from the IP address offline, using the default location"
+            );
+        } else if self.here && !self.dry_run {
+            // with `dry_run` the request is only written out by `write_dry_run`
+            match get_ip_location(ip_location_url).await {
+                Ok(loc) => return Ok(loc),
+                Err(e) => eprintln!(
+                    "Failed to find location from IP address ({}), using the default location",
+                    e
+                ),
+            }
+        }
+        if let Some(city_name) = self.city_name.as_ref().filter(|_| self.zipcode.is_none()) {
+            if let Some(coord) = geocoders.resolve(city_name).await? {
+                return Ok(WeatherLocation::from_lat_lon(coord.lat, coord.lon));
+            }
+        }
+        self.get_location()
+    }
+
+    /// Plus Codes are always decoded, other place names are only geocoded
+    /// when the request needs coordinates (One Call and history), otherwise
+    /// the api looks them up itself
+    fn get_geocoders(&self, api: &WeatherApi) -> Geocoders {
+        let geocoders = Geocoders::new().register(PlusCodeGeocoder);
+        if (self.onecall || self.date.is_some()) && !self.dry_run {
+            geocoders.register(OpenWeatherMapGeocoder::new(api.clone()))
+        } else {
+            geocoders
+        }
+    }
+
+    /// One Call sections to leave out of the request, either those passed
+    /// as `exclude` or any not required to print the requested output.
+    /// With `forecast_only` everything but the forecast sections is left out
+    /// in either case.
+    fn get_exclude(&self) -> Vec<OneCallSection> {
+        OneCallSection::all()
+            .iter()
+            .copied()
+            .filter(|section| {
+                if self.forecast_only && !section.is_forecast() {
+                    return true;
+                }
+                if let Some(exclude) = &self.exclude {
+                    return exclude.contains(section);
+                }
+                match section {
+                    OneCallSection::Current => false,
+                    OneCallSection::Minutely => true,
+                    OneCallSection::Alerts => !self.show_alerts(),
+                    // the summary mentions the precipitation of the next hours
+                    OneCallSection::Hourly => !(self.hourly || self.summary),
+                    OneCallSection::Daily => !(self.show_forecast() || self.export.is_some()),
+                }
+            })
+            .collect()
+    }
+
+    fn show_forecast(&self) -> bool {
+        self.forecast || self.forecast_only || self.feels_like || self.days.is_some()
+    }
+
+    /// Number of days when they're more than the 3 hour forecast covers
+    fn daily_forecast_days(&self) -> Option<usize> {
+        self.days.filter(|days| *days > FORECAST_DAYS)
+    }
+
+    /// Days of the 3 hour forecast that are printed, when that is fewer
+    /// than it covers.  Anything else using the forecast (the summary,
+    /// advice, export, JSON and CSV output, metrics, interpolated
+    /// temperatures and `at`) gets all of it.
+    fn forecast_window_days(&self) -> Option<usize> {
+        if self.summary
+            || self.advice
+            || self.json
+            || self.csv
+            || self.output.is_some()
+            || self.export.is_some()
+            || self.prometheus
+            || self.interpolate
+            || self.at.is_some()
+        {
+            return None;
+        }
+        self.days
+            .into_iter()
+            .chain(self.forecast_days)
+            .min()
+            .filter(|days| *days < FORECAST_DAYS)
+    }
+
+    /// Which of the sunrise and sunset to print, if only those are requested
+    fn sun_times_only(&self) -> Option<(bool, bool)> {
+        match (self.sunrise_only, self.sunset_only) {
+            (false, false) if self.golden_hour => Some((true, true)),
+            (false, false) => None,
+            selected => Some(selected),
+        }
+    }
+
+    fn show_alerts(&self) -> bool {
+        self.alerts || self.alerts_filter.is_some()
+    }
+
+    fn get_display_opts(&self, config: &Config) -> DisplayOpts {
+        let theme = self.theme.map(Theme::defaults).unwrap_or_default();
+        let units = self.units.unwrap_or_else(|| config.get_units());
+        let mut opts = DisplayOpts::new()
+            .with_units(units)
+            .with_wind_unit(self.wind_unit.unwrap_or_else(|| units.wind_unit()))
+            .with_ascii(self.ascii || theme.ascii)
+            .with_verbose(self.verbose || theme.verbose)
+            .with_hide_location(self.no_location_name || theme.hide_location)
+            .with_compact_forecast(self.compact_forecast || theme.compact_forecast)
+            .with_rain_days_only(self.rain_days_only)
+            .with_show_utc(self.utc)
+            .with_aviation(self.aviation)
+            .with_hide_forecast_banner(self.no_forecast_banner)
+            .with_hide_confidence(self.no_confidence_note);
+        if self.feels_like {
+            opts = opts.with_forecast_field(TemperatureField::FeelsLike);
+        }
+        if let Some(compass_points) = self.compass_points {
+            opts = opts.with_compass_points(compass_points);
+        }
+        if let Some(precip_unit) = self.precip_unit {
+            opts = opts.with_precipitation_unit(precip_unit);
+        }
+        if let Some(elevation) = self.elevation {
+            opts = opts.with_elevation(elevation);
+        }
+        if let Some(icons) = theme.icons {
+            opts = opts.with_icons(icons);
+        }
+        if let Some(precision) = self.precision.or(theme.precision) {
+            opts = opts.with_precision(precision);
+        }
+        if let Some(wind_precision) = self.wind_precision {
+            opts = opts.with_wind_precision(wind_precision);
+        }
+        if let Some(pressure_precision) = self.pressure_precision {
+            opts = opts.with_pressure_precision(pressure_precision);
+        }
+        if let Some(coord_precision) = self.coord_precision {
+            opts = opts.with_coord_precision(coord_precision);
+        }
+        if let Some(forecast_days) = self.forecast_days {
+            opts = opts.with_forecast_days(forecast_days);
+        }
+        if let Some(forecast_heading) = &self.forecast_heading {
+            opts = opts.with_forecast_heading(forecast_heading);
+        }
+        if let Some(locale) = &self.locale {
+            opts = opts.with_locale(locale.clone());
+        }
+        if self.color || theme.color {
+            opts =
+                opts.with_temperature_colors(config.temperature_colors.clone().unwrap_or_default());
+        }
+        opts
     }
 
     async fn run_opts(&self, config: &Config) -> Result<(), Error> {
+        if self.list_conditions {
+            return write_conditions(&mut stdout().lock());
+        }
+        if self.list_presets {
+            return config
+                .presets
+                .clone()
+                .unwrap_or_default()
+                .write_presets(&mut stdout().lock());
+        }
+        if self.check {
+            let report = self.run_check(config).await;
+            report.write_report(&mut stdout().lock())?;
+            if !report.passed() {
+                return Err(format_err!(
+                    "{} of {} checks failed",
+                    report.failures(),
+                    report.checks.len()
+                ));
+            }
+            return Ok(());
+        }
+        if let Some(path) = &self.from_json {
+            return self.render_saved(path, self.get_display_opts(config), &mut stdout().lock());
+        }
         let api = self.get_api(config)?;
-        let loc = self.get_location()?;
+        if self.verbose {
+            eprintln!("Using {:?}", api);
+        }
+        if let Some(bbox) = &self.bbox {
+            if self.dry_run {
+                writeln!(stdout().lock(), "GET {}", api.get_weather_box_url(bbox)?)?;
+                return Ok(());
+            }
+            let data = api.get_weather_box(bbox).await?;
+            return data.get_conditions(&mut stdout().lock(), &self.get_display_opts(config));
+        }
+        if let Some(query) = &self.find {
+            if self.dry_run {
+                writeln!(stdout().lock(), "GET {}", api.get_weather_find_url(query)?)?;
+                return Ok(());
+            }
+            let data = api.get_weather_find(query).await?;
+            return data.get_candidates(&mut stdout().lock(), &self.get_display_opts(config));
+        }
+        if !self.locations.is_empty() {
+            return self
+                .run_locations(&api, self.get_display_opts(config), &mut stdout().lock())
+                .await;
+        }
+        let ip_location_url = config
+            .ip_location_url
+            .as_deref()
+            .unwrap_or(DEFAULT_IP_LOCATION_URL);
+        let loc = self
+            .resolve_location(ip_location_url, &self.get_geocoders(&api))
+            .await?;
+        if self.dry_run {
+            return self.write_dry_run(&api, ip_location_url, &loc, &mut stdout().lock());
+        }
+        let display_opts = self.get_display_opts(config);
 
-        let data = api.get_weather_data(&loc);
-        let (data, forecast) = if self.forecast {
-            let forecast = api.get_weather_forecast(&loc);
-            let (data, forecast) = join(data, forecast).await;
-            (data?, Some(forecast?))
-        } else {
-            (data.await?, None)
-        };
+        #[cfg(feature = "tui")]
+        if self.tui {
+            let interval = self
+                .get_watch_interval()?
+                .unwrap_or(DEFAULT_TUI_REFRESH_SECONDS);
+            return tui::run(
+                &api,
+                &loc,
+                &display_opts,
+                std::time::Duration::from_secs(interval),
+            )
+            .await;
+        }
+        if let Some(interval) = self.get_watch_interval()? {
+            return self
+                .run_watch(&api, &loc, config, interval, &display_opts)
+                .await;
+        }
+        if let Some((sunrise, sunset)) = self.sun_times_only() {
+            let data = api.get_weather_data(&loc).await?;
+            return data.get_sun_times(&mut stdout().lock(), sunrise, sunset, self.golden_hour);
+        }
+        if let Some(dt) = self.at {
+            let (mut data, forecast) =
+                try_join(api.get_weather_data(&loc), api.get_weather_forecast(&loc)).await?;
+            if let Some(names) = &config.location_names {
+                names.apply(&mut data);
+            }
+            return forecast
+                .get_conditions_at(dt, &data)?
+                .get_forecast_conditions(&mut stdout().lock(), &display_opts);
+        }
+        if let Some(dt) = self.date {
+            return self.run_history(&api, &loc, dt, &display_opts).await;
+        }
+        if self.onecall {
+            return self.run_onecall(&api, &loc, &display_opts).await;
+        }
+        if let Some(days) = self.daily_forecast_days() {
+            return self
+                .run_daily_forecast(&api, &loc, days, &display_opts)
+                .await;
+        }
+
+        let (mut data, mut forecast) = self
+            .get_current_and_forecast(&api, &loc, &mut stderr().lock())
+            .await?;
+        if let (Some(data), Some(names)) = (data.as_mut(), &config.location_names) {
+            names.apply(data);
+        }
+        if let (Some(forecast), Some(days)) = (forecast.as_mut(), self.days) {
+            forecast.retain_days(days);
+        }
+        if let (Some(path), Some(forecast)) = (&self.export, &forecast) {
+            Self::write_export(path, &forecast.get_forecast_summary())?;
+        }
 
         let stdout = stdout();
-        data.get_current_conditions(&mut stdout.lock())?;
-        if let Some(forecast) = forecast {
-            forecast.get_forecast(&mut stdout.lock())?;
+        if self.prometheus {
+            return match &data {
+                Some(data) => write_metrics(&mut stdout.lock(), data, forecast.as_ref()),
+                None => Err(format_err!("current conditions unavailable")),
+            };
         }
-        Ok(())
+        let display_opts = match (&data, LastRun::default_path()) {
+            (Some(data), Some(path)) if self.diff => Self::apply_diff(&path, data, display_opts),
+            _ => display_opts,
+        };
+        self.render_current_and_forecast(
+            data.as_ref(),
+            forecast.as_ref(),
+            display_opts,
+            &mut stdout.lock(),
+        )
     }
 
-    fn apply_defaults(&mut self, config: &Config) {
-        if self.api_key.is_none() {
-            set_default!(self, config, api_key);
+    /// Write the JSON to the `output` file if there is one, and the current
+    /// conditions and forecast in the requested format to `buf`
+    fn render_current_and_forecast<T: Write>(
+        &self,
+        data: Option<&WeatherData>,
+        forecast: Option<&WeatherForecast>,
+        display_opts: DisplayOpts,
+        buf: &mut T,
+    ) -> Result<(), Error> {
+        if let Some(path) = &self.output {
+            let mut f = BufWriter::new(File::create(path)?);
+            self.get_json_format(&display_opts)
+                .render(data, forecast, &mut f)?;
+            f.flush()?;
         }
-        if self.zipcode.is_none()
-            && self.country_code.is_none()
-            && self.city_name.is_none()
-            && (self.lat.is_none() || self.lon.is_none())
+        self.get_output_format(display_opts, self.show_forecast())
+            .render(data, forecast, buf)
+    }
+
+    /// Use the run stored at `path` as the baseline of `opts` and replace it
+    /// with `data`, problems with the state file only disable the changes
+    fn apply_diff(path: &Path, data: &WeatherData, opts: DisplayOpts) -> DisplayOpts {
+        let baseline = LastRun::read(path).unwrap_or_else(|e| {
+            eprintln!("Ignoring the previous run: {}", e);
+            None
+        });
+        if let Err(e) = LastRun::from(data).write(path) {
+            eprintln!("Failed to store this run in {}: {}", path.display(), e);
+        }
+        match baseline {
+            Some(baseline) => opts.with_baseline(baseline),
+            None => opts,
+        }
+    }
+
+    /// Checks of `--check`: the api key, the cache directory and the
+    /// default location, the requests are skipped without a usable key
+    async fn run_check(&self, config: &Config) -> CheckReport {
+        let mut report = CheckReport::new();
+        let api = self.get_api(config);
+        match &api {
+            Ok(api) => report.push("API key", check_api_key(api).await),
+            Err(_) => report.push("API key", Err(format_err!("no api key configured"))),
+        }
+        match config
+            .cache_dir
+            .clone()
+            .or_else(DiskCache::default_directory)
         {
-            set_default!(self, config, zipcode);
-            set_default!(self, config, country_code);
-            set_default!(self, config, city_name);
-            if config.lat.is_some() && config.lon.is_some() {
-                set_default!(self, config, lat);
-                set_default!(self, config, lon);
+            Some(directory) => report.push("Cache directory", check_cache_dir(&directory)),
+            None => report.push(
+                "Cache directory",
+                Err(format_err!("no cache directory configured")),
+            ),
+        }
+        let result = match (self.location_from_opts(), &api) {
+            (None, _) => Err(format_err!("no default location configured")),
+            (Some(_), Err(_)) => Err(format_err!("not checked without an api key")),
+            (Some(loc), Ok(api)) => check_location(api, &loc).await,
+        };
+        report.push("Default location", result);
+        report
+    }
+
+    /// Render the `JsonOutput` saved at `path`, including its forecast
+    /// if it has one
+    fn render_saved<T: Write>(
+        &self,
+        path: &Path,
+        display_opts: DisplayOpts,
+        buf: &mut T,
+    ) -> Result<(), Error> {
+        let saved = fs::read_to_string(path)
+            .map_err(Error::from)
+            .and_then(|text| JsonOutput::from_json(&text))
+            .map_err(|e| format_err!("Failed to read {}: {}", path.display(), e))?;
+        self.get_output_format(display_opts, saved.forecast.is_some())
+            .render(saved.current.as_ref(), saved.forecast.as_ref(), buf)
+    }
+
+    fn get_json_format(&self, display_opts: &DisplayOpts) -> JsonFormat {
+        JsonFormat::new()
+            .with_interpolate(self.interpolate)
+            .with_coord_precision(display_opts.coord_precision())
+    }
+
+    /// Format of the current conditions and forecast written to stdout,
+    /// with `output` the JSON goes to the file and stdout gets the text.
+    /// The text includes the forecast if `show_forecast`.
+    fn get_output_format(
+        &self,
+        display_opts: DisplayOpts,
+        show_forecast: bool,
+    ) -> Box<dyn OutputFormat> {
+        if self.json && self.output.is_none() {
+            Box::new(self.get_json_format(&display_opts))
+        } else if self.csv {
+            Box::new(CsvFormat::new(display_opts))
+        } else if self.oneline {
+            Box::new(OneLineFormat::new(display_opts))
+        } else {
+            Box::new(
+                TextFormat::new(display_opts)
+                    .with_forecast(show_forecast)
+                    .with_summary(self.summary)
+                    .with_interpolate(self.interpolate)
+                    .with_advice(self.advice),
+            )
+        }
+    }
+
+    /// Write `output` to the `output` file if there is one, otherwise to
+    /// stdout.  Returns whether stdout was used, in which case nothing else
+    /// should be printed.
+    fn write_json_output(
+        &self,
+        output: JsonOutput,
+        display_opts: &DisplayOpts,
+    ) -> Result<bool, Error> {
+        let output = output.with_coord_precision(display_opts.coord_precision());
+        match &self.output {
+            Some(path) => {
+                let mut f = BufWriter::new(File::create(path)?);
+                output.write_json(&mut f)?;
+                f.flush()?;
+                Ok(false)
+            }
+            None => {
+                output.write_json(&mut stdout().lock())?;
+                Ok(true)
             }
         }
     }
 
-    fn api_help_msg() -> String {
-        let config_dir = dirs::config_dir().expect("This shouldn't happen");
-        format!(
-            "API_KEY environment variable must be set\nEither set them directly or place them in \
-             {}",
-            config_dir
-                .join("weather_util")
-                .join("config.env")
-                .to_string_lossy()
-        )
+    /// Write the current conditions at each of `locations`, in the order
+    /// given.  Locations that fail are reported on stderr without stopping
+    /// the others.
+    async fn run_locations<T: Write>(
+        &self,
+        api: &WeatherApi,
+        display_opts: DisplayOpts,
+        buf: &mut T,
+    ) -> Result<(), Error> {
+        let locations: Vec<_> = self
+            .locations
+            .iter()
+            .map(|location| self.location_from_arg(location))
+            .collect();
+        if self.dry_run {
+            return locations.iter().try_for_each(|loc| {
+                writeln!(buf, "GET {}", api.get_weather_data_url(loc)?).map_err(Error::from)
+            });
+        }
+        let format = self.get_output_format(display_opts, false);
+        let results = api.get_weather_data_many(&locations).await;
+        let mut failures = 0;
+        for (location, result) in self.locations.iter().zip(results) {
+            match result {
+                Ok(data) => format.render(Some(&data), None, buf)?,
+                Err(e) => {
+                    failures += 1;
+                    eprintln!("Failed to get {}: {}", location.trim(), e);
+                }
+            }
+        }
+        if failures > 0 {
+            return Err(format_err!(
+                "{} of {} locations failed",
+                failures,
+                locations.len()
+            ));
+        }
+        Ok(())
+    }
+
+    fn get_change_detector(&self, units: Units) -> Result<ChangeDetector, Error> {
+        let mut detector =
+            ChangeDetector::new().with_debounce(self.debounce.unwrap_or(DEFAULT_DEBOUNCE));
+        if let Some(threshold) = self.threshold {
+            detector = detector.with_threshold(match units {
+                Units::Imperial => Temperature::from_fahrenheit(threshold)?,
+                Units::Metric => Temperature::from_celcius(threshold)?,
+            });
+        }
+        Ok(detector)
+    }
+
+    /// Print the current conditions, then poll them every `interval`
+    /// seconds reporting each change.  Failed polls are reported on stderr
+    /// and retried on the next one.
+    async fn run_watch(
+        &self,
+        api: &WeatherApi,
+        loc: &WeatherLocation,
+        config: &Config,
+        interval: u64,
+        display_opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        let mut detector = self.get_change_detector(display_opts.units)?;
+        let format = self.get_output_format(display_opts.clone(), false);
+        let mut first = true;
+        loop {
+            match api.get_weather_data(loc).await {
+                Ok(mut data) => {
+                    if let Some(names) = &config.location_names {
+                        names.apply(&mut data);
+                    }
+                    if first {
+                        format.render(Some(&data), None, &mut stdout().lock())?;
+                        first = false;
+                    }
+                    if let Some(event) = detector.observe(&data) {
+                        writeln!(
+                            stdout().lock(),
+                            "Conditions changed: {}",
+                            event.description()
+                        )?;
+                        if let Some(command) = &self.on_change {
+                            Self::run_hook(command, &event.hook_env(&data, display_opts)).await;
+                        }
+                        if self.exit_on_change {
+                            return Err(ConditionsChanged(event).into());
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Failed to get the current conditions: {}", e),
+            }
+            tokio::time::delay_for(std::time::Duration::from_secs(interval)).await;
+        }
+    }
+
+    /// `watch` if given, an interval below `MIN_WATCH_SECONDS` is an error
+    fn get_watch_interval(&self) -> Result<Option<u64>, Error> {
+        match self.watch {
+            Some(interval) if interval < MIN_WATCH_SECONDS => Err(format_err!(
+                "--watch must be at least {} seconds, got {}",
+                MIN_WATCH_SECONDS,
+                interval
+            )),
+            interval => Ok(interval),
+        }
+    }
+
+    /// Run `command` with `sh -c`, reporting failures on stderr
+    async fn run_hook(command: &str, env: &[(&'static str, String)]) {
+        let status = tokio::process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .envs(env.iter().map(|(key, value)| (key, value)))
+            .status()
+            .await;
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => eprintln!("on-change command failed: {}", status),
+            Err(e) => eprintln!("on-change command failed: {}", e),
+        }
+    }
+
+    /// Write the url of every request the options would make to `buf`,
+    /// starting with `ip_location_url` for `here`
+    fn write_dry_run<T: Write>(
+        &self,
+        api: &WeatherApi,
+        ip_location_url: &str,
+        loc: &WeatherLocation,
+        buf: &mut T,
+    ) -> Result<(), Error> {
+        if self.here {
+            writeln!(buf, "GET {}", ip_location_url)?;
+        }
+        let urls = if let Some(dt) = self.date {
+            vec![api.get_weather_history_url(loc, dt)?]
+        } else if self.onecall {
+            vec![api.get_weather_onecall_url(loc, &self.get_exclude())?]
+        } else if let Some(days) = self.daily_forecast_days() {
+            vec![
+                api.get_weather_data_url(loc)?,
+                api.get_weather_daily_forecast_url(loc, days)?,
+            ]
+        } else if self.forecast_only {
+            vec![api.get_weather_forecast_url(loc)?]
+        } else {
+            vec![
+                api.get_weather_data_url(loc)?,
+                api.get_weather_forecast_url(loc)?,
+            ]
+        };
+        urls.iter()
+            .try_for_each(|url| writeln!(buf, "GET {}", url).map_err(Error::from))
+    }
+
+    /// Fetch the current conditions, and the forecast if it's needed.  When
+    /// only one of the two fails a warning is written to `warn` and the other
+    /// is still returned, only when both fail is this an error.  With
+    /// `forecast_only` only the forecast is fetched.
+    async fn get_current_and_forecast<T: Write>(
+        &self,
+        api: &WeatherApi,
+        loc: &WeatherLocation,
+        warn: &mut T,
+    ) -> Result<(Option<WeatherData>, Option<WeatherForecast>), Error> {
+        if self.forecast_only {
+            return api
+                .get_weather_forecast(loc)
+                .await
+                .map(|forecast| (None, Some(forecast)));
+        }
+        let data = api.get_weather_data(loc);
+        if !(self.show_forecast()
+            || self.export.is_some()
+            || self.interpolate
+            || self.summary
+            || self.advice
+            || self.prometheus)
+        {
+            return data.await.map(|data| (Some(data), None));
+        }
+        match join(data, api.get_weather_forecast(loc)).await {
+            (Ok(data), Ok(forecast)) => Ok((Some(data), Some(forecast))),
+            (Ok(data), Err(e)) => {
+                writeln!(warn, "forecast unavailable: {}", e)?;
+                Ok((Some(data), None))
+            }
+            (Err(e), Ok(forecast)) => {
+                writeln!(warn, "current conditions unavailable: {}", e)?;
+                Ok((None, Some(forecast)))
+            }
+            (Err(data_err), Err(forecast_err)) => Err(format_err!(
+                "current conditions unavailable: {}; forecast unavailable: {}",
+                data_err,
+                forecast_err
+            )),
+        }
+    }
+
+    async fn run_onecall(
+        &self,
+        api: &WeatherApi,
+        loc: &WeatherLocation,
+        display_opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        let onecall = api.get_weather_onecall(loc, &self.get_exclude()).await?;
+        if let Some(path) = &self.export {
+            Self::write_export(path, &onecall.get_forecast_summary())?;
+        }
+
+        let stdout = stdout();
+        if (self.json || self.output.is_some())
+            && self.write_json_output(
+                JsonOutput::new().with_onecall(onecall.clone()),
+                display_opts,
+            )?
+        {
+            return Ok(());
+        }
+        if let Some(data) = onecall.get_weather_data() {
+            if self.summary {
+                let forecast = onecall.get_weather_forecast();
+                let summary = get_summary(&data, forecast.as_ref(), display_opts);
+                writeln!(stdout.lock(), "{}", summary)?;
+            } else {
+                let summary = onecall.get_forecast_summary();
+                let opts = Self::with_wind_percentile(display_opts, &summary, &data);
+                data.get_current_conditions(&mut stdout.lock(), &opts)?;
+            }
+            if self.advice {
+                writeln!(stdout.lock(), "{}", get_advice(&data, None))?;
+            }
+        }
+        if self.show_alerts() && !self.forecast_only {
+            let keywords = self.alerts_filter.as_deref().unwrap_or(&[]);
+            onecall.get_alerts(&mut stdout.lock(), keywords)?;
+        }
+        if self.summary && onecall.current.is_some() {
+            return Ok(());
+        }
+        if self.hourly {
+            onecall.get_hourly(&mut stdout.lock(), display_opts)?;
+        }
+        if self.show_forecast() {
+            onecall.get_daily_forecast(&mut stdout.lock(), display_opts)?;
+        }
+        Ok(())
+    }
+
+    async fn run_daily_forecast(
+        &self,
+        api: &WeatherApi,
+        loc: &WeatherLocation,
+        days: usize,
+        display_opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        let (data, forecast): (WeatherData, WeatherDailyForecast) = try_join(
+            api.get_weather_data(loc),
+            api.get_weather_daily_forecast(loc, days),
+        )
+        .await?;
+        if let Some(path) = &self.export {
+            Self::write_export(path, &forecast.get_forecast_summary())?;
+        }
+
+        let stdout = stdout();
+        if (self.json || self.output.is_some())
+            && self.write_json_output(
+                JsonOutput::new()
+                    .with_current(data.clone())
+                    .with_daily_forecast(forecast.clone()),
+                display_opts,
+            )?
+        {
+            return Ok(());
+        }
+        if self.summary {
+            let forecast = forecast.get_weather_forecast();
+            let summary = get_summary(&data, Some(&forecast), display_opts);
+            writeln!(stdout.lock(), "{}", summary)?;
+        } else {
+            if !self.forecast_only {
+                let summary = forecast.get_forecast_summary();
+                let opts = Self::with_wind_percentile(display_opts, &summary, &data);
+                data.get_current_conditions(&mut stdout.lock(), &opts)?;
+            }
+            forecast.get_forecast(&mut stdout.lock(), display_opts)?;
+        }
+        if self.advice {
+            writeln!(stdout.lock(), "{}", get_advice(&data, None))?;
+        }
+        Ok(())
+    }
+
+    /// `display_opts` with the percentile of the current wind speed of
+    /// `data` among the days of `summary`
+    fn with_wind_percentile(
+        display_opts: &DisplayOpts,
+        summary: &ForecastSummary,
+        data: &WeatherData,
+    ) -> DisplayOpts {
+        match summary.wind_percentile(data.wind.speed) {
+            Some(percentile) => display_opts.clone().with_wind_percentile(percentile),
+            None => display_opts.clone(),
+        }
+    }
+
+    /// Write `summary` to `path` as a `WeekExport`
+    fn write_export(path: &Path, summary: &ForecastSummary) -> Result<(), Error> {
+        let mut f = BufWriter::new(File::create(path)?);
+        WeekExport::from(summary).write_json(&mut f)?;
+        f.flush()?;
+        Ok(())
+    }
+
+    async fn run_history(
+        &self,
+        api: &WeatherApi,
+        loc: &WeatherLocation,
+        dt: DateTime<Utc>,
+        display_opts: &DisplayOpts,
+    ) -> Result<(), Error> {
+        let history = api.get_weather_history(loc, dt).await?;
+
+        let stdout = stdout();
+        if (self.json || self.output.is_some())
+            && self.write_json_output(
+                JsonOutput::new().with_onecall(history.clone()),
+                display_opts,
+            )?
+        {
+            return Ok(());
+        }
+        if let Some(data) = history.get_weather_data() {
+            data.get_current_conditions(&mut stdout.lock(), display_opts)?;
+        }
+        Ok(())
+    }
+
+    /// Whether `location` looks like a zipcode rather than a city name:
+    /// non-empty and made up of ASCII digits only, e.g. `90210`
+    fn is_numeric_location(location: &str) -> bool {
+        let location = location.trim();
+        !location.is_empty() && location.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    /// A numeric `city_name` is almost certainly a zipcode the api won't
+    /// find as a city
+    fn numeric_city_name_warning(&self) -> Option<String> {
+        self.city_name
+            .as_deref()
+            .filter(|city_name| Self::is_numeric_location(city_name))
+            .map(|city_name| {
+                format!(
+                    "Warning: city name {0} looks like a zipcode, did you mean --zipcode {0}?",
+                    city_name.trim()
+                )
+            })
+    }
+
+    /// Warning printed when the api key is given with `--api-key`, where it
+    /// shows up in the process list.  Checked before the config defaults
+    /// are applied.
+    fn api_key_warning(&self) -> Option<&'static str> {
+        self.api_key.as_ref().map(|_| {
+            "Warning: --api-key is visible to other users in the process list, \
+             prefer the API_KEY environment variable or the config file"
+        })
+    }
+
+    /// `location` as a zipcode if it is numeric and fits one, otherwise
+    /// `None`
+    fn zipcode_from_arg(location: &str) -> Option<u64> {
+        Some(location.trim())
+            .filter(|location| Self::is_numeric_location(location))
+            .and_then(|location| location.parse().ok())
+    }
+
+    /// One of the `locations`, a zipcode (in `country_code` if given) or a
+    /// city name
+    fn location_from_arg(&self, location: &str) -> WeatherLocation {
+        match (Self::zipcode_from_arg(location), &self.country_code) {
+            (Some(zipcode), Some(country_code)) => {
+                WeatherLocation::from_zipcode_country_code(zipcode, country_code)
+            }
+            (Some(zipcode), None) => WeatherLocation::from_zipcode(zipcode),
+            (None, _) => WeatherLocation::from_city_name(location.trim()),
+        }
+    }
+
+    /// Replace the `preset` with the location stored for it: coordinates
+    /// written as `lat,lon`, otherwise a zipcode or city name like the
+    /// positional `location`
+    fn apply_preset(&mut self, presets: &LocationPresets) -> Result<(), Error> {
+        let name = match &self.preset {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let query = presets.resolve(name)?;
+        match Self::lat_lon_from_arg(query) {
+            Some((lat, lon)) => {
+                self.lat = Some(lat);
+                self.lon = Some(lon);
+            }
+            None => self.location = Some(query.into()),
+        }
+        Ok(())
+    }
+
+    /// Coordinates written as `lat,lon`, e.g. `40.71,-74.01`
+    fn lat_lon_from_arg(location: &str) -> Option<(Latitude, Longitude)> {
+        let (lat, lon) = location.split_once(',')?;
+        let lat = Latitude::try_from(lat.trim().parse::<f64>().ok()?).ok()?;
+        let lon = Longitude::try_from(lon.trim().parse::<f64>().ok()?).ok()?;
+        Some((lat, lon))
+    }
+
+    /// Use the coordinates of the `icao` or `iata` airport, unless a
+    /// zipcode, city name or coordinates were also given
+    fn apply_airport(&mut self, config: &Config) -> Result<(), Error> {
+        let code = match self.icao.as_ref().or(self.iata.as_ref()) {
+            Some(code) => code,
+            None => return Ok(()),
+        };
+        if self.zipcode.is_some()
+            || self.city_name.is_some()
+            || (self.lat.is_some() && self.lon.is_some())
+        {
+            return Ok(());
+        }
+        let coord = config.airports.clone().unwrap_or_default().resolve(code)?;
+        self.lat = Some(coord.lat);
+        self.lon = Some(coord.lon);
+        Ok(())
+    }
+
+    /// Use the positional `location`, unless another location option was
+    /// given: a numeric location is a zipcode, unless it doesn't fit one
+    /// (e.g. too many digits) and is a city name instead.  This is decided
+    /// before any request, a zipcode the api doesn't find is an error.
+    fn apply_location_arg(&mut self) {
+        let location = match self.location.take() {
+            Some(location) => location,
+            None => return,
+        };
+        if self.zipcode.is_some()
+            || self.city_name.is_some()
+            || (self.lat.is_some() && self.lon.is_some())
+        {
+            return;
+        }
+        match Self::zipcode_from_arg(&location) {
+            Some(zipcode) => self.zipcode = Some(zipcode),
+            None => self.city_name = Some(location),
+        }
+    }
+
+    fn apply_defaults(&mut self, config: &Config) {
+        if self.api_key.is_none() {
+            set_default!(self, config, api_key);
+        }
+        if self.zipcode.is_none()
+            && self.country_code.is_none()
+            && self.city_name.is_none()
+            && (self.lat.is_none() || self.lon.is_none())
+        {
+            set_default!(self, config, zipcode);
+            set_default!(self, config, country_code);
+            set_default!(self, config, city_name);
+            if config.lat.is_some() && config.lon.is_some() {
+                set_default!(self, config, lat);
+                set_default!(self, config, lon);
+            }
+        }
+    }
+
+    fn api_help_msg() -> String {
+        let config_dir = dirs::config_dir().expect("This shouldn't happen");
+        format!(
+            "API_KEY (or OPENWEATHER_API_KEY_FILE) environment variable must be set\nEither set \
+             them directly or place them in {}",
+            config_dir
+                .join("weather_util")
+                .join("config.env")
+                .to_string_lossy()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use std::{convert::TryFrom, env::temp_dir, fs, time::Duration};
+    use structopt::StructOpt;
+    use tokio::time::timeout;
+
+    use crate::{
+        config::{Config, ConfigInner},
+        disk_cache::DiskCache,
+        display_opts::{DisplayOpts, IconStyle},
+        forecast_summary::TemperatureField,
+        geocoder::{Geocoder, Geocoders},
+        http_client::HttpFuture,
+        ip_location::DEFAULT_IP_LOCATION_URL,
+        latitude::Latitude,
+        location_presets::LocationPresets,
+        logger::captured_logs,
+        longitude::Longitude,
+        mock_server::{http_response, serve, serve_routes},
+        output_format::{JsonFormat, OutputFormat},
+        temperature::Temperature,
+        theme::Theme,
+        weather_api::{WeatherApi, WeatherLocation},
+        weather_data::{Coord, WeatherData},
+        weather_forecast::WeatherForecast,
+        weather_onecall::OneCallSection,
+        weather_opts::WeatherOpts,
+        week_export::WeekExport,
+    };
+
+    #[test]
+    fn test_get_exclude() {
+        let mut opts = WeatherOpts::default();
+        assert_eq!(
+            opts.get_exclude(),
+            vec![
+                OneCallSection::Minutely,
+                OneCallSection::Hourly,
+                OneCallSection::Daily,
+                OneCallSection::Alerts
+            ]
+        );
+
+        let summary = WeatherOpts {
+            summary: true,
+            ..WeatherOpts::default()
+        };
+        assert!(!summary.get_exclude().contains(&OneCallSection::Hourly));
+
+        opts.hourly = true;
+        opts.forecast = true;
+        assert_eq!(
+            opts.get_exclude(),
+            vec![OneCallSection::Minutely, OneCallSection::Alerts]
+        );
+
+        opts.alerts_filter = Some(vec!["warning".into()]);
+        assert_eq!(opts.get_exclude(), vec![OneCallSection::Minutely]);
+
+        opts.exclude = Some(vec![OneCallSection::Current]);
+        assert_eq!(opts.get_exclude(), vec![OneCallSection::Current]);
+    }
+
+    #[test]
+    fn test_forecast_only_exclude() -> Result<(), Error> {
+        let mut opts = WeatherOpts {
+            forecast_only: true,
+            alerts: true,
+            ..WeatherOpts::default()
+        };
+        assert!(opts.show_forecast());
+        assert_eq!(
+            opts.get_exclude(),
+            vec![
+                OneCallSection::Current,
+                OneCallSection::Minutely,
+                OneCallSection::Hourly,
+                OneCallSection::Alerts
+            ]
+        );
+        opts.hourly = true;
+        assert_eq!(
+            opts.get_exclude(),
+            vec![
+                OneCallSection::Current,
+                OneCallSection::Minutely,
+                OneCallSection::Alerts
+            ]
+        );
+
+        // An explicit exclude only adds to the sections left out
+        opts.exclude = Some(vec![OneCallSection::Hourly]);
+        assert_eq!(
+            opts.get_exclude(),
+            vec![
+                OneCallSection::Current,
+                OneCallSection::Minutely,
+                OneCallSection::Hourly,
+                OneCallSection::Alerts
+            ]
+        );
+
+        let api = WeatherApi::new("api_key", "api.openweathermap.org", "data/2.5/");
+        let loc =
+            WeatherLocation::from_lat_lon(Latitude::try_from(40.76)?, Longitude::try_from(-73.93)?);
+        let mut buf = Vec::new();
+        opts.onecall = true;
+        opts.write_dry_run(&api, DEFAULT_IP_LOCATION_URL, &loc, &mut buf)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("exclude=current%2Cminutely%2Chourly%2Calerts"),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_feels_like() {
+        let opts = WeatherOpts {
+            feels_like: true,
+            ..WeatherOpts::default()
+        };
+        assert!(opts.show_forecast());
+        assert!(!opts.get_exclude().contains(&OneCallSection::Daily));
+        let display = opts.get_display_opts(&Config::default());
+        assert_eq!(display.forecast_field, TemperatureField::FeelsLike);
+        let display = WeatherOpts::default().get_display_opts(&Config::default());
+        assert_eq!(display.forecast_field, TemperatureField::Temperature);
+    }
+
+    #[test]
+    fn test_export() -> Result<(), Error> {
+        let opts = WeatherOpts {
+            export: Some("week.json".into()),
+            ..WeatherOpts::default()
+        };
+        assert!(!opts.show_forecast());
+        assert!(!opts.get_exclude().contains(&OneCallSection::Daily));
+
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        let summary = forecast.get_forecast_summary();
+        let path = temp_dir().join(format!("weather_util_export_{}.json", std::process::id()));
+        WeatherOpts::write_export(&path, &summary)?;
+        let export: WeekExport = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        fs::remove_file(&path)?;
+        assert_eq!(export, WeekExport::from(&summary));
+        Ok(())
+    }
+
+    #[test]
+    fn test_forecast_window_days() {
+        assert_eq!(WeatherOpts::default().forecast_window_days(), None);
+        let opts = WeatherOpts {
+            forecast_days: Some(2),
+            days: Some(3),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.forecast_window_days(), Some(2));
+        let opts = WeatherOpts {
+            days: Some(5),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.forecast_window_days(), None);
+        let opts = WeatherOpts {
+            forecast_days: Some(2),
+            summary: true,
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.forecast_window_days(), None);
+        let opts = WeatherOpts {
+            forecast_days: Some(2),
+            json: true,
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.forecast_window_days(), None);
+        let opts = WeatherOpts {
+            forecast_days: Some(2),
+            output: Some("forecast.json".into()),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.forecast_window_days(), None);
+    }
+
+    #[test]
+    fn test_sun_times_only() {
+        assert_eq!(WeatherOpts::default().sun_times_only(), None);
+        let opts = WeatherOpts {
+            sunset_only: true,
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.sun_times_only(), Some((false, true)));
+        let opts = WeatherOpts {
+            golden_hour: true,
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.sun_times_only(), Some((true, true)));
+        let opts = WeatherOpts {
+            sunrise_only: true,
+            golden_hour: true,
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.sun_times_only(), Some((true, false)));
+    }
+
+    #[test]
+    fn test_output() -> Result<(), Error> {
+        let path = temp_dir().join(format!("weather_util_output_{}.json", std::process::id()));
+        let opts = WeatherOpts {
+            forecast: true,
+            json: true,
+            output: Some(path.clone()),
+            ..WeatherOpts::default()
+        };
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+
+        let mut text = Vec::new();
+        opts.render_current_and_forecast(
+            Some(&data),
+            Some(&forecast),
+            DisplayOpts::default(),
+            &mut text,
+        )?;
+
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        fs::remove_file(&path)?;
+        assert_eq!(json["current"]["name"], "Astoria");
+        assert_eq!(json["forecast"]["list"].as_array().map(Vec::len), Some(40));
+        let text = String::from_utf8(text)?;
+        assert!(text.starts_with("Current conditions Astoria"), "{}", text);
+        assert!(text.contains("\nForecast:\n"), "{}", text);
+        assert!(!text.contains("schema_version"), "{}", text);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_json() -> Result<(), Error> {
+        let path = temp_dir().join(format!("weather_util_saved_{}.json", std::process::id()));
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        let mut f = fs::File::create(&path)?;
+        JsonFormat::new().render(Some(&data), Some(&forecast), &mut f)?;
+
+        let opts = WeatherOpts {
+            from_json: Some(path.clone()),
+            ..WeatherOpts::default()
+        };
+        let mut text = Vec::new();
+        opts.render_saved(&path, DisplayOpts::default(), &mut text)?;
+        let text = String::from_utf8(text)?;
+        assert!(text.starts_with("Current conditions Astoria"), "{}", text);
+        assert!(text.contains("\nForecast:\n"), "{}", text);
+
+        let opts = WeatherOpts { csv: true, ..opts };
+        let mut csv = Vec::new();
+        opts.render_saved(&path, DisplayOpts::default(), &mut csv)?;
+        assert_eq!(String::from_utf8(csv)?.lines().count(), 1 + 1 + 40);
+
+        fs::write(&path, include_str!("../tests/weather.json"))?;
+        let e = opts
+            .render_saved(&path, DisplayOpts::default(), &mut Vec::new())
+            .unwrap_err();
+        assert!(e.to_string().contains("schema_version"), "{}", e);
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_theme() {
+        let config = Config::default();
+        let opts = WeatherOpts {
+            theme: Some(Theme::Minimal),
+            ..WeatherOpts::default()
+        };
+        let display = opts.get_display_opts(&config);
+        assert!(display.hide_location);
+        assert!(display.compact_forecast);
+        assert_eq!(display.precision, Some(0));
+        assert!(display.temperature_colors.is_none());
+        assert!(display.icons.is_none());
+
+        let opts = WeatherOpts {
+            theme: Some(Theme::Detailed),
+            ..WeatherOpts::default()
+        };
+        let display = opts.get_display_opts(&config);
+        assert!(display.verbose);
+        assert!(display.temperature_colors.is_some());
+        assert_eq!(display.icons, Some(IconStyle::Emoji));
+        assert!(!display.hide_location);
+
+        let opts = WeatherOpts {
+            theme: Some(Theme::Ascii),
+            ..WeatherOpts::default()
+        };
+        let display = opts.get_display_opts(&config);
+        assert!(display.ascii);
+        assert!(display.icons.is_none());
+        assert_eq!(display.condition_icon(800), "");
+
+        let opts = WeatherOpts {
+            theme: Some(Theme::Nerd),
+            ..WeatherOpts::default()
+        };
+        let display = opts.get_display_opts(&config);
+        assert_eq!(display.icons, Some(IconStyle::Glyph));
+        assert_eq!(display.condition_icon(800), "\u{e30d} ");
+
+        // Flags given explicitly take precedence over the theme
+        let opts = WeatherOpts {
+            theme: Some(Theme::Minimal),
+            precision: Some(2),
+            ascii: true,
+            ..WeatherOpts::default()
+        };
+        let display = opts.get_display_opts(&config);
+        assert_eq!(display.precision, Some(2));
+        assert!(display.ascii);
+        assert!(display.hide_location);
+    }
+
+    #[tokio::test]
+    async fn test_list_conditions() -> Result<(), Error> {
+        // No api key or location is configured, so anything other than
+        // listing the conditions would fail
+        let opts = WeatherOpts {
+            list_conditions: true,
+            ..WeatherOpts::default()
+        };
+        opts.run_opts(&Config::default()).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dry_run() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![http_response(
+            "200 OK",
+            &[],
+            include_str!("../tests/weather.json"),
+        )])
+        .await?;
+        let api = WeatherApi::new("0123456789abcdef", &endpoint, "data/2.5/");
+        let loc = WeatherLocation::from_zipcode(11106);
+        let opts = WeatherOpts {
+            dry_run: true,
+            ..WeatherOpts::default()
+        };
+
+        let mut buf = Vec::new();
+        opts.write_dry_run(&api, DEFAULT_IP_LOCATION_URL, &loc, &mut buf)?;
+        let buf = String::from_utf8(buf)?;
+        assert_eq!(
+            buf,
+            format!(
+                "GET {0}/data/2.5/weather?zip=11106&country_code=us&APPID=0123...cdef\nGET \
+                 {0}/data/2.5/forecast?zip=11106&country_code=us&APPID=0123...cdef\n",
+                endpoint
+            )
+        );
+        assert!(!buf.contains("0123456789abcdef"));
+
+        let opts = WeatherOpts {
+            dry_run: true,
+            forecast_only: true,
+            ..WeatherOpts::default()
+        };
+        let mut buf = Vec::new();
+        opts.write_dry_run(&api, DEFAULT_IP_LOCATION_URL, &loc, &mut buf)?;
+        assert_eq!(
+            String::from_utf8(buf)?,
+            format!(
+                "GET {}/data/2.5/forecast?zip=11106&country_code=us&APPID=0123...cdef\n",
+                endpoint
+            )
+        );
+
+        let opts = WeatherOpts {
+            dry_run: true,
+            days: Some(10),
+            ..WeatherOpts::default()
+        };
+        let mut buf = Vec::new();
+        opts.write_dry_run(&api, DEFAULT_IP_LOCATION_URL, &loc, &mut buf)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.ends_with(
+                "/data/2.5/forecast/daily?zip=11106&country_code=us&cnt=10&APPID=0123...cdef\n"
+            ),
+            "{}",
+            buf
+        );
+
+        // The IP location is only written out as well
+        let opts = WeatherOpts {
+            dry_run: true,
+            here: true,
+            zipcode: Some(11106),
+            ..WeatherOpts::default()
+        };
+        let ip_location_url = format!("{}/json", endpoint);
+        let loc = opts
+            .resolve_location(&ip_location_url, &Geocoders::new())
+            .await?;
+        let mut buf = Vec::new();
+        opts.write_dry_run(&api, &ip_location_url, &loc, &mut buf)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.starts_with(&format!(
+                "GET {0}/json\nGET {0}/data/2.5/weather?zip=11106",
+                endpoint
+            )),
+            "{}",
+            buf
+        );
+
+        // The server never sees a connection
+        assert!(timeout(Duration::from_millis(100), handle).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_locations() -> Result<(), Error> {
+        let (endpoint, handle) = serve(vec![
+            http_response("200 OK", &[], include_str!("../tests/weather.json")),
+            http_response(
+                "404 Not Found",
+                &[],
+                r#"{"cod":"404","message":"city not found"}"#,
+            ),
+        ])
+        .await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/")
+            .with_retries(0)
+            .with_concurrency(1);
+        let opts = WeatherOpts {
+            locations: vec!["11106".into(), " Nowhere ".into()],
+            oneline: true,
+            ..WeatherOpts::default()
+        };
+
+        let mut buf = Vec::new();
+        let result = opts
+            .run_locations(&api, DisplayOpts::default(), &mut buf)
+            .await;
+        assert_eq!(result.unwrap_err().to_string(), "1 of 2 locations failed");
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.starts_with("Astoria: 41.1°F clear sky"), "{}", buf);
+        assert_eq!(buf.lines().count(), 1);
+        let requests = handle.await?;
+        assert!(requests[0].contains("zip=11106"), "{:?}", requests);
+        assert!(requests[1].contains("q=Nowhere"), "{:?}", requests);
+
+        let opts = WeatherOpts {
+            locations: vec!["11106".into(), "London,GB".into()],
+            dry_run: true,
+            ..WeatherOpts::default()
+        };
+        let mut buf = Vec::new();
+        opts.run_locations(&api, DisplayOpts::default(), &mut buf)
+            .await?;
+        let buf = String::from_utf8(buf)?;
+        let lines: Vec<_> = buf.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("/weather?q=London%2CGB&"), "{}", buf);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_forecast_unavailable() -> Result<(), Error> {
+        let (endpoint, handle) = serve_routes(vec![
+            (
+                "/data/2.5/weather?",
+                http_response("200 OK", &[], include_str!("../tests/weather.json")),
+            ),
+            (
+                "/data/2.5/forecast?",
+                http_response("500 Internal Server Error", &[], "{}"),
+            ),
+        ])
+        .await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/").with_retries(0);
+        let loc = WeatherLocation::from_zipcode(11106);
+        let opts = WeatherOpts {
+            forecast: true,
+            ..WeatherOpts::default()
+        };
+
+        let mut warn = Vec::new();
+        let (data, forecast) = opts.get_current_and_forecast(&api, &loc, &mut warn).await?;
+        assert_eq!(data.map(|data| data.name), Some("Astoria".into()));
+        assert!(forecast.is_none());
+        let warn = String::from_utf8(warn)?;
+        assert!(warn.starts_with("forecast unavailable: "), "{}", warn);
+        assert_eq!(handle.await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_current_and_forecast_unavailable() -> Result<(), Error> {
+        let (endpoint, handle) = serve_routes(vec![
+            (
+                "/data/2.5/weather?",
+                http_response("500 Internal Server Error", &[], "{}"),
+            ),
+            (
+                "/data/2.5/forecast?",
+                http_response("500 Internal Server Error", &[], "{}"),
+            ),
+        ])
+        .await?;
+        let api = WeatherApi::new("api_key", &endpoint, "data/2.5/").with_retries(0);
+        let loc = WeatherLocation::from_zipcode(11106);
+        let opts = WeatherOpts {
+            forecast: true,
+            ..WeatherOpts::default()
+        };
+
+        let mut warn = Vec::new();
+        assert!(opts
+            .get_current_and_forecast(&api, &loc, &mut warn)
+            .await
+            .is_err());
+        assert!(warn.is_empty());
+        assert_eq!(handle.await?.len(), 2);
+        Ok(())
+    }
+
+    /// Resolves "stub:" queries to a fixed location
+    struct StubGeocoder;
+
+    impl Geocoder for StubGeocoder {
+        fn accepts(&self, query: &str) -> bool {
+            query.starts_with("stub:")
+        }
+
+        fn resolve<'a>(&'a self, _query: &'a str) -> HttpFuture<'a, Result<Coord, Error>> {
+            Box::pin(async {
+                Ok(Coord {
+                    lon: Longitude::try_from(-73.93)?,
+                    lat: Latitude::try_from(40.76)?,
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_apply_diff() -> Result<(), Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let path = temp_dir()
+            .join(format!("weather_util_diff_{}", std::process::id()))
+            .join("last_run.json");
+
+        // The first run only stores the baseline
+        let opts = WeatherOpts::apply_diff(&path, &data, DisplayOpts::default());
+        assert!(opts.baseline.is_none());
+        let opts = WeatherOpts::apply_diff(&path, &data, DisplayOpts::default());
+        assert_eq!(opts.baseline.unwrap().name, "Astoria");
+
+        let mut warmer = data.clone();
+        warmer.main.temp = Temperature::from_fahrenheit(data.main.temp.fahrenheit() + 3.0)?;
+        let opts = WeatherOpts::apply_diff(&path, &warmer, DisplayOpts::default());
+        let mut buf = Vec::new();
+        warmer.get_current_conditions(&mut buf, &opts)?;
+        let buf = String::from_utf8(buf)?;
+        assert!(
+            buf.contains("°C) (+3.0 since last check) (feels"),
+            "{}",
+            buf
+        );
+        assert!(buf.contains("\tRelative Humidity: 56%\n"), "{}", buf);
+
+        fs::remove_dir_all(path.parent().unwrap())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_location_arg() {
+        let mut opts = WeatherOpts {
+            location: Some("90210".into()),
+            ..WeatherOpts::default()
+        };
+        opts.apply_location_arg();
+        assert_eq!(opts.zipcode, Some(90210));
+        assert_eq!(opts.city_name, None);
+        assert!(opts.location.is_none());
+
+        for location in &[
+            "New York",
+            "London,GB",
+            "90210a",
+            "123456789012345678901234",
+        ] {
+            let mut opts = WeatherOpts {
+                location: Some((*location).into()),
+                ..WeatherOpts::default()
+            };
+            opts.apply_location_arg();
+            assert_eq!(opts.zipcode, None, "{}", location);
+            assert_eq!(opts.city_name.as_deref(), Some(*location));
+        }
+
+        // Explicit options take precedence
+        let mut opts = WeatherOpts {
+            city_name: Some("Astoria".into()),
+            location: Some("90210".into()),
+            ..WeatherOpts::default()
+        };
+        opts.apply_location_arg();
+        assert_eq!(opts.zipcode, None);
+        assert_eq!(opts.city_name.as_deref(), Some("Astoria"));
+    }
+
+    #[test]
+    fn test_airport() -> Result<(), Error> {
+        let mut opts = WeatherOpts {
+            iata: Some("lhr".into()),
+            ..WeatherOpts::default()
+        };
+        opts.apply_airport(&Config::default())?;
+        match opts.get_location()? {
+            WeatherLocation::LatLon {
+                latitude,
+                longitude,
+            } => {
+                assert_eq!(f64::from(latitude), 51.47);
+                assert_eq!(f64::from(longitude), -0.4543);
+            }
+            loc => panic!("{:?}", loc),
+        }
+
+        let mut opts = WeatherOpts {
+            icao: Some("ZZZZ".into()),
+            ..WeatherOpts::default()
+        };
+        assert!(opts.apply_airport(&Config::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_preset() -> Result<(), Error> {
+        let presets: LocationPresets = "home=40.71,-74.01;work=Boston,MA,US;gym=11106".parse()?;
+        let mut opts = WeatherOpts {
+            preset: Some("home".into()),
+            ..WeatherOpts::default()
+        };
+        opts.apply_preset(&presets)?;
+        assert_eq!(opts.lat.map(f64::from), Some(40.71));
+        assert_eq!(opts.lon.map(f64::from), Some(-74.01));
+
+        let mut opts = WeatherOpts {
+            preset: Some("work".into()),
+            ..WeatherOpts::default()
+        };
+        opts.apply_preset(&presets)?;
+        opts.apply_location_arg();
+        assert_eq!(opts.city_name.as_deref(), Some("Boston,MA,US"));
+        assert!(opts.lat.is_none());
+
+        let mut opts = WeatherOpts {
+            preset: Some("gym".into()),
+            ..WeatherOpts::default()
+        };
+        opts.apply_preset(&presets)?;
+        opts.apply_location_arg();
+        assert_eq!(opts.zipcode, Some(11106));
+
+        let mut opts = WeatherOpts {
+            preset: Some("school".into()),
+            ..WeatherOpts::default()
+        };
+        let err = opts.apply_preset(&presets).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown preset 'school', expected one of home, work, gym"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_numeric_city_name_warning() {
+        let opts = WeatherOpts {
+            city_name: Some("90210".into()),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(
+            opts.numeric_city_name_warning().as_deref(),
+            Some("Warning: city name 90210 looks like a zipcode, did you mean --zipcode 90210?")
+        );
+        let opts = WeatherOpts {
+            city_name: Some("Astoria".into()),
+            ..WeatherOpts::default()
+        };
+        assert!(opts.numeric_city_name_warning().is_none());
+        assert!(WeatherOpts::default().numeric_city_name_warning().is_none());
+    }
+
+    #[test]
+    fn test_coord_precision() -> Result<(), Error> {
+        let opts = WeatherOpts {
+            lat: Some(Latitude::try_from(40.712_776)?),
+            lon: Some(Longitude::try_from(-74.005_974)?),
+            coord_precision: Some(1),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(
+            opts.get_display_opts(&Config::default()).coord_precision(),
+            1
+        );
+        let display_opts = opts.get_display_opts(&Config::default());
+        assert_eq!(opts.get_json_format(&display_opts).coord_precision, Some(1));
+        let display_opts = WeatherOpts::default().get_display_opts(&Config::default());
+        assert_eq!(
+            WeatherOpts::default()
+                .get_json_format(&display_opts)
+                .coord_precision,
+            Some(4)
+        );
+
+        // the request keeps the full precision
+        let api = WeatherApi::new("api_key", "api.openweathermap.org", "data/2.5/");
+        let url = api.get_weather_data_url(&opts.get_location()?)?;
+        assert!(
+            url.as_str().contains("lat=40.712776&lon=-74.005974"),
+            "{}",
+            url
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_api_key_flag() -> Result<(), Error> {
+        let opts = WeatherOpts::from_iter(&[
+            "weather-util-rust",
+            "--api-key",
+            "95337ed3a8a87acae620d673fae85b11",
+            "-z",
+            "11106",
+        ]);
+        assert!(opts.api_key_warning().is_some());
+        assert!(WeatherOpts::default().api_key_warning().is_none());
+
+        let api = opts.get_api(&Config::default())?;
+        let verbose = format!("Using {:?}", api);
+        assert!(
+            verbose.starts_with("Using WeatherApi(key=9533...5b11,"),
+            "{}",
+            verbose
+        );
+        assert!(!verbose.contains("95337ed3a8a87acae620d673fae85b11"));
+        let url = api.get_weather_data_url(&opts.get_location()?)?;
+        assert!(url.as_str().contains("APPID=9533...5b11"), "{}", url);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_geocoder() -> Result<(), Error> {
+        let geocoders = Geocoders::new().register(StubGeocoder);
+        let opts = WeatherOpts {
+            city_name: Some("stub:home".into()),
+            ..WeatherOpts::default()
+        };
+        match opts.resolve_location("", &geocoders).await? {
+            WeatherLocation::LatLon {
+                latitude,
+                longitude,
+            } => {
+                assert_eq!(f64::from(latitude), 40.76);
+                assert_eq!(f64::from(longitude), -73.93);
+            }
+            loc => panic!("unexpected location {:?}", loc),
+        }
+
+        // Queries no geocoder accepts are left to the api
+        let opts = WeatherOpts {
+            city_name: Some("Astoria".into()),
+            ..WeatherOpts::default()
+        };
+        match opts.resolve_location("", &geocoders).await? {
+            WeatherLocation::CityName(name) => assert_eq!(name, "Astoria"),
+            loc => panic!("unexpected location {:?}", loc),
+        }
+
+        // Plus Codes need no request
+        let opts = WeatherOpts {
+            city_name: Some("87G8Q2PQ+2X".into()),
+            ..WeatherOpts::default()
+        };
+        let geocoders = opts.get_geocoders(&WeatherApi::default());
+        assert!(matches!(
+            opts.resolve_location("", &geocoders).await?,
+            WeatherLocation::LatLon { .. }
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_here() -> Result<(), Error> {
+        let body = r#"{"status":"success","country":"United States","lat":40.7128,"lon":-74.006}"#;
+        let (url, handle) = serve(vec![http_response("200 OK", &[], body)]).await?;
+        let opts = WeatherOpts {
+            here: true,
+            zipcode: Some(11106),
+            ..WeatherOpts::default()
+        };
+
+        match opts
+            .resolve_location(&format!("{}/json", url), &Geocoders::new())
+            .await?
+        {
+            WeatherLocation::LatLon {
+                latitude,
+                longitude,
+            } => {
+                assert_eq!(f64::from(latitude), 40.7128);
+                assert_eq!(f64::from(longitude), -74.006);
+            }
+            loc => panic!("unexpected location {:?}", loc),
+        }
+        assert!(handle.await?[0].starts_with("GET /json"));
+
+        // Nothing is listening here, fall back to the zipcode
+        match opts
+            .resolve_location("http://127.0.0.1:1/json", &Geocoders::new())
+            .await?
+        {
+            WeatherLocation::ZipCode { zipcode, .. } => assert_eq!(zipcode, 11106),
+            loc => panic!("unexpected location {:?}", loc),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_offline_here() -> Result<(), Error> {
+        let body = r#"{"status":"success","country":"United States","lat":40.7128,"lon":-74.006}"#;
+        let (url, handle) = serve(vec![http_response("200 OK", &[], body)]).await?;
+        let opts = WeatherOpts {
+            here: true,
+            offline: true,
+            zipcode: Some(11106),
+            ..WeatherOpts::default()
+        };
+        match opts
+            .resolve_location(&format!("{}/json", url), &Geocoders::new())
+            .await?
+        {
+            WeatherLocation::ZipCode { zipcode, .. } => assert_eq!(zipcode, 11106),
+            loc => panic!("unexpected location {:?}", loc),
+        }
+        // no request was made
+        assert!(timeout(Duration::from_millis(100), handle).await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_check() -> Result<(), Error> {
+        let data = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        let mut body = serde_json::to_value(&data)?;
+        body["main"]["round_trip_field"] = 42.into();
+        let (endpoint, _) = serve(vec![http_response("200 OK", &[], &body.to_string())]).await?;
+        let config = Config::from(ConfigInner {
+            api_endpoint: Some(endpoint),
+            ..ConfigInner::default()
+        });
+        let opts = WeatherOpts {
+            api_key: Some("0123456789abcdef".into()),
+            round_trip_check: true,
+            ..WeatherOpts::default()
+        };
+        let api = opts.get_api(&config)?;
+
+        captured_logs();
+        api.get_weather_data(&WeatherLocation::from_zipcode(11106))
+            .await?;
+        let logs = captured_logs();
+        assert!(
+            logs.contains(
+                &"round trip check: weather response has unknown keys: main.round_trip_field"
+                    .to_string()
+            ),
+            "{:?}",
+            logs
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_dir() {
+        let config = Config::default();
+        assert_eq!(WeatherOpts::default().get_cache_dir(&config), None);
+        for opts in &[
+            WeatherOpts {
+                cache: true,
+                ..WeatherOpts::default()
+            },
+            WeatherOpts {
+                offline: true,
+                ..WeatherOpts::default()
+            },
+        ] {
+            assert_eq!(opts.get_cache_dir(&config), DiskCache::default_directory());
+        }
+    }
+
+    #[test]
+    fn test_watch_interval() -> Result<(), Error> {
+        assert_eq!(WeatherOpts::default().get_watch_interval()?, None);
+        let opts = WeatherOpts {
+            watch: Some(60),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(opts.get_watch_interval()?, Some(60));
+        let opts = WeatherOpts {
+            watch: Some(0),
+            ..WeatherOpts::default()
+        };
+        assert_eq!(
+            opts.get_watch_interval().unwrap_err().to_string(),
+            "--watch must be at least 10 seconds, got 0"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_run_hook() -> Result<(), Error> {
+        let path = temp_dir().join(format!("weather_util_hook_{}", std::process::id()));
+        let command = format!("printf \"$WEATHER_CONDITION\" > {}", path.display());
+        WeatherOpts::run_hook(&command, &[("WEATHER_CONDITION", "Rain".into())]).await;
+        assert_eq!(fs::read_to_string(&path)?, "Rain");
+        fs::remove_file(&path)?;
+        Ok(())
     }
 }
@@ -0,0 +1,276 @@
+use anyhow::{format_err, Error};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use roxmltree::{Document, Node};
+use serde_json::json;
+use std::{collections::HashMap, convert::TryFrom};
+
+use crate::{
+    conditions::Condition,
+    direction::Direction,
+    distance::Distance,
+    humidity::Humidity,
+    latitude::Latitude,
+    longitude::Longitude,
+    precipitation::Precipitation,
+    pressure::Pressure,
+    speed::Speed,
+    temperature::Temperature,
+    timezone::TimeZone,
+    weather_data::{Coord, Rain, Snow, Sys, WeatherCond, WeatherData, WeatherMain, Wind},
+};
+
+/// Value of `base` for conditions parsed from XML, which doesn't report the
+/// source of the data
+pub const XML_BASE: &str = "xml";
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn element<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Result<Node<'a, 'i>, Error> {
+    child(node, name).ok_or_else(|| format_err!("missing element <{}>", name))
+}
+
+fn attribute<'a>(node: Node<'a, '_>, name: &str) -> Result<&'a str, Error> {
+    node.attribute(name)
+        .ok_or_else(|| format_err!("missing attribute {} of <{}>", name, node.tag_name().name()))
+}
+
+fn number(node: Node, name: &str) -> Result<f64, Error> {
+    attribute(node, name)?
+        .parse()
+        .map_err(|e| format_err!("invalid {} of <{}>: {}", name, node.tag_name().name(), e))
+}
+
+/// Times are UTC without an offset, e.g. "2020-01-19T18:23:42"
+fn time(node: Node, name: &str) -> Result<DateTime<Utc>, Error> {
+    let value = attribute(node, name)?;
+    Ok(NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")?.and_utc())
+}
+
+/// Temperatures are in kelvin unless the request set `units`
+fn temperature(node: Node, name: &str) -> Result<Temperature, Error> {
+    let value = number(node, name)?;
+    match node.attribute("unit") {
+        Some("celsius") | Some("metric") => Temperature::from_celcius(value),
+        Some("fahrenheit") | Some("imperial") => Temperature::from_fahrenheit(value),
+        _ => Temperature::try_from(value),
+    }
+}
+
+/// Group of a condition code, the `main` field of the JSON response
+fn condition_group(id: u32) -> String {
+    match id / 100 {
+        8 if id > 800 => "Clouds".into(),
+        _ => Condition::find(id).map_or("", |c| c.name).into(),
+    }
+}
+
+impl WeatherData {
+    /// Parse the body of a `mode=xml` current weather response, for
+    /// environments where the JSON responses get mangled
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_data::WeatherData;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/weather.xml")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let data = WeatherData::from_xml(&buf)?;
+    /// assert_eq!(data.name, "Astoria");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_xml(text: &str) -> Result<Self, Error> {
+        Self::parse_xml(text).map_err(|e| format_err!("Failed to parse current conditions: {}", e))
+    }
+
+    fn parse_xml(text: &str) -> Result<Self, Error> {
+        let doc = Document::parse(text)?;
+        let current = doc.root_element();
+        if !current.has_tag_name("current") {
+            return Err(format_err!(
+                "unexpected root element <{}>",
+                current.tag_name().name()
+            ));
+        }
+        let city = element(current, "city")?;
+        let coord = element(city, "coord")?;
+        let sun = element(city, "sun")?;
+        let temp = element(current, "temperature")?;
+        let wind = element(current, "wind")?;
+
+        let weather = current
+            .children()
+            .filter(|n| n.has_tag_name("weather"))
+            .map(|n| {
+                let id = number(n, "number")? as u32;
+                Ok(WeatherCond {
+                    id,
+                    main: condition_group(id),
+                    description: attribute(n, "value")?.into(),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        let main = WeatherMain {
+            temp: temperature(temp, "value")?,
+            feels_like: temperature(element(current, "feels_like")?, "value")?,
+            temp_min: temperature(temp, "min")?,
+            temp_max: temperature(temp, "max")?,
+            pressure: Pressure::from_hpa(number(element(current, "pressure")?, "value")?)?,
+            humidity: Humidity::try_from(number(element(current, "humidity")?, "value")? as i64)?,
+        };
+        let speed = match child(wind, "speed") {
+            Some(speed) => {
+                let value = number(speed, "value")?;
+                match speed.attribute("unit") {
+                    Some("mph") => Speed::from_mph(value)?,
+                    _ => Speed::from_mps(value)?,
+                }
+            }
+            None => Speed::default(),
+        };
+        let deg = child(wind, "direction")
+            .and_then(|n| n.attribute("value"))
+            .and_then(|value| value.parse().ok())
+            .map(Direction::from_deg);
+
+        let (mut rain, mut snow) = (None, None);
+        if let Some(precipitation) = child(current, "precipitation") {
+            let hours = precipitation.attribute("unit");
+            let amount = precipitation
+                .attribute("value")
+                .map(|_| number(precipitation, "value").and_then(Precipitation::from_millimeters))
+                .transpose()?;
+            let (one_hour, three_hour) = if hours == Some("3h") {
+                (None, amount)
+            } else {
+                (amount, None)
+            };
+            match precipitation.attribute("mode") {
+                Some("rain") => {
+                    rain = Some(Rain {
+                        three_hour,
+                        one_hour,
+                    })
+                }
+                Some("snow") => {
+                    snow = Some(Snow {
+                        three_hour,
+                        one_hour,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        let mut extra = HashMap::new();
+        if let Some(id) = city.attribute("id").and_then(|id| id.parse::<u64>().ok()) {
+            extra.insert("id".into(), json!(id));
+        }
+        if let Some(clouds) = child(current, "clouds").and_then(|n| n.attribute("value")) {
+            extra.insert("clouds".into(), json!({"all": clouds.parse::<u64>()?}));
+        }
+
+        Ok(Self {
+            coord: Coord {
+                lon: Longitude::try_from(number(coord, "lon")?)?,
+                lat: Latitude::try_from(number(coord, "lat")?)?,
+            },
+            weather,
+            base: XML_BASE.into(),
+            main,
+            visibility: match child(current, "visibility") {
+                Some(n) => Some(Distance::from_meters(number(n, "value")?)?),
+                None => None,
+            },
+            wind: Wind { speed, deg },
+            rain,
+            snow,
+            dt: time(element(current, "lastupdate")?, "value")?,
+            sys: Sys {
+                country: child(city, "country")
+                    .and_then(|n| n.text())
+                    .map(String::from),
+                sunrise: time(sun, "rise")?,
+                sunset: time(sun, "set")?,
+            },
+            timezone: match child(city, "timezone").and_then(|n| n.text()) {
+                Some(offset) => TimeZone::try_from(offset.trim().parse::<i32>()?)?,
+                None => TimeZone::try_from(0)?,
+            },
+            name: attribute(city, "name")?.into(),
+            elevation: None,
+            extra,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+
+    use crate::{display_opts::DisplayOpts, weather_data::WeatherData, weather_xml::XML_BASE};
+
+    #[test]
+    fn test_from_xml() -> Result<(), Error> {
+        let xml = WeatherData::from_xml(include_str!("../tests/weather.xml"))?;
+        let json = WeatherData::from_json(include_str!("../tests/weather.json"))?;
+        assert_eq!(xml.name, json.name);
+        assert_eq!(xml.dt, json.dt);
+        assert_eq!(xml.sys.sunrise, json.sys.sunrise);
+        assert_eq!(xml.sys.sunset, json.sys.sunset);
+        assert_eq!(xml.sys.country, json.sys.country);
+        assert_eq!(xml.timezone, json.timezone);
+        assert_eq!(xml.main.temp, json.main.temp);
+        assert_eq!(xml.main.temp_max, json.main.temp_max);
+        assert_eq!(xml.main.feels_like, json.main.feels_like);
+        assert_eq!(xml.main.humidity, json.main.humidity);
+        assert_eq!(xml.wind.speed, json.wind.speed);
+        assert_eq!(xml.visibility, json.visibility);
+        assert_eq!(xml.weather[0].id, 800);
+        assert_eq!(xml.weather[0].main, json.weather[0].main);
+        assert_eq!(xml.weather[0].description, json.weather[0].description);
+        assert_eq!(xml.base, XML_BASE);
+        assert_eq!(xml.extra["clouds"]["all"], 1);
+        let snow = xml.snow.as_ref().and_then(|snow| snow.one_hour).unwrap();
+        assert_eq!(snow.millimeters(), 0.25);
+        assert!(xml.rain.is_none());
+
+        let mut buf = Vec::new();
+        xml.get_current_conditions(&mut buf, &DisplayOpts::default())?;
+        let buf = String::from_utf8(buf)?;
+        assert!(buf.contains("Wind: 310 degrees at 16.1 mph"), "{}", buf);
+        assert!(
+            buf.contains("Last Updated 2020-01-19 13:23:42 -05:00"),
+            "{}",
+            buf
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_errors() {
+        let e = WeatherData::from_xml("<current><city").unwrap_err();
+        assert!(
+            e.to_string()
+                .starts_with("Failed to parse current conditions: "),
+            "{}",
+            e
+        );
+        let e = WeatherData::from_xml(r#"<current><city name="Astoria"/></current>"#)
+            .unwrap_err()
+            .to_string();
+        assert!(e.ends_with("missing element <coord>"), "{}", e);
+        let e = WeatherData::from_xml("<ClientError><cod>401</cod></ClientError>")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            e.ends_with("unexpected root element <ClientError>"),
+            "{}",
+            e
+        );
+    }
+}
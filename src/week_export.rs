@@ -0,0 +1,141 @@
+use anyhow::Error;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::{conditions::Condition, forecast_summary::ForecastSummary};
+
+/// Version of the `--export` structure, bump this whenever a field is
+/// renamed, removed or changes meaning.  Adding a field doesn't require a
+/// new version.
+pub const EXPORT_SCHEMA_VERSION: u32 = 1;
+
+/// Temperatures are rounded to hundredths of a degree
+fn round_temperature(celcius: f64) -> f64 {
+    (celcius * 100.0).round() / 100.0
+}
+
+/// A single day of the export, temperatures are always in degrees Celcius
+/// regardless of the display units
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportDay {
+    /// Local date of the forecast location, e.g. "2020-01-19"
+    pub date: NaiveDate,
+    pub high: f64,
+    pub low: f64,
+    /// `null` if the forecast has no temperatures to average
+    pub average: Option<f64>,
+    /// Highest probability of precipitation (0.0 to 1.0), `null` if unknown
+    pub pop: Option<f64>,
+    /// Name of the most severe condition, e.g. "Rain"
+    pub condition: Option<String>,
+    /// Emoji for `condition`
+    pub icon: Option<String>,
+}
+
+/// Document written by `--export`: the daily summary of the forecast,
+/// meant to be consumed by dashboards.  Unlike `--json` this doesn't follow
+/// the api responses, every field is always present.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WeekExport {
+    pub schema_version: u32,
+    pub days: Vec<ExportDay>,
+}
+
+impl From<&ForecastSummary> for WeekExport {
+    fn from(summary: &ForecastSummary) -> Self {
+        let days = summary
+            .days
+            .iter()
+            .map(|day| {
+                let condition = day.condition.and_then(Condition::find);
+                ExportDay {
+                    date: day.date,
+                    high: round_temperature(day.high.celcius()),
+                    low: round_temperature(day.low.celcius()),
+                    average: day.average.map(|t| round_temperature(t.celcius())),
+                    pop: day.pop,
+                    condition: condition.map(|c| c.name.to_string()),
+                    icon: condition.map(|c| c.icon.to_string()),
+                }
+            })
+            .collect();
+        Self {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            days,
+        }
+    }
+}
+
+impl WeekExport {
+    /// Write out the export as pretty printed JSON
+    /// ```
+    /// # use anyhow::Error;
+    /// # use std::io::Read;
+    /// # use std::fs::File;
+    /// use weather_util_rust::weather_forecast::WeatherForecast;
+    /// use weather_util_rust::week_export::WeekExport;
+    /// # fn main() -> Result<(), Error> {
+    /// # let mut buf = String::new();
+    /// # let mut f = File::open("tests/forecast.json")?;
+    /// # f.read_to_string(&mut buf)?;
+    /// let forecast = WeatherForecast::from_json(&buf)?;
+    /// let export = WeekExport::from(&forecast.get_forecast_summary());
+    ///
+    /// let mut buf = Vec::new();
+    /// export.write_json(&mut buf)?;
+    ///
+    /// let buf = String::from_utf8(buf)?;
+    /// assert!(buf.starts_with("{\n  \"schema_version\": 1,\n  \"days\": ["));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_json<T: Write>(&self, buf: &mut T) -> Result<(), Error> {
+        serde_json::to_writer_pretty(&mut *buf, self)?;
+        writeln!(buf)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Error;
+    use serde_json::Value;
+
+    use crate::{
+        weather_forecast::WeatherForecast,
+        week_export::{WeekExport, EXPORT_SCHEMA_VERSION},
+    };
+
+    #[test]
+    fn test_week_export() -> Result<(), Error> {
+        let forecast = WeatherForecast::from_json(include_str!("../tests/forecast.json"))?;
+        let summary = forecast.get_forecast_summary();
+        let export = WeekExport::from(&summary);
+        assert_eq!(export.days.len(), summary.days.len());
+
+        let mut buf = Vec::new();
+        export.write_json(&mut buf)?;
+        let value: Value = serde_json::from_slice(&buf)?;
+        assert_eq!(value["schema_version"], EXPORT_SCHEMA_VERSION);
+        let days = value["days"].as_array().unwrap();
+        assert_eq!(days.len(), 6);
+        for day in days {
+            let mut keys: Vec<_> = day.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            assert_eq!(
+                keys,
+                vec!["average", "condition", "date", "high", "icon", "low", "pop"]
+            );
+            assert!(day["high"].as_f64() >= day["low"].as_f64(), "{}", day);
+        }
+        assert_eq!(days[0]["date"], "2020-01-19");
+        assert_eq!(days[0]["high"].as_f64(), Some(3.15));
+        assert_eq!(days[2]["average"].as_f64(), Some(-3.64));
+        assert!(days[0]["condition"].is_string(), "{}", days[0]);
+
+        let parsed: WeekExport = serde_json::from_slice(&buf)?;
+        assert_eq!(parsed, export);
+        Ok(())
+    }
+}